@@ -0,0 +1,165 @@
+use syn::{Attribute, Error, ExprPath, Field, Generics, Ident, LitStr, Result, Variant, parse_quote};
+use syn::ext::IdentExt;
+
+pub (crate) enum Tagging {
+    External,
+    Internal { tag: String },
+    Adjacent { tag: String, content: String },
+    Untagged
+}
+
+pub (crate) struct ContainerAttributes {
+    pub (crate) tagging: Tagging
+}
+
+impl ContainerAttributes {
+    pub (crate) fn parse(attributes: &[Attribute]) -> Result<Self> {
+        let mut tag = None;
+        let mut content = None;
+        let mut untagged = false;
+
+        for attribute in json_attributes(attributes) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("content") {
+                    content = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("untagged") {
+                    untagged = true;
+                } else {
+                    return Err(meta.error("Unknown container attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let tagging = match (tag, content, untagged) {
+            (None, None, false) => Tagging::External,
+            (Some(tag), None, false) => Tagging::Internal { tag },
+            (Some(tag), Some(content), false) => Tagging::Adjacent { tag, content },
+            (None, None, true) => Tagging::Untagged,
+            (None, Some(_), _) => {
+                return Err(Error::new(proc_macro2::Span::call_site(),
+                                      "content requires a tag attribute"));
+            }
+            (_, _, true) => {
+                return Err(Error::new(proc_macro2::Span::call_site(),
+                                      "untagged can't be used together with tag"));
+            }
+        };
+
+        Ok(Self { tagging })
+    }
+}
+
+pub (crate) enum DefaultValue {
+    Trait,
+    Function(ExprPath)
+}
+
+pub (crate) struct FieldAttributes {
+    pub (crate) rename: Option<String>,
+    pub (crate) default: Option<DefaultValue>,
+    pub (crate) skip: bool,
+    pub (crate) flatten: bool
+}
+
+impl FieldAttributes {
+    pub (crate) fn parse(field: &Field) -> Result<Self> {
+        let mut rename = None;
+        let mut default = None;
+        let mut skip = false;
+        let mut flatten = false;
+
+        for attribute in json_attributes(&field.attrs) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    if meta.input.peek(syn::Token![=]) {
+                        let path = meta.value()?.parse::<LitStr>()?.parse::<ExprPath>()?;
+                        default = Some(DefaultValue::Function(path));
+                    } else {
+                        default = Some(DefaultValue::Trait);
+                    }
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    flatten = true;
+                } else {
+                    return Err(meta.error("Unknown field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if flatten && field.ident.is_none() {
+            return Err(Error::new_spanned(field, "Only named fields can be flattened"));
+        }
+
+        Ok(Self { rename, default, skip, flatten })
+    }
+}
+
+pub (crate) struct VariantAttributes {
+    pub (crate) rename: Option<String>
+}
+
+impl VariantAttributes {
+    pub (crate) fn parse(variant: &Variant) -> Result<Self> {
+        let mut rename = None;
+
+        for attribute in json_attributes(&variant.attrs) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    return Err(meta.error("Unknown variant attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(Self { rename })
+    }
+}
+
+// The name of a field or variant in JSON, without the r# of raw identifiers
+pub (crate) fn json_name(ident: &Ident, rename: &Option<String>) -> String {
+    match rename {
+        Some(rename) => rename.clone(),
+        None => ident.unraw().to_string()
+    }
+}
+
+// A single unnamed field stands for the whole value, so there is nothing to skip it for
+pub (crate) fn check_transparent(field: &Field) -> Result<()> {
+    if FieldAttributes::parse(field)?.skip {
+        return Err(Error::new_spanned(field, "The only field of a tuple struct or variant can't be skipped"));
+    }
+
+    Ok(())
+}
+
+// Every type parameter must implement the derived trait as well
+pub (crate) fn add_bounds(generics: &Generics, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+
+    for parameter in generics.type_params_mut() {
+        parameter.bounds.push(bound.clone());
+    }
+
+    generics
+}
+
+pub (crate) fn to_json_bound() -> syn::TypeParamBound {
+    parse_quote!(::rusty_json::ToJson)
+}
+
+pub (crate) fn from_json_bound() -> syn::TypeParamBound {
+    parse_quote!(::rusty_json::FromJson)
+}
+
+fn json_attributes(attributes: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attributes.iter().filter(|attribute| attribute.path().is_ident("json"))
+}
@@ -0,0 +1,302 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Result};
+
+use crate::attributes::{ContainerAttributes, FieldAttributes, VariantAttributes, Tagging,
+                        DefaultValue, json_name, check_transparent, add_bounds, from_json_bound};
+
+pub (crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, from_json_bound());
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => fields_from_value(quote!(Self), &data.fields, quote!(value))?,
+        Data::Enum(data) => {
+            let container = ContainerAttributes::parse(&input.attrs)?;
+            let mut variants = Vec::new();
+
+            for variant in &data.variants {
+                let attributes = VariantAttributes::parse(variant)?;
+                let ident = &variant.ident;
+
+                variants.push((json_name(ident, &attributes.rename), quote!(Self::#ident),
+                               &variant.fields));
+            }
+
+            enum_from_value(&container.tagging, &name.to_string(), &variants)?
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "Unions can't be converted from JSON"))
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rusty_json::FromJson for #name #type_generics #where_clause {
+            fn from_json(value: ::rusty_json::ArrayTypeRef<'_, '_>)
+                    -> ::std::result::Result<Self, ::rusty_json::ConversionError> {
+                #body
+            }
+        }
+    })
+}
+
+fn wrong_type(expected: &str, value: TokenStream) -> TokenStream {
+    quote! {
+        ::std::result::Result::Err(::rusty_json::ConversionError::WrongType {
+            expected: #expected,
+            found: #value.type_name()
+        })
+    }
+}
+
+fn unknown_variant(variant: TokenStream) -> TokenStream {
+    quote! {
+        ::std::result::Result::Err(::rusty_json::ConversionError::UnknownVariant {
+            variant: ::std::string::ToString::to_string(#variant)
+        })
+    }
+}
+
+// An expression that builds the struct or variant from an ArrayTypeRef expression,
+// mirroring the shapes written by ToJson
+fn fields_from_value(path: TokenStream, fields: &Fields, value: TokenStream) -> Result<TokenStream> {
+    match fields {
+        Fields::Named(_) => {
+            let expected_object = wrong_type("object", quote!(other));
+            let initializers = field_initializers(fields)?;
+
+            Ok(quote! {{
+                let object = match #value {
+                    ::rusty_json::ArrayTypeRef::Object(object) => object,
+                    other => return #expected_object
+                };
+                ::std::result::Result::Ok(#path { #(#initializers),* })
+            }})
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            check_transparent(&unnamed.unnamed[0])?;
+            Ok(quote!(::std::result::Result::Ok(#path(::rusty_json::FromJson::from_json(#value)?))))
+        }
+        Fields::Unnamed(_) => {
+            let expected_array = wrong_type("array", quote!(other));
+            let mut elements = Vec::new();
+            let mut length = 0usize;
+
+            for field in fields.iter() {
+                if FieldAttributes::parse(field)?.skip {
+                    elements.push(quote!(::std::default::Default::default()));
+                } else {
+                    elements.push(quote!(::rusty_json::FromJson::from_json(
+                        items.next().expect("Length was checked"))?));
+                    length += 1;
+                }
+            }
+
+            Ok(quote! {{
+                let array = match #value {
+                    ::rusty_json::ArrayTypeRef::Array(array) => array,
+                    other => return #expected_array
+                };
+                if array.len() != #length {
+                    return ::std::result::Result::Err(::rusty_json::ConversionError::WrongLength {
+                        expected: #length,
+                        found: array.len()
+                    });
+                }
+                let mut items = array.iter();
+                ::std::result::Result::Ok(#path(#(#elements),*))
+            }})
+        }
+        Fields::Unit => Ok(quote!(::std::result::Result::Ok(#path)))
+    }
+}
+
+// Field initializers that read from a variable called object
+fn field_initializers(fields: &Fields) -> Result<Vec<TokenStream>> {
+    let mut initializers = Vec::new();
+
+    for field in fields.iter() {
+        let attributes = FieldAttributes::parse(field)?;
+        let ident = field.ident.as_ref().expect("Named field");
+
+        let initializer = if attributes.skip {
+            quote!(::std::default::Default::default())
+        } else if attributes.flatten {
+            quote!(::rusty_json::FromJson::from_object(object)?)
+        } else {
+            let key = json_name(ident, &attributes.rename);
+
+            let missing = match &attributes.default {
+                Some(DefaultValue::Trait) => quote!(::std::default::Default::default()),
+                Some(DefaultValue::Function(path)) => quote!(#path()),
+                None => quote!(::rusty_json::FromJson::from_missing(#key)?)
+            };
+
+            quote! {
+                match object.get(#key) {
+                    ::std::option::Option::Some(value) => ::rusty_json::FromJson::from_json(value)?,
+                    ::std::option::Option::None => #missing
+                }
+            }
+        };
+
+        initializers.push(quote!(#ident: #initializer));
+    }
+
+    Ok(initializers)
+}
+
+fn enum_from_value(tagging: &Tagging, enum_name: &str,
+                   variants: &[(String, TokenStream, &Fields)]) -> Result<TokenStream> {
+    match tagging {
+        Tagging::External => {
+            let mut unit_arms = Vec::new();
+            let mut data_arms = Vec::new();
+
+            for (name, path, fields) in variants {
+                if let Fields::Unit = fields {
+                    unit_arms.push(quote!(#name => ::std::result::Result::Ok(#path)));
+                }
+                let data = fields_from_value(path.clone(), fields, quote!(value))?;
+                data_arms.push(quote!(#name => #data));
+            }
+
+            let unknown_name = unknown_variant(quote!(name));
+            let expected = wrong_type("string or object", quote!(other));
+
+            Ok(quote! {
+                match value {
+                    ::rusty_json::ArrayTypeRef::String(name) => match name.as_str() {
+                        #(#unit_arms,)*
+                        _ => #unknown_name
+                    },
+                    ::rusty_json::ArrayTypeRef::Object(object) if object.len() == 1 => {
+                        let (name, value) = object.iter().next().expect("Object has one member");
+                        match name {
+                            #(#data_arms,)*
+                            _ => #unknown_name
+                        }
+                    }
+                    other => #expected
+                }
+            })
+        }
+        Tagging::Internal { tag } => {
+            let mut arms = Vec::new();
+
+            for (name, path, fields) in variants {
+                let data = match fields {
+                    Fields::Unnamed(unnamed) if unnamed.unnamed.len() > 1 => {
+                        return Err(Error::new_spanned(unnamed,
+                                                      "Internally tagged enums can't have tuple variants"));
+                    }
+                    Fields::Unit => quote!(::std::result::Result::Ok(#path)),
+                    _ => fields_from_value(path.clone(), fields, quote!(value))?
+                };
+                arms.push(quote!(#name => #data));
+            }
+
+            let tag_lookup = tag_lookup(tag);
+            let unknown_name = unknown_variant(quote!(name));
+
+            Ok(quote! {
+                #tag_lookup
+                match name.as_str() {
+                    #(#arms,)*
+                    _ => #unknown_name
+                }
+            })
+        }
+        Tagging::Adjacent { tag, content } => {
+            let mut arms = Vec::new();
+
+            for (name, path, fields) in variants {
+                let data = if let Fields::Unit = fields {
+                    quote!(::std::result::Result::Ok(#path))
+                } else {
+                    let data = fields_from_value(path.clone(), fields, quote!(value))?;
+
+                    quote! {{
+                        let value = match object.get(#content) {
+                            ::std::option::Option::Some(value) => value,
+                            ::std::option::Option::None => {
+                                return ::std::result::Result::Err(
+                                    ::rusty_json::ConversionError::MissingField {
+                                        field: ::std::string::String::from(#content)
+                                    });
+                            }
+                        };
+                        #data
+                    }}
+                };
+                arms.push(quote!(#name => #data));
+            }
+
+            let tag_lookup = tag_lookup(tag);
+            let unknown_name = unknown_variant(quote!(name));
+
+            Ok(quote! {
+                #tag_lookup
+                match name.as_str() {
+                    #(#arms,)*
+                    _ => #unknown_name
+                }
+            })
+        }
+        Tagging::Untagged => {
+            let mut attempts = Vec::new();
+
+            for (_, path, fields) in variants {
+                let data = fields_from_value(path.clone(), fields, quote!(value))?;
+
+                let attempt = if let Fields::Unit = fields {
+                    quote! {
+                        if let ::rusty_json::ArrayTypeRef::Null(_) = value {
+                            return ::std::result::Result::Ok(#path);
+                        }
+                    }
+                } else {
+                    quote! {
+                        let attempt = || -> ::std::result::Result<Self, ::rusty_json::ConversionError> {
+                            #data
+                        };
+                        if let ::std::result::Result::Ok(result) = attempt() {
+                            return ::std::result::Result::Ok(result);
+                        }
+                    }
+                };
+                attempts.push(attempt);
+            }
+
+            let message = format!("untagged {}", enum_name);
+            let unknown = unknown_variant(quote!(#message));
+
+            Ok(quote! {
+                #(#attempts)*
+                #unknown
+            })
+        }
+    }
+}
+
+// Binds object to the tagged object and name to the variant name found in it
+fn tag_lookup(tag: &str) -> TokenStream {
+    let expected_object = wrong_type("object", quote!(other));
+    let expected_string = wrong_type("string", quote!(other));
+
+    quote! {
+        let object = match value {
+            ::rusty_json::ArrayTypeRef::Object(object) => object,
+            other => return #expected_object
+        };
+        let name = match object.get(#tag) {
+            ::std::option::Option::Some(::rusty_json::ArrayTypeRef::String(name)) => name,
+            ::std::option::Option::Some(other) => return #expected_string,
+            ::std::option::Option::None => {
+                return ::std::result::Result::Err(::rusty_json::ConversionError::MissingField {
+                    field: ::std::string::String::from(#tag)
+                });
+            }
+        };
+        let value = ::rusty_json::ArrayTypeRef::Object(object);
+    }
+}
@@ -0,0 +1,44 @@
+// Derive macros for the ToJson and FromJson traits of rusty_json
+//
+// Supported attributes:
+//
+// #[json(tag = "type")]                       on enums, internally tagged
+// #[json(tag = "type", content = "data")]     on enums, adjacently tagged
+// #[json(untagged)]                           on enums, no tag at all
+// #[json(rename = "name")]                    on fields and variants
+// #[json(default)]                            on fields, Default::default() when missing
+// #[json(default = "path::to::function")]     on fields, function() when missing
+// #[json(skip)]                               on fields, never written, always defaulted
+// #[json(flatten)]                            on fields, members are merged into the parent object
+//
+// Enums without a tag attribute are externally tagged: unit variants are
+// strings and the others are objects with the variant name as the only key.
+
+extern crate proc_macro;
+
+mod attributes;
+mod to_json;
+mod from_json;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match to_json::expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into()
+    }
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match from_json::expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into()
+    }
+}
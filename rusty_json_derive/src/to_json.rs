@@ -0,0 +1,224 @@
+use proc_macro2::TokenStream;
+use quote::{quote, format_ident};
+use syn::{Data, DeriveInput, Error, Fields, Result};
+
+use crate::attributes::{ContainerAttributes, FieldAttributes, VariantAttributes, Tagging,
+                        json_name, check_transparent, add_bounds, to_json_bound};
+
+pub (crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, to_json_bound());
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let accesses = field_accesses(&data.fields, |member| quote!(&self.#member));
+            fields_value(&data.fields, &accesses)?
+        }
+        Data::Enum(data) => {
+            let container = ContainerAttributes::parse(&input.attrs)?;
+            let mut arms = Vec::new();
+
+            for variant in &data.variants {
+                let attributes = VariantAttributes::parse(variant)?;
+                let variant_name = json_name(&variant.ident, &attributes.rename);
+                let ident = &variant.ident;
+
+                let bindings: Vec<_> = (0..variant.fields.len())
+                    .map(|i| format_ident!("__field{}", i))
+                    .collect();
+                let accesses: Vec<_> = bindings.iter().map(|binding| quote!(#binding)).collect();
+
+                let pattern = match &variant.fields {
+                    Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|field| &field.ident);
+                        quote!(Self::#ident { #(#names: #bindings),* })
+                    }
+                    Fields::Unnamed(_) => quote!(Self::#ident(#(#bindings),*)),
+                    Fields::Unit => quote!(Self::#ident)
+                };
+
+                let value = variant_value(&container.tagging, &variant_name, &variant.fields,
+                                          &accesses)?;
+
+                arms.push(quote!(#pattern => #value));
+            }
+
+            quote! {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "Unions can't be converted to JSON"))
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rusty_json::ToJson for #name #type_generics #where_clause {
+            fn to_json(&self) -> ::rusty_json::ArrayType<'static> {
+                #body
+            }
+        }
+    })
+}
+
+fn field_accesses<F>(fields: &Fields, access: F) -> Vec<TokenStream>
+where
+    F: Fn(TokenStream) -> TokenStream
+{
+    fields.iter().enumerate().map(|(i, field)| {
+        match &field.ident {
+            Some(ident) => access(quote!(#ident)),
+            None => {
+                let index = syn::Index::from(i);
+                access(quote!(#index))
+            }
+        }
+    }).collect()
+}
+
+// Named fields become an object, a single unnamed field is transparent,
+// more unnamed fields become an array and no fields become null
+fn fields_value(fields: &Fields, accesses: &[TokenStream]) -> Result<TokenStream> {
+    match fields {
+        Fields::Named(_) => {
+            let members = object_members(fields, accesses)?;
+
+            Ok(quote! {{
+                let mut object = ::rusty_json::JsonObject::new();
+                #(#members)*
+                ::rusty_json::ArrayType::Object(object)
+            }})
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            check_transparent(&unnamed.unnamed[0])?;
+
+            let access = &accesses[0];
+            Ok(quote!(::rusty_json::ToJson::to_json(#access)))
+        }
+        Fields::Unnamed(_) => {
+            let mut items = Vec::new();
+
+            for (field, access) in fields.iter().zip(accesses) {
+                if !FieldAttributes::parse(field)?.skip {
+                    items.push(quote!(array.add(::rusty_json::ToJson::to_json(#access));));
+                }
+            }
+
+            Ok(quote! {{
+                let mut array = ::rusty_json::JsonArray::new();
+                #(#items)*
+                ::rusty_json::ArrayType::Array(array)
+            }})
+        }
+        Fields::Unit => Ok(quote!(::rusty_json::ArrayType::Null(::rusty_json::Null)))
+    }
+}
+
+// Statements that insert every field into a variable called object
+fn object_members(fields: &Fields, accesses: &[TokenStream]) -> Result<Vec<TokenStream>> {
+    let mut members = Vec::new();
+
+    for (field, access) in fields.iter().zip(accesses) {
+        let attributes = FieldAttributes::parse(field)?;
+
+        if attributes.skip {
+            continue;
+        }
+
+        if attributes.flatten {
+            members.push(quote! {
+                if let ::rusty_json::ArrayType::Object(inner) = ::rusty_json::ToJson::to_json(#access) {
+                    for (key, value) in inner {
+                        object.insert(key, value);
+                    }
+                }
+            });
+        } else {
+            let key = json_name(field.ident.as_ref().expect("Named field"), &attributes.rename);
+            members.push(quote! {
+                object.insert(#key, ::rusty_json::ToJson::to_json(#access));
+            });
+        }
+    }
+
+    Ok(members)
+}
+
+fn variant_value(tagging: &Tagging, variant_name: &str, fields: &Fields,
+                 accesses: &[TokenStream]) -> Result<TokenStream> {
+    let string = quote!(::rusty_json::ArrayType::String(::std::string::String::from(#variant_name)));
+
+    let value = match tagging {
+        Tagging::External => {
+            if let Fields::Unit = fields {
+                string
+            } else {
+                let data = fields_value(fields, accesses)?;
+
+                quote! {{
+                    let mut object = ::rusty_json::JsonObject::new();
+                    object.insert(#variant_name, #data);
+                    ::rusty_json::ArrayType::Object(object)
+                }}
+            }
+        }
+        Tagging::Internal { tag } => {
+            match fields {
+                Fields::Named(_) => {
+                    let members = object_members(fields, accesses)?;
+
+                    quote! {{
+                        let mut object = ::rusty_json::JsonObject::new();
+                        object.insert(#tag, #string);
+                        #(#members)*
+                        ::rusty_json::ArrayType::Object(object)
+                    }}
+                }
+                Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                    let access = &accesses[0];
+
+                    // Only objects can carry the tag, anything else is written as it is
+                    quote! {
+                        match ::rusty_json::ToJson::to_json(#access) {
+                            ::rusty_json::ArrayType::Object(mut object) => {
+                                object.insert(#tag, #string);
+                                ::rusty_json::ArrayType::Object(object)
+                            }
+                            other => other
+                        }
+                    }
+                }
+                Fields::Unnamed(unnamed) => {
+                    return Err(Error::new_spanned(unnamed,
+                                                  "Internally tagged enums can't have tuple variants"));
+                }
+                Fields::Unit => {
+                    quote! {{
+                        let mut object = ::rusty_json::JsonObject::new();
+                        object.insert(#tag, #string);
+                        ::rusty_json::ArrayType::Object(object)
+                    }}
+                }
+            }
+        }
+        Tagging::Adjacent { tag, content } => {
+            let content_member = if let Fields::Unit = fields {
+                quote!()
+            } else {
+                let data = fields_value(fields, accesses)?;
+                quote!(object.insert(#content, #data);)
+            };
+
+            quote! {{
+                let mut object = ::rusty_json::JsonObject::new();
+                object.insert(#tag, #string);
+                #content_member
+                ::rusty_json::ArrayType::Object(object)
+            }}
+        }
+        Tagging::Untagged => fields_value(fields, accesses)?
+    };
+
+    Ok(value)
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use rusty_json::{ToJson, FromJson, ArrayType, ArrayTypeRef, ConversionError, load_str, json};
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Thing {
+    name: String,
+    #[json(rename = "type")]
+    kind: Kind
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+enum Kind {
+    Instrument,
+    Toy { fun: u8 },
+    Other(String),
+    Pair(i32, i32)
+}
+
+#[derive(Debug, PartialEq, Default, ToJson, FromJson)]
+struct Metadata {
+    created: String,
+    version: u32
+}
+
+fn default_age() -> u32 {
+    18
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Person {
+    name: String,
+    #[json(default = "default_age")]
+    age: u32,
+    #[json(default)]
+    friends: Vec<String>,
+    nickname: Option<String>,
+    things: Vec<Thing>,
+    #[json(skip)]
+    cache: HashMap<String, i32>,
+    #[json(flatten)]
+    metadata: Metadata
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(tag = "type")]
+enum Shape {
+    Circle { radius: f32 },
+    Square(Metadata),
+    #[json(rename = "nothing")]
+    Empty
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(tag = "t", content = "c")]
+enum Message {
+    Quit,
+    Move(i32, i32),
+    Write(String)
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(untagged)]
+enum Setting {
+    Flag(bool),
+    Number(i32),
+    Named { name: String },
+    Unset
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Wrapper<T>(T);
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Reference {
+    r#type: String,
+    r#ref: u8
+}
+
+#[test]
+fn structs() {
+    let object = load_str(String::from(r#"{
+        "name": "Simon",
+        "nickname": null,
+        "things": [
+            { "name": "guitar", "type": "Instrument" },
+            { "name": "Rubik's Cube", "type": { "Toy": { "fun": 10 } } },
+            { "name": "rock", "type": { "Pair": [1, 2] } }
+        ],
+        "cache": { "ignored": 1 },
+        "created": "today",
+        "version": 2
+    }"#)).expect("Couldn't load");
+
+    let person = Person::from_object(&object).expect("Conversion");
+
+    assert_eq!(person, Person {
+        name: String::from("Simon"),
+        age: 18,
+        friends: Vec::new(),
+        nickname: None,
+        things: vec![
+            Thing { name: String::from("guitar"), kind: Kind::Instrument },
+            Thing { name: String::from("Rubik's Cube"), kind: Kind::Toy { fun: 10 } },
+            Thing { name: String::from("rock"), kind: Kind::Pair(1, 2) }
+        ],
+        cache: HashMap::new(),
+        metadata: Metadata { created: String::from("today"), version: 2 }
+    });
+
+    let value = person.to_json();
+    if let ArrayType::Object(object) = &value {
+        assert!(object.get("cache").is_none());
+        assert_eq!(object.get_string("created").expect("Is none"), "today");
+        assert_eq!(object.get_int("age").expect("Is none"), 18);
+    } else {
+        panic!("Not an object");
+    }
+    assert_eq!(Person::from_json(value.to_ref()).expect("Conversion"), person);
+
+    let error = Person::from_object(&json!({ "name": "Simon" })).unwrap_err();
+    assert!(matches!(error, ConversionError::MissingField { field } if field == "things"));
+}
+
+#[test]
+fn enums() {
+    let shapes = vec![
+        Shape::Circle { radius: 1.5 },
+        Shape::Square(Metadata { created: String::from("now"), version: 1 }),
+        Shape::Empty
+    ];
+    let value = shapes.to_json();
+    assert_eq!(value, ArrayType::Array(json!([
+        { "type": "Circle", "radius": 1.5 },
+        { "type": "Square", "created": "now", "version": 1 },
+        { "type": "nothing" }
+    ])));
+    assert_eq!(Vec::<Shape>::from_json(value.to_ref()).expect("Conversion"), shapes);
+
+    let messages = vec![Message::Quit, Message::Move(1, -1), Message::Write(String::from("hi"))];
+    let value = messages.to_json();
+    assert_eq!(value, ArrayType::Array(json!([
+        { "t": "Quit" },
+        { "t": "Move", "c": [1, -1] },
+        { "t": "Write", "c": "hi" }
+    ])));
+    assert_eq!(Vec::<Message>::from_json(value.to_ref()).expect("Conversion"), messages);
+
+    let settings = vec![
+        Setting::Flag(true),
+        Setting::Number(3),
+        Setting::Named { name: String::from("x") },
+        Setting::Unset
+    ];
+    let value = settings.to_json();
+    assert_eq!(value, ArrayType::Array(json!([true, 3, { "name": "x" }, null])));
+    assert_eq!(Vec::<Setting>::from_json(value.to_ref()).expect("Conversion"), settings);
+
+    assert!(Kind::from_json(ArrayTypeRef::String(&String::from("Unknown"))).is_err());
+    assert_eq!(Wrapper::<i32>::from_json(Wrapper(5).to_json().to_ref()).expect("Conversion"),
+               Wrapper(5));
+
+    // Raw identifiers are written without their r#
+    let reference = Reference { r#type: String::from("link"), r#ref: 1 };
+    let value = reference.to_json();
+    assert_eq!(value, ArrayType::Object(json!({ "type": "link", "ref": 1 })));
+    assert_eq!(Reference::from_json(value.to_ref()).expect("Conversion"), reference);
+}
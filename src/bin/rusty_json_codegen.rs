@@ -0,0 +1,48 @@
+// Prints Rust types for a JSON Schema or a sample document
+//
+// Usage: rusty_json_codegen [--schema | --sample] <file> [RootName]
+//
+// Without a flag, files with a "$schema" member are read as schemas.
+
+use std::env;
+use std::process;
+
+use rusty_json::{load, generate_rust_from_schema, generate_rust_from_sample};
+
+fn main() {
+    let mut arguments: Vec<String> = env::args().skip(1).collect();
+
+    let mode = match arguments.first().map(|argument| argument.as_str()) {
+        Some("--schema") | Some("--sample") => Some(arguments.remove(0)),
+        _ => None
+    };
+
+    let (file, root_name) = match arguments.as_slice() {
+        [file] => (file.clone(), String::from("Root")),
+        [file, root_name] => (file.clone(), root_name.clone()),
+        _ => {
+            eprintln!("Usage: rusty_json_codegen [--schema | --sample] <file> [RootName]");
+            process::exit(2);
+        }
+    };
+
+    let document = match load(file) {
+        Ok(document) => document,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+
+    let is_schema = match mode.as_deref() {
+        Some("--schema") => true,
+        Some(_) => false,
+        None => document.contains_key("$schema")
+    };
+
+    if is_schema {
+        print!("{}", generate_rust_from_schema(&document, &root_name));
+    } else {
+        print!("{}", generate_rust_from_sample(&document, &root_name));
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::data_structure::{JsonObject, ArrayTypeRef};
+use crate::infer::infer_schema;
+
+// Generates Rust structs and enums from a JSON Schema or from a sample document.
+// The types derive ToJson and FromJson, so the generated code needs the "derive"
+// feature. Objects with properties become structs named after their keys, string
+// enums become enums, arrays become Vecs, nullable or optional members become
+// Options and anything that can't be described by one type stays an ArrayType.
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try"
+];
+
+const ANY_VALUE: &str = "ArrayType<'static>";
+
+struct Generator<'s, 'a> {
+    root: &'s JsonObject<'a>,  // For resolving references
+    definitions: Vec<String>,
+    type_names: HashSet<String>,
+    references: HashMap<String, String>,  // Already generated references and their types
+    in_progress: Vec<String>,  // Structs being generated, which need a Box to refer to themselves
+    uses_value: bool,
+    uses_null: bool,
+    uses_map: bool
+}
+
+pub fn generate_rust_from_schema(schema: &JsonObject, root_name: &str) -> String {
+    let mut generator = Generator {
+        root: schema,
+        definitions: Vec::new(),
+        type_names: HashSet::new(),
+        references: HashMap::new(),
+        in_progress: Vec::new(),
+        uses_value: false,
+        uses_null: false,
+        uses_map: false
+    };
+
+    let root_type = generator.rust_type(ArrayTypeRef::Object(schema), root_name);
+
+    // Make sure there is a type with the requested name, even if the root isn't a struct
+    if generator.definitions.is_empty() {
+        let name = generator.unique_type_name(root_name);
+        generator.definitions.push(format!("pub type {} = {};\n", name, root_type));
+    }
+
+    let mut output = String::from("use rusty_json::{ToJson, FromJson};\n");
+    match (generator.uses_value, generator.uses_null) {
+        (true, true) => output.push_str("use rusty_json::{ArrayType, Null};\n"),
+        (true, false) => output.push_str("use rusty_json::ArrayType;\n"),
+        (false, true) => output.push_str("use rusty_json::Null;\n"),
+        (false, false) => ()
+    }
+    if generator.uses_map {
+        output.push_str("use std::collections::HashMap;\n");
+    }
+
+    for definition in &generator.definitions {
+        output.push('\n');
+        output.push_str(definition);
+    }
+
+    output
+}
+
+// The schema of the sample is inferred first, so every member of the sample is required
+pub fn generate_rust_from_sample(sample: &JsonObject, root_name: &str) -> String {
+    generate_rust_from_schema(&infer_schema(std::iter::once(sample)), root_name)
+}
+
+impl<'s, 'a> Generator<'s, 'a> {
+    fn rust_type(&mut self, schema: ArrayTypeRef, name_hint: &str) -> String {
+        let schema = match schema {
+            ArrayTypeRef::Object(schema) => schema,
+            _ => return self.any_value()
+        };
+
+        if let Some(ArrayTypeRef::String(reference)) = schema.get("$ref") {
+            return self.reference(reference);
+        }
+
+        let mut types: Vec<&str> = match schema.get("type") {
+            Some(ArrayTypeRef::String(name)) => vec![name.as_str()],
+            Some(ArrayTypeRef::Array(names)) => names.iter().filter_map(|name| match name {
+                ArrayTypeRef::String(name) => Some(name.as_str()),
+                _ => None
+            }).collect(),
+            _ => implied_types(schema)
+        };
+
+        let nullable = types.contains(&"null");
+        types.retain(|name| *name != "null");
+
+        let rust_type = match types.as_slice() {
+            [] if nullable => {
+                self.uses_null = true;
+                return String::from("Null");
+            }
+            ["string"] => match schema.get("enum") {
+                Some(ArrayTypeRef::Array(values)) => {
+                    let values: Option<Vec<&String>> = values.iter().filter_map(|value| match value {
+                        ArrayTypeRef::String(value) => Some(Some(value)),
+                        ArrayTypeRef::Null(_) => None,
+                        _ => Some(None)
+                    }).collect();
+
+                    match values {
+                        Some(values) if !values.is_empty() => self.enumeration(&values, name_hint),
+                        _ => String::from("String")
+                    }
+                }
+                _ => String::from("String")
+            },
+            ["integer"] => String::from("i32"),
+            ["number"] => String::from("f64"),
+            ["boolean"] => String::from("bool"),
+            ["array"] => match schema.get("items") {
+                Some(items) => format!("Vec<{}>", self.rust_type(items, &singular(name_hint))),
+                None => format!("Vec<{}>", self.any_value())
+            },
+            ["object"] => match (schema.get("properties"), schema.get("additionalProperties")) {
+                (Some(ArrayTypeRef::Object(_)), _) => self.structure(schema, name_hint, None),
+                (_, Some(values @ ArrayTypeRef::Object(_))) => {
+                    self.uses_map = true;
+                    format!("HashMap<String, {}>", self.rust_type(values, &singular(name_hint)))
+                }
+                _ => {
+                    self.uses_map = true;
+                    format!("HashMap<String, {}>", self.any_value())
+                }
+            },
+            _ => self.any_value()
+        };
+
+        if nullable {
+            format!("Option<{}>", rust_type)
+        } else {
+            rust_type
+        }
+    }
+
+    fn any_value(&mut self) -> String {
+        self.uses_value = true;
+        String::from(ANY_VALUE)
+    }
+
+    // Only references inside the schema, like "#/$defs/address", are supported
+    fn reference(&mut self, reference: &str) -> String {
+        if let Some(rust_type) = self.references.get(reference) {
+            if self.in_progress.contains(rust_type) {
+                return format!("Box<{}>", rust_type);
+            }
+            return rust_type.clone();
+        }
+
+        let target = match reference.strip_prefix('#').and_then(|pointer| self.root.pointer(pointer)) {
+            Some(target) => target,
+            None => return self.any_value()
+        };
+        let name_hint = reference.rsplit('/').next().unwrap_or("Type").to_string();
+
+        let is_struct = match target {
+            ArrayTypeRef::Object(schema) => matches!(schema.get("properties"), Some(ArrayTypeRef::Object(_))),
+            _ => false
+        };
+
+        // Structs get their name up front, so that they can refer to themselves
+        if let (true, ArrayTypeRef::Object(schema)) = (is_struct, target) {
+            let name = self.unique_type_name(&name_hint);
+            self.references.insert(reference.to_string(), name.clone());
+
+            return self.structure(schema, &name_hint, Some(name));
+        }
+
+        self.references.insert(reference.to_string(), String::from(ANY_VALUE));
+        let rust_type = self.rust_type(target, &name_hint);
+        self.references.insert(reference.to_string(), rust_type.clone());
+
+        rust_type
+    }
+
+    fn structure(&mut self, schema: &JsonObject, name_hint: &str, name: Option<String>) -> String {
+        let name = name.unwrap_or_else(|| self.unique_type_name(name_hint));
+
+        // Reserve the place of the struct, so that it comes before the types of its fields
+        let index = self.definitions.len();
+        self.definitions.push(String::new());
+        self.in_progress.push(name.clone());
+
+        let required: Vec<&String> = match schema.get("required") {
+            Some(ArrayTypeRef::Array(required)) => required.iter().filter_map(|key| match key {
+                ArrayTypeRef::String(key) => Some(key),
+                _ => None
+            }).collect(),
+            _ => Vec::new()
+        };
+
+        let mut properties: Vec<(&str, ArrayTypeRef)> = match schema.get("properties") {
+            Some(ArrayTypeRef::Object(properties)) => properties.iter().collect(),
+            _ => Vec::new()
+        };
+        properties.sort_by_key(|(key, _)| *key);
+
+        let mut field_names = HashSet::new();
+        let mut fields = Vec::new();
+
+        for (key, property) in properties {
+            let field_name = unique_name(&field_name(key), &mut field_names);
+            let mut rust_type = self.rust_type(property, key);
+
+            let is_required = required.iter().any(|required| required.as_str() == key);
+            if !is_required && !rust_type.starts_with("Option<") {
+                rust_type = format!("Option<{}>", rust_type);
+            }
+
+            let mut field = String::new();
+            if field_name != key {
+                field.push_str(&format!("    #[json(rename = {:?})]\n", key));
+            }
+            field.push_str(&format!("    pub {}: {}", field_name, rust_type));
+            fields.push(field);
+        }
+
+        self.in_progress.pop();
+
+        let body = if fields.is_empty() {
+            String::from("{}")
+        } else {
+            format!("{{\n{}\n}}", fields.join(",\n"))
+        };
+        self.definitions[index] = format!("#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]\n\
+                                           pub struct {} {}\n", name, body);
+
+        name
+    }
+
+    fn enumeration(&mut self, values: &[&String], name_hint: &str) -> String {
+        let name = self.unique_type_name(name_hint);
+
+        let mut variant_names = HashSet::new();
+        let mut variants = Vec::new();
+
+        for value in values {
+            let variant_name = unique_name(&type_name(value, "Value"), &mut variant_names);
+
+            let mut variant = String::new();
+            if &variant_name != *value {
+                variant.push_str(&format!("    #[json(rename = {:?})]\n", value));
+            }
+            variant.push_str(&format!("    {}", variant_name));
+            variants.push(variant);
+        }
+
+        self.definitions.push(format!("#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]\n\
+                                       pub enum {} {{\n{}\n}}\n", name, variants.join(",\n")));
+
+        name
+    }
+
+    fn unique_type_name(&mut self, name_hint: &str) -> String {
+        unique_name(&type_name(name_hint, "Type"), &mut self.type_names)
+    }
+}
+
+// Schemas without a type keyword can still say what they describe
+fn implied_types(schema: &JsonObject) -> Vec<&'static str> {
+    if schema.contains_key("properties") || schema.contains_key("additionalProperties") {
+        vec!["object"]
+    } else if schema.contains_key("items") {
+        vec!["array"]
+    } else if let Some(ArrayTypeRef::Array(values)) = schema.get("enum") {
+        let all_strings = values.iter()
+            .all(|value| matches!(value, ArrayTypeRef::String(_) | ArrayTypeRef::Null(_)));
+        if all_strings {
+            vec!["string"]
+        } else {
+            Vec::new()
+        }
+    } else {
+        Vec::new()
+    }
+}
+
+fn unique_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let mut unique = name.to_string();
+    let mut suffix = 2;
+
+    while taken.contains(&unique) {
+        unique = format!("{}{}", name, suffix);
+        suffix += 1;
+    }
+    taken.insert(unique.clone());
+
+    unique
+}
+
+// Splits keys like "firstName", "first_name" or "first-name" into lowercase words
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lowercase = false;
+
+    for character in key.chars() {
+        if !character.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lowercase = false;
+            continue;
+        }
+
+        if character.is_uppercase() && previous_lowercase && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lowercase = character.is_lowercase() || character.is_numeric();
+        word.extend(character.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn type_name(key: &str, fallback: &str) -> String {
+    let name: String = words(key).iter().map(|word| {
+        let mut characters = word.chars();
+        match characters.next() {
+            Some(first) => first.to_uppercase().chain(characters).collect::<String>(),
+            None => String::new()
+        }
+    }).collect();
+
+    let name: String = name.chars().filter(|character| character.is_ascii_alphanumeric()).collect();
+
+    if name.is_empty() {
+        fallback.to_string()
+    } else if name.starts_with(|character: char| character.is_ascii_digit()) ||
+              KEYWORDS.contains(&name.as_str()) {
+        format!("{}{}", fallback, name)
+    } else {
+        name
+    }
+}
+
+fn field_name(key: &str) -> String {
+    let name: String = words(key).join("_").chars()
+        .filter(|character| character.is_ascii_alphanumeric() || *character == '_')
+        .collect();
+
+    if name.is_empty() {
+        String::from("field")
+    } else if name.starts_with(|character: char| character.is_ascii_digit()) {
+        format!("field_{}", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+// Names array items after their array, like "users" to "user"
+fn singular(name: &str) -> String {
+    if name.len() > 1 && name.ends_with('s') && !name.ends_with("ss") {
+        name[..name.len() - 1].to_string()
+    } else {
+        format!("{}Item", name)
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use crate::data_structure::{JsonObject, JsonArray};
+
+// Comments kept from a JSONC document. Each comment is stored with its delimiters,
+// so "// note" and "/* note */" are written back the way they were read.
+// Comments belong to the member or item that follows them, and so do those inside a
+// member or between an item and its comma; the ones after the last member or item are
+// trailing comments of the object or array. The comments before and after the whole
+// document are kept on its root as header and footer comments.
+#[derive(Debug, Clone, Default)]
+pub (crate) struct Comments<K> {
+    pub (crate) members: HashMap<K, Vec<String>>,  // By key in objects, by index in arrays
+    pub (crate) trailing: Vec<String>,
+    pub (crate) header: Vec<String>,
+    pub (crate) footer: Vec<String>
+}
+
+// The getter and setter of a list of comments that objects and arrays share
+macro_rules! comment_list {
+    ($get:ident, $set:ident, $field:ident) => {
+        pub fn $get(&self) -> &[String] {
+            self.comments.as_ref().map_or(&[], |comments| comments.$field.as_slice())
+        }
+
+        pub fn $set(&mut self, comments: Vec<String>) {
+            if comments.is_empty() {
+                if let Some(existing) = &mut self.comments {
+                    existing.$field.clear();
+                }
+            } else {
+                self.comments.get_or_insert_with(Box::default).$field = comments;
+            }
+        }
+    };
+}
+
+impl<'a> JsonObject<'a> {
+    // Comments stay with their key when the value is replaced or deleted,
+    // so that they are written back if the key is inserted again
+    pub fn comments(&self, key: &str) -> &[String] {
+        self.comments.as_ref()
+            .and_then(|comments| comments.members.get(key))
+            .map_or(&[], |comments| comments.as_slice())
+    }
+
+    pub fn set_comments(&mut self, key: impl Into<String>, comments: Vec<String>) {
+        let key = key.into();
+
+        if comments.is_empty() {
+            if let Some(existing) = &mut self.comments {
+                existing.members.remove(&key);
+            }
+        } else {
+            self.comments.get_or_insert_with(Box::default).members.insert(key, comments);
+        }
+    }
+
+    comment_list!(trailing_comments, set_trailing_comments, trailing);
+    comment_list!(header_comments, set_header_comments, header);
+    comment_list!(footer_comments, set_footer_comments, footer);
+}
+
+impl<'a> JsonArray<'a> {
+    // Comments move with their item when items are inserted or removed before it,
+    // and are removed along with it
+    pub fn comments(&self, index: usize) -> &[String] {
+        self.comments.as_ref()
+            .and_then(|comments| comments.members.get(&index))
+            .map_or(&[], |comments| comments.as_slice())
+    }
+
+    pub fn set_comments(&mut self, index: usize, comments: Vec<String>) {
+        if comments.is_empty() {
+            if let Some(existing) = &mut self.comments {
+                existing.members.remove(&index);
+            }
+        } else {
+            self.comments.get_or_insert_with(Box::default).members.insert(index, comments);
+        }
+    }
+
+    comment_list!(trailing_comments, set_trailing_comments, trailing);
+    comment_list!(header_comments, set_header_comments, header);
+    comment_list!(footer_comments, set_footer_comments, footer);
+
+    // Follows an item being inserted at the index, or the item at the index being removed
+    pub (crate) fn shift_comments(&mut self, index: usize, inserted: bool) {
+        let comments = match &mut self.comments {
+            Some(comments) if !comments.members.is_empty() => comments,
+            _ => return
+        };
+
+        if !inserted {
+            comments.members.remove(&index);
+        }
+
+        comments.members = comments.members.drain()
+            .map(|(i, item_comments)| match i {
+                i if inserted && i >= index => (i + 1, item_comments),
+                i if !inserted && i > index => (i - 1, item_comments),
+                i => (i, item_comments)
+            })
+            .collect();
+    }
+}
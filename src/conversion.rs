@@ -0,0 +1,426 @@
+use std::collections::{HashMap, BTreeMap};
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+
+// Rust types into JSON values
+
+impl<'a> From<bool> for ArrayType<'a> {
+    fn from(value: bool) -> Self {
+        ArrayType::Bool(value)
+    }
+}
+
+// Integers that always fit into a JSON int
+macro_rules! from_small_int {
+    ($($int:ty),*) => {
+        $(
+            impl<'a> From<$int> for ArrayType<'a> {
+                fn from(value: $int) -> Self {
+                    ArrayType::Int(i32::from(value))
+                }
+            }
+        )*
+    };
+}
+
+from_small_int!(i8, i16, i32, u8, u16);
+
+// Integers that may not fit into a JSON int. Those that don't are kept as decimal
+// strings, so that they convert back exactly, where a float would lose digits.
+macro_rules! from_big_int {
+    ($($int:ty),*) => {
+        $(
+            impl<'a> From<$int> for ArrayType<'a> {
+                fn from(value: $int) -> Self {
+                    match i32::try_from(value) {
+                        Ok(value) => ArrayType::Int(value),
+                        Err(_) => ArrayType::String(value.to_string())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_big_int!(i64, i128, isize, u32, u64, u128, usize);
+
+impl<'a> From<f32> for ArrayType<'a> {
+    fn from(value: f32) -> Self {
+        ArrayType::Float(value)
+    }
+}
+
+// JSON floats are single precision, so values are rounded to the nearest f32. Those
+// beyond the f32 range are kept as strings like the big integers, instead of becoming
+// infinities. NaN and the infinities themselves are kept as floats.
+impl<'a> From<f64> for ArrayType<'a> {
+    fn from(value: f64) -> Self {
+        let narrowed = value as f32;
+
+        if value.is_finite() && !narrowed.is_finite() {
+            ArrayType::String(format!("{:?}", value))
+        } else {
+            ArrayType::Float(narrowed)
+        }
+    }
+}
+
+impl<'a> From<String> for ArrayType<'a> {
+    fn from(value: String) -> Self {
+        ArrayType::String(value)
+    }
+}
+
+impl<'a> From<&str> for ArrayType<'a> {
+    fn from(value: &str) -> Self {
+        ArrayType::String(value.to_string())
+    }
+}
+
+impl<'a> From<JsonArray<'a>> for ArrayType<'a> {
+    fn from(value: JsonArray<'a>) -> Self {
+        ArrayType::Array(value)
+    }
+}
+
+impl<'a> From<JsonObject<'a>> for ArrayType<'a> {
+    fn from(value: JsonObject<'a>) -> Self {
+        ArrayType::Object(value)
+    }
+}
+
+impl<'a> From<Null> for ArrayType<'a> {
+    fn from(value: Null) -> Self {
+        ArrayType::Null(value)
+    }
+}
+
+impl<'a, T: Into<ArrayType<'a>>> From<Option<T>> for ArrayType<'a> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => ArrayType::Null(Null)
+        }
+    }
+}
+
+impl<'a, T: Into<ArrayType<'a>>> From<Vec<T>> for ArrayType<'a> {
+    fn from(value: Vec<T>) -> Self {
+        ArrayType::Array(value.into_iter().collect())
+    }
+}
+
+impl<'a, T: Into<ArrayType<'a>>> From<HashMap<String, T>> for ArrayType<'a> {
+    fn from(value: HashMap<String, T>) -> Self {
+        ArrayType::Object(value.into_iter().collect())
+    }
+}
+
+impl<'a, T: Into<ArrayType<'a>>> From<BTreeMap<String, T>> for ArrayType<'a> {
+    fn from(value: BTreeMap<String, T>) -> Self {
+        ArrayType::Object(value.into_iter().collect())
+    }
+}
+
+// Borrowed values are cloned
+impl<'r, 'a> From<ArrayTypeRef<'r, 'a>> for ArrayType<'a> {
+    fn from(value: ArrayTypeRef<'r, 'a>) -> Self {
+        match value {
+            ArrayTypeRef::Int(value) => ArrayType::Int(value),
+            ArrayTypeRef::Float(value) => ArrayType::Float(value),
+            ArrayTypeRef::Bool(value) => ArrayType::Bool(value),
+            ArrayTypeRef::String(value) => ArrayType::String(value.clone()),
+            ArrayTypeRef::Array(value) => ArrayType::Array(value.clone()),
+            ArrayTypeRef::Object(value) => ArrayType::Object(value.clone()),
+            ArrayTypeRef::Null(value) => ArrayType::Null(value)
+        }
+    }
+}
+
+impl<'a, T: Into<ArrayType<'a>>> FromIterator<T> for JsonArray<'a> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut array = JsonArray::new();
+
+        for item in iter {
+            array.add(item.into());
+        }
+
+        array
+    }
+}
+
+impl<'a, K, V> FromIterator<(K, V)> for JsonObject<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<ArrayType<'a>>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut object = JsonObject::new();
+
+        for (key, value) in iter {
+            object.insert(key, value.into());
+        }
+
+        object
+    }
+}
+
+// JSON values into Rust types
+
+impl<'a> TryFrom<ArrayType<'a>> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Bool(value) => Ok(value),
+            other => Err(ConversionError::wrong_type("bool", &other))
+        }
+    }
+}
+
+macro_rules! try_into_int {
+    ($($int:ty),*) => {
+        $(
+            impl<'a> TryFrom<ArrayType<'a>> for $int {
+                type Error = ConversionError;
+
+                fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+                    match value {
+                        ArrayType::Int(value) => <$int>::try_from(value)
+                            .map_err(|_| ConversionError::OutOfRange { target: stringify!($int) }),
+                        // Only the strings that ints beyond a JSON int are converted to
+                        ArrayType::String(text) => match text.parse::<$int>() {
+                            Ok(int) if i32::try_from(int).is_err() => Ok(int),
+                            _ => Err(ConversionError::wrong_type("int", &ArrayType::String(text)))
+                        },
+                        other => Err(ConversionError::wrong_type("int", &other))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_into_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// Ints are accepted where floats are expected
+impl<'a> TryFrom<ArrayType<'a>> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Float(value) => Ok(value),
+            ArrayType::Int(value) => Ok(value as f32),
+            other => Err(ConversionError::wrong_type("float", &other))
+        }
+    }
+}
+
+impl<'a> TryFrom<ArrayType<'a>> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Float(value) => Ok(f64::from(value)),
+            ArrayType::Int(value) => Ok(f64::from(value)),
+            // Only the strings that floats beyond the f32 range are converted to
+            ArrayType::String(text) => match text.parse::<f64>() {
+                Ok(value) if value.is_finite() && (value as f32).is_infinite() => Ok(value),
+                _ => Err(ConversionError::wrong_type("float", &ArrayType::String(text)))
+            },
+            other => Err(ConversionError::wrong_type("float", &other))
+        }
+    }
+}
+
+impl<'a> TryFrom<ArrayType<'a>> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::String(value) => Ok(value),
+            other => Err(ConversionError::wrong_type("string", &other))
+        }
+    }
+}
+
+impl<'a> TryFrom<ArrayType<'a>> for Null {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Null(value) => Ok(value),
+            other => Err(ConversionError::wrong_type("null", &other))
+        }
+    }
+}
+
+impl<'a> TryFrom<ArrayType<'a>> for JsonArray<'a> {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Array(value) => Ok(value),
+            other => Err(ConversionError::wrong_type("array", &other))
+        }
+    }
+}
+
+impl<'a> TryFrom<ArrayType<'a>> for JsonObject<'a> {
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Object(value) => Ok(value),
+            other => Err(ConversionError::wrong_type("object", &other))
+        }
+    }
+}
+
+impl<'a, T> TryFrom<ArrayType<'a>> for Option<T>
+where
+    T: TryFrom<ArrayType<'a>, Error = ConversionError>
+{
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Null(_) => Ok(None),
+            other => T::try_from(other).map(Some)
+        }
+    }
+}
+
+impl<'a, T> TryFrom<ArrayType<'a>> for Vec<T>
+where
+    T: TryFrom<ArrayType<'a>, Error = ConversionError>
+{
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Array(array) => array.into_iter().map(T::try_from).collect(),
+            other => Err(ConversionError::wrong_type("array", &other))
+        }
+    }
+}
+
+impl<'a, T> TryFrom<ArrayType<'a>> for HashMap<String, T>
+where
+    T: TryFrom<ArrayType<'a>, Error = ConversionError>
+{
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Object(object) => object.into_iter()
+                .map(|(key, value)| T::try_from(value).map(|value| (key.into_owned(), value)))
+                .collect(),
+            other => Err(ConversionError::wrong_type("object", &other))
+        }
+    }
+}
+
+impl<'a, T> TryFrom<ArrayType<'a>> for BTreeMap<String, T>
+where
+    T: TryFrom<ArrayType<'a>, Error = ConversionError>
+{
+    type Error = ConversionError;
+
+    fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+        match value {
+            ArrayType::Object(object) => object.into_iter()
+                .map(|(key, value)| T::try_from(value).map(|value| (key.into_owned(), value)))
+                .collect(),
+            other => Err(ConversionError::wrong_type("object", &other))
+        }
+    }
+}
+
+// Tuples are represented as fixed length arrays
+macro_rules! tuple_conversions {
+    ($length:expr => $($name:ident),+) => {
+        impl<'a, $($name: Into<ArrayType<'a>>),+> From<($($name,)+)> for ArrayType<'a> {
+            #[allow(non_snake_case)]
+            fn from(value: ($($name,)+)) -> Self {
+                let ($($name,)+) = value;
+                let mut array = JsonArray::new();
+                $(array.add($name.into());)+
+                ArrayType::Array(array)
+            }
+        }
+
+        impl<'a, $($name),+> TryFrom<ArrayType<'a>> for ($($name,)+)
+        where
+            $($name: TryFrom<ArrayType<'a>, Error = ConversionError>),+
+        {
+            type Error = ConversionError;
+
+            fn try_from(value: ArrayType<'a>) -> Result<Self, Self::Error> {
+                let array = JsonArray::try_from(value)?;
+
+                if array.len() != $length {
+                    return Err(ConversionError::WrongLength { expected: $length, found: array.len() });
+                }
+
+                let mut items = array.into_iter();
+                Ok(($($name::try_from(items.next().expect("Length was checked"))?,)+))
+            }
+        }
+    };
+}
+
+tuple_conversions!(1 => A);
+tuple_conversions!(2 => A, B);
+tuple_conversions!(3 => A, B, C);
+tuple_conversions!(4 => A, B, C, D);
+tuple_conversions!(5 => A, B, C, D, E);
+tuple_conversions!(6 => A, B, C, D, E, F);
+
+#[derive(Debug)]
+pub enum ConversionError {
+    WrongType { expected: &'static str, found: &'static str },
+    OutOfRange { target: &'static str },
+    WrongLength { expected: usize, found: usize },
+    MissingField { field: String },
+    UnknownVariant { variant: String }
+}
+
+impl ConversionError {
+    fn wrong_type(expected: &'static str, found: &ArrayType) -> Self {
+        ConversionError::WrongType {
+            expected,
+            found: found.type_name()
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::WrongType { expected, found } => {
+                write!(formatter, "ConversionError: expected {}, found {}", expected, found)
+            }
+            ConversionError::OutOfRange { target } => {
+                write!(formatter, "ConversionError: number out of range for {}", target)
+            }
+            ConversionError::WrongLength { expected, found } => {
+                write!(formatter, "ConversionError: expected array of length {}, found {}",
+                       expected, found)
+            }
+            ConversionError::MissingField { field } => {
+                write!(formatter, "ConversionError: missing field {}", field)
+            }
+            ConversionError::UnknownVariant { variant } => {
+                write!(formatter, "ConversionError: unknown variant {}", variant)
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {}
@@ -0,0 +1,499 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use crate::tokenizer::{Token, tokenize_with};
+use crate::parser::parse_with;
+use crate::options::ParseOptions;
+use crate::pointer::{PointerError, parse_pointer, parse_index};
+use crate::data_structure::{JsonObject, ArrayType};
+use crate::dumper::{dump_value, dump_string};
+
+// A lossless concrete syntax tree: the document keeps its source text, and every
+// token, whitespace and comments included, has a byte span in it. Edits replace
+// only the bytes of the value, member or item they change, so the formatting of
+// the rest of the document stays as it was. New values are written in the layout
+// of their surroundings: pretty if the document spans several lines, compact otherwise.
+
+pub struct Document {
+    source: String,
+    options: ParseOptions,
+    tokens: Vec<SyntaxToken>,
+    root: Node
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    kind: SyntaxKind,
+    span: Range<usize>
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    // Byte offsets in the source
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Whitespace,
+    Comment,
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
+    Comma,
+    String,
+    Number,
+    Keyword
+}
+
+struct Node {
+    span: Range<usize>,
+    container: Option<Container>  // None for strings, numbers and keywords
+}
+
+struct Container {
+    is_object: bool,
+    open: Range<usize>,
+    close: Range<usize>,
+    elements: Vec<Element>
+}
+
+// An object member or an array item
+struct Element {
+    key: Option<(String, Range<usize>)>,
+    value: Node,
+    comma: Option<Range<usize>>
+}
+
+impl Element {
+    fn start(&self) -> usize {
+        match &self.key {
+            Some((_, span)) => span.start,
+            None => self.value.span.start
+        }
+    }
+}
+
+impl Document {
+    pub fn parse(source: String) -> Result<Document, Box<dyn Error>> {
+        Document::parse_with(source, &ParseOptions::default())
+    }
+
+    pub fn parse_with(source: String, options: &ParseOptions) -> Result<Document, Box<dyn Error>> {
+        let tokens = tokenize_with(source.clone(), options)?;
+        parse_with(tokens.clone(), options)?;  // Checks the document, so that the tree can be built without checks
+
+        let syntax_tokens = scan(&source);
+
+        // The tokenizer gives the same tokens, without whitespace and comments,
+        // along with the unescaped strings
+        let significant: Vec<_> = syntax_tokens.iter()
+            .filter(|token| !matches!(token.kind, SyntaxKind::Whitespace | SyntaxKind::Comment))
+            .map(|token| token.span.clone())
+            .zip(tokens.into_iter()
+                 .map(|(token, _)| token)
+                 .filter(|token| !matches!(token, Token::Comment(_) | Token::Eof)))
+            .collect();
+
+        let root = build_node(&significant, &mut 0);
+
+        Ok(Document {
+            source,
+            options: options.clone(),
+            tokens: syntax_tokens,
+            root
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn tokens(&self) -> &[SyntaxToken] {
+        &self.tokens
+    }
+
+    pub fn to_object(&self) -> JsonObject<'static> {
+        let tokens = tokenize_with(self.source.clone(), &self.options).expect("The document was checked");
+
+        parse_with(tokens, &self.options).expect("The document was checked")
+    }
+
+    // The span of the value at a JSON Pointer
+    pub fn span(&self, pointer: &str) -> Option<Range<usize>> {
+        let tokens = parse_pointer(pointer).ok()?;
+
+        Some(self.find(&tokens)?.span.clone())
+    }
+
+    // The value at a JSON Pointer as it's written, like "1.50" or "é"
+    pub fn raw(&self, pointer: &str) -> Option<&str> {
+        Some(&self.source[self.span(pointer)?])
+    }
+
+    // Replaces an existing value
+    pub fn set(&mut self, pointer: &str, value: &ArrayType) -> Result<(), Box<dyn Error>> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() && !matches!(value, ArrayType::Object(_)) {
+            return Err(Box::new(PointerError::Root));
+        }
+
+        let node = self.find(&tokens).ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+        let indentation = line_indentation(&self.source, node.span.start);
+        let text = self.render(value, &indentation);
+
+        self.splice(node.span.clone(), &text)
+    }
+
+    // Adds a member to an object or an item to an array, like pointer_insert: an existing
+    // member is replaced, and an array index or "-" inserts before that index or at the end
+    pub fn insert(&mut self, pointer: &str, value: &ArrayType) -> Result<(), Box<dyn Error>> {
+        let mut tokens = parse_pointer(pointer)?;
+        let last = tokens.pop().ok_or(PointerError::Root)?;
+
+        let not_found = || PointerError::NotFound(pointer.to_string());
+        let container = self.find(&tokens).and_then(|node| node.container.as_ref()).ok_or_else(not_found)?;
+
+        let index = if container.is_object {
+            let existing = container.elements.iter().rposition(|element| {
+                element.key.as_ref().is_some_and(|(key, _)| *key == last)
+            });
+
+            if existing.is_some() {
+                return self.set(pointer, value);
+            }
+            container.elements.len()
+        } else if last == "-" {
+            container.elements.len()
+        } else {
+            match parse_index(&last) {
+                Some(index) if index <= container.elements.len() => index,
+                _ => return Err(Box::new(PointerError::InvalidIndex(last)))
+            }
+        };
+
+        let (range, text) = self.insertion(container, index, &last, value);
+        self.splice(range, &text)
+    }
+
+    // Removes a member or an item along with its comma. Removing the last one
+    // empties the container, comments inside it included.
+    pub fn remove(&mut self, pointer: &str) -> Result<(), Box<dyn Error>> {
+        let mut tokens = parse_pointer(pointer)?;
+        let last = tokens.pop().ok_or(PointerError::Root)?;
+
+        let not_found = || PointerError::NotFound(pointer.to_string());
+        let container = self.find(&tokens).and_then(|node| node.container.as_ref()).ok_or_else(not_found)?;
+        let index = element_index(container, &last).ok_or_else(not_found)?;
+
+        let elements = &container.elements;
+        let element = &elements[index];
+
+        let (range, kept) = if elements.len() == 1 {
+            (container.open.end..container.close.start, String::new())
+        } else if index + 1 < elements.len() {
+            // Up to the next member or item, taking the comments on the same line along
+            // but leaving the comments in front of it
+            let comma = element.comma.as_ref().expect("Elements before the last have a comma");
+            let end = trailing_comments_end(&self.source, comma.end);
+            let whitespace = self.source[end..].len() - self.source[end..].trim_start().len();
+            (element.start()..end + whitespace, String::new())
+        } else {
+            // The comments after the previous comma stay with the previous element
+            let previous_comma = elements[index - 1].comma.as_ref().expect("Elements before the last have a comma");
+            let kept = &self.source[previous_comma.end..trailing_comments_end(&self.source, previous_comma.end)];
+            (previous_comma.start..trailing_comments_end(&self.source, element.value.span.end), kept.to_string())
+        };
+
+        self.splice(range, &kept)
+    }
+
+    fn find(&self, tokens: &[String]) -> Option<&Node> {
+        let mut node = &self.root;
+
+        for token in tokens {
+            let container = node.container.as_ref()?;
+            node = &container.elements[element_index(container, token)?].value;
+        }
+
+        Some(node)
+    }
+
+    // Where a new element goes and the text to put there, copying the separators around it
+    fn insertion(&self, container: &Container, index: usize, key: &str, value: &ArrayType) -> (Range<usize>, String) {
+        let elements = &container.elements;
+        let multi_line = self.source.contains('\n');
+
+        let indentation = match elements.first() {
+            Some(element) => line_indentation(&self.source, element.start()),
+            None if multi_line => line_indentation(&self.source, container.open.start) + &indentation_unit(&self.source),
+            None => String::new()
+        };
+
+        let mut element = String::new();
+        if container.is_object {
+            dump_string(key, &mut element);
+            element.push_str(&self.colon_separator(container));
+        }
+        element.push_str(&self.render(value, &indentation));
+
+        if elements.is_empty() {
+            if multi_line {
+                let closing_indentation = line_indentation(&self.source, container.open.start);
+                return (container.open.end..container.close.start,
+                        format!("\n{}{}\n{}", indentation, element, closing_indentation));
+            }
+            return (container.open.end..container.close.start, element);
+        }
+
+        let separator = self.separator(container);
+
+        if index == elements.len() {
+            let end = elements[index - 1].value.span.end;
+            (end..end, format!("{}{}", separator, element))
+        } else {
+            let start = elements[index].start();
+            (start..start, format!("{}{}", element, separator))
+        }
+    }
+
+    // The text between two elements, from the comma up to the next element
+    fn separator(&self, container: &Container) -> String {
+        let elements = &container.elements;
+
+        if let [first, second, ..] = elements.as_slice() {
+            let comma = first.comma.as_ref().expect("Elements before the last have a comma");
+            let separator = &self.source[comma.start..second.start()];
+
+            if !separator.contains('/') {
+                return separator.to_string();
+            }
+        }
+
+        let leading = &self.source[container.open.end..elements[0].start()];
+
+        if leading.contains('\n') {
+            format!(",\n{}", line_indentation(&self.source, elements[0].start()))
+        } else if leading.is_empty() {
+            ",".to_string()
+        } else {
+            ", ".to_string()
+        }
+    }
+
+    fn colon_separator(&self, container: &Container) -> String {
+        for element in &container.elements {
+            if let Some((_, key_span)) = &element.key {
+                let separator = &self.source[key_span.end..element.value.span.start];
+
+                if !separator.contains('/') {
+                    return separator.to_string();
+                }
+            }
+        }
+
+        // As the dumper writes it
+        if self.source.contains('\n') { ": " } else { ":" }.to_string()
+    }
+
+    // Writes a value in the layout of the document, indenting its lines after the first
+    fn render(&self, value: &ArrayType, indentation: &str) -> String {
+        let mut output = String::new();
+        let multi_line = self.source.contains('\n');
+        dump_value(value.to_ref(), multi_line, 0, &mut output);
+
+        if !multi_line {
+            return output;
+        }
+
+        let unit = indentation_unit(&self.source);
+        let lines: Vec<String> = output.split('\n').enumerate().map(|(i, line)| {
+            if i == 0 {
+                return line.to_string();
+            }
+
+            let content = line.trim_start_matches(' ');
+            let level = (line.len() - content.len()) / 4;  // The dumper indents with four spaces
+            format!("{}{}{}", indentation, unit.repeat(level), content)
+        }).collect();
+
+        lines.join("\n")
+    }
+
+    // Replaces a range of the source and parses it again. The document is
+    // left unchanged if the result doesn't parse, like with a maximum depth.
+    fn splice(&mut self, range: Range<usize>, text: &str) -> Result<(), Box<dyn Error>> {
+        let mut source = self.source.clone();
+        source.replace_range(range, text);
+
+        *self = Document::parse_with(source, &self.options)?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.source)
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Document").field("source", &self.source).finish()
+    }
+}
+
+// Duplicate keys resolve to the last member, like when loading
+fn element_index(container: &Container, token: &str) -> Option<usize> {
+    if container.is_object {
+        container.elements.iter().rposition(|element| {
+            element.key.as_ref().is_some_and(|(key, _)| key == token)
+        })
+    } else {
+        parse_index(token).filter(|index| *index < container.elements.len())
+    }
+}
+
+// The end of the comments that follow a position on the same line, or the position itself
+fn trailing_comments_end(source: &str, position: usize) -> usize {
+    let mut end = position;
+    let mut current = position;
+
+    loop {
+        current += source[current..].len() - source[current..].trim_start_matches([' ', '\t']).len();
+
+        if source[current..].starts_with("//") {
+            return current + source[current..].find(['\n', '\r']).unwrap_or(source.len() - current);
+        } else if source[current..].starts_with("/*") {
+            current += source[current + 2..].find("*/").expect("Comments were checked") + 4;
+            end = current;
+        } else {
+            return end;
+        }
+    }
+}
+
+fn line_indentation(source: &str, position: usize) -> String {
+    let line_start = source[..position].rfind('\n').map_or(0, |index| index + 1);
+
+    source[line_start..].chars().take_while(|character| *character == ' ' || *character == '\t').collect()
+}
+
+// The indentation of the first indented line, or four spaces
+fn indentation_unit(source: &str) -> String {
+    source.split('\n')
+        .skip(1)
+        .map(|line| line.chars().take_while(|character| *character == ' ' || *character == '\t').collect::<String>())
+        .find(|indentation| !indentation.is_empty())
+        .unwrap_or_else(|| "    ".to_string())
+}
+
+// Splits the source into tokens that cover every byte. The source was already
+// checked by the tokenizer, so this only has to find where tokens end.
+fn scan(source: &str) -> Vec<SyntaxToken> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let (kind, end) = match bytes[start] {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                let length = bytes[start..].iter().take_while(|byte| b" \t\n\r".contains(byte)).count();
+                (SyntaxKind::Whitespace, start + length)
+            }
+            b'{' => (SyntaxKind::LeftBrace, start + 1),
+            b'}' => (SyntaxKind::RightBrace, start + 1),
+            b'[' => (SyntaxKind::LeftBracket, start + 1),
+            b']' => (SyntaxKind::RightBracket, start + 1),
+            b':' => (SyntaxKind::Colon, start + 1),
+            b',' => (SyntaxKind::Comma, start + 1),
+            b'"' => {
+                let mut end = start + 1;
+                while bytes[end] != b'"' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                (SyntaxKind::String, end + 1)
+            }
+            b'/' if bytes.get(start + 1) == Some(&b'/') => {
+                let length = source[start..].find(['\n', '\r']).unwrap_or(bytes.len() - start);
+                (SyntaxKind::Comment, start + length)
+            }
+            b'/' => {
+                let length = source[start + 2..].find("*/").expect("Comments were checked") + 4;
+                (SyntaxKind::Comment, start + length)
+            }
+            byte => {
+                let length = bytes[start..].iter()
+                    .take_while(|byte| !b" \t\n\r{}[]:,\"/".contains(byte))
+                    .count();
+                let kind = match byte {
+                    b't' | b'f' | b'n' => SyntaxKind::Keyword,
+                    _ => SyntaxKind::Number  // Including NaN and Infinity
+                };
+                (kind, start + length)
+            }
+        };
+
+        tokens.push(SyntaxToken { kind, span: start..end });
+        start = end;
+    }
+
+    tokens
+}
+
+fn build_node(significant: &[(Range<usize>, Token)], current: &mut usize) -> Node {
+    let (open, token) = &significant[*current];
+    *current += 1;
+
+    let is_object = match token {
+        Token::LeftBrace => true,
+        Token::LeftBracket => false,
+        _ => return Node { span: open.clone(), container: None }
+    };
+
+    let mut elements = Vec::new();
+
+    let close = loop {
+        let (span, token) = &significant[*current];
+
+        if let Token::RightBrace | Token::RightBracket = token {
+            *current += 1;
+            break span.clone();
+        }
+
+        let key = match token {
+            Token::String(key) if is_object => {
+                *current += 2;  // The key and the colon
+                Some((key.clone(), span.clone()))
+            }
+            _ => None
+        };
+
+        let value = build_node(significant, current);
+
+        let comma = match &significant[*current] {
+            (span, Token::Comma) => {
+                *current += 1;
+                Some(span.clone())
+            }
+            _ => None
+        };
+
+        elements.push(Element { key, value, comma });
+    };
+
+    Node {
+        span: open.start..close.end,
+        container: Some(Container { is_object, open: open.clone(), close, elements })
+    }
+}
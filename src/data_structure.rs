@@ -1,16 +1,21 @@
 #![allow(unused)]
 
 use std::collections::HashMap;
+use std::borrow::Cow;
+
+use crate::entry::{Entry, OccupiedEntry, VacantEntry};
+use crate::comments::Comments;
 
 #[derive(Debug, Clone)]
 pub struct JsonObject<'a> {
-    int_pairs: HashMap<&'a str, i32>,
-    float_pairs: HashMap<&'a str, f32>,
-    bool_pairs: HashMap<&'a str, bool>,
-    string_pairs: HashMap<&'a str, String>,
-    array_pairs: HashMap<&'a str, JsonArray<'a>>,
-    object_pairs: HashMap<&'a str, JsonObject<'a>>,
-    null_pairs: HashMap<&'a str, Null>
+    int_pairs: HashMap<Cow<'a, str>, i32>,
+    float_pairs: HashMap<Cow<'a, str>, f32>,
+    bool_pairs: HashMap<Cow<'a, str>, bool>,
+    string_pairs: HashMap<Cow<'a, str>, String>,
+    array_pairs: HashMap<Cow<'a, str>, JsonArray<'a>>,
+    object_pairs: HashMap<Cow<'a, str>, JsonObject<'a>>,
+    null_pairs: HashMap<Cow<'a, str>, Null>,
+    pub (crate) comments: Option<Box<Comments<String>>>  // Only set for JSONC documents parsed with comments kept
 }
 
 impl<'a> JsonObject<'a> {
@@ -22,114 +27,278 @@ impl<'a> JsonObject<'a> {
             string_pairs: HashMap::new(),
             array_pairs: HashMap::new(),
             object_pairs: HashMap::new(),
-            null_pairs: HashMap::new()
+            null_pairs: HashMap::new(),
+            comments: None
         }
     }
 
-    pub fn insert_int(&mut self, key: &'a str, value: i32) {
-        self.int_pairs.insert(key, value);
+    pub fn insert_int(&mut self, key: impl Into<Cow<'a, str>>, value: i32) {
+        self.int_pairs.insert(key.into(), value);
     }
 
-    pub fn insert_float(&mut self, key: &'a str, value: f32) {
-        self.float_pairs.insert(key, value);
+    pub fn insert_float(&mut self, key: impl Into<Cow<'a, str>>, value: f32) {
+        self.float_pairs.insert(key.into(), value);
     }
 
-    pub fn insert_bool(&mut self, key: &'a str, value: bool) {
-        self.bool_pairs.insert(key, value);
+    pub fn insert_bool(&mut self, key: impl Into<Cow<'a, str>>, value: bool) {
+        self.bool_pairs.insert(key.into(), value);
     }
 
-    pub fn insert_string(&mut self, key: &'a str, value: String) {
-        self.string_pairs.insert(key, value);
+    pub fn insert_string(&mut self, key: impl Into<Cow<'a, str>>, value: String) {
+        self.string_pairs.insert(key.into(), value);
     }
 
-    pub fn insert_array(&mut self, key: &'a str, value: JsonArray<'a>) {
-        self.array_pairs.insert(key, value);
+    pub fn insert_array(&mut self, key: impl Into<Cow<'a, str>>, value: JsonArray<'a>) {
+        self.array_pairs.insert(key.into(), value);
     }
 
-    pub fn insert_object(&mut self, key: &'a str, value: JsonObject<'a>) {
-        self.object_pairs.insert(key, value);
+    pub fn insert_object(&mut self, key: impl Into<Cow<'a, str>>, value: JsonObject<'a>) {
+        self.object_pairs.insert(key.into(), value);
     }
 
-    pub fn insert_null(&mut self, key: &'a str, value: Null) {
-        self.null_pairs.insert(key, value);
+    pub fn insert_null(&mut self, key: impl Into<Cow<'a, str>>, value: Null) {
+        self.null_pairs.insert(key.into(), value);
     }
 
-    pub fn delete_int(&mut self, key: &'a str) -> Option<i32> {
-        self.int_pairs.remove(&key)
+    pub fn delete_int(&mut self, key: &str) -> Option<i32> {
+        self.int_pairs.remove(key)
     }
 
-    pub fn delete_float(&mut self, key: &'a str) -> Option<f32> {
-        self.float_pairs.remove(&key)
+    pub fn delete_float(&mut self, key: &str) -> Option<f32> {
+        self.float_pairs.remove(key)
     }
 
-    pub fn delete_bool(&mut self, key: &'a str) -> Option<bool> {
+    pub fn delete_bool(&mut self, key: &str) -> Option<bool> {
         self.bool_pairs.remove(key)
     }
 
-    pub fn delete_string(&mut self, key: &'a str) -> Option<String> {
+    pub fn delete_string(&mut self, key: &str) -> Option<String> {
         self.string_pairs.remove(key)
     }
 
-    pub fn delete_array(&mut self, key: &'a str) -> Option<JsonArray> {
+    pub fn delete_array(&mut self, key: &str) -> Option<JsonArray<'a>> {
         self.array_pairs.remove(key)
     }
 
-    pub fn delete_object(&mut self, key: &'a str) -> Option<JsonObject> {
+    pub fn delete_object(&mut self, key: &str) -> Option<JsonObject<'a>> {
         self.object_pairs.remove(key)
     }
 
-    pub fn delete_null(&mut self, key: &'a str) -> Option<Null> {
+    pub fn delete_null(&mut self, key: &str) -> Option<Null> {
         self.null_pairs.remove(key)
     }
 
-    pub fn get_int(&self, key: &'a str) -> Option<i32> {
+    pub fn get_int(&self, key: &str) -> Option<i32> {
         if let Some(result) = self.int_pairs.get(key) {
-            return Some(result.clone());
+            return Some(*result);
         }
         None
     }
 
-    pub fn get_float(&self, key: &'a str) -> Option<f32> {
+    pub fn get_float(&self, key: &str) -> Option<f32> {
         if let Some(result) = self.float_pairs.get(key) {
-            return Some(result.clone());
+            return Some(*result);
         }
         None
     }
 
-    pub fn get_bool(&self, key: &'a str) -> Option<bool> {
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
         if let Some(result) = self.bool_pairs.get(key) {
-            return Some(result.clone());
+            return Some(*result);
         }
         None
     }
 
-    pub fn get_string(&self, key: &'a str) -> Option<String> {
+    pub fn get_string(&self, key: &str) -> Option<String> {
         if let Some(result) = self.string_pairs.get(key) {
             return Some(result.clone());
         }
         None
     }
 
-    pub fn get_array(&self, key: &'a str) -> Option<JsonArray> {
+    pub fn get_array(&self, key: &str) -> Option<JsonArray<'a>> {
         if let Some(result) = self.array_pairs.get(key) {
             return Some(result.clone());
         }
         None
     }
 
-    pub fn get_object(&mut self, key: &'a str) -> Option<JsonObject> {
+    pub fn get_object(&mut self, key: &str) -> Option<JsonObject<'a>> {
         if let Some(result) = self.object_pairs.get(key) {
             return Some(result.clone());
         }
         None
     }
 
-    pub fn get_null(&self, key: &'a str) -> Option<Null> {
+    pub fn get_null(&self, key: &str) -> Option<Null> {
         if let Some(result) = self.null_pairs.get(key) {
-            return Some(result.clone());
+            return Some(*result);
+        }
+        None
+    }
+
+    pub fn get(&self, key: &str) -> Option<ArrayTypeRef<'_, 'a>> {
+        if let Some(value) = self.int_pairs.get(key) {
+            return Some(ArrayTypeRef::Int(*value));
+        }
+        if let Some(value) = self.float_pairs.get(key) {
+            return Some(ArrayTypeRef::Float(*value));
+        }
+        if let Some(value) = self.bool_pairs.get(key) {
+            return Some(ArrayTypeRef::Bool(*value));
+        }
+        if let Some(value) = self.string_pairs.get(key) {
+            return Some(ArrayTypeRef::String(value));
+        }
+        if let Some(value) = self.array_pairs.get(key) {
+            return Some(ArrayTypeRef::Array(value));
+        }
+        if let Some(value) = self.object_pairs.get(key) {
+            return Some(ArrayTypeRef::Object(value));
+        }
+        if let Some(value) = self.null_pairs.get(key) {
+            return Some(ArrayTypeRef::Null(*value));
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<ArrayTypeMut<'_, 'a>> {
+        if let Some(value) = self.int_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::Int(value));
+        }
+        if let Some(value) = self.float_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::Float(value));
+        }
+        if let Some(value) = self.bool_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::Bool(value));
+        }
+        if let Some(value) = self.string_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::String(value));
+        }
+        if let Some(value) = self.array_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::Array(value));
+        }
+        if let Some(value) = self.object_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::Object(value));
+        }
+        if let Some(value) = self.null_pairs.get_mut(key) {
+            return Some(ArrayTypeMut::Null(value));
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    // Inserts a value of any type, replacing whatever was stored under the key,
+    // even if it was of a different type
+    pub fn insert(&mut self, key: impl Into<Cow<'a, str>>,
+                  value: ArrayType<'a>) -> Option<ArrayType<'a>> {
+        let key = key.into();
+        let old_value = self.delete(&key);
+
+        match value {
+            ArrayType::Int(value) => self.insert_int(key, value),
+            ArrayType::Float(value) => self.insert_float(key, value),
+            ArrayType::Bool(value) => self.insert_bool(key, value),
+            ArrayType::String(value) => self.insert_string(key, value),
+            ArrayType::Array(value) => self.insert_array(key, value),
+            ArrayType::Object(value) => self.insert_object(key, value),
+            ArrayType::Null(value) => self.insert_null(key, value)
+        }
+
+        old_value
+    }
+
+    pub fn delete(&mut self, key: &str) -> Option<ArrayType<'a>> {
+        if let Some(value) = self.int_pairs.remove(key) {
+            return Some(ArrayType::Int(value));
+        }
+        if let Some(value) = self.float_pairs.remove(key) {
+            return Some(ArrayType::Float(value));
+        }
+        if let Some(value) = self.bool_pairs.remove(key) {
+            return Some(ArrayType::Bool(value));
+        }
+        if let Some(value) = self.string_pairs.remove(key) {
+            return Some(ArrayType::String(value));
+        }
+        if let Some(value) = self.array_pairs.remove(key) {
+            return Some(ArrayType::Array(value));
+        }
+        if let Some(value) = self.object_pairs.remove(key) {
+            return Some(ArrayType::Object(value));
+        }
+        if let Some(value) = self.null_pairs.remove(key) {
+            return Some(ArrayType::Null(value));
         }
         None
     }
+
+    pub fn entry<'r>(&'r mut self, key: impl Into<Cow<'a, str>>) -> Entry<'r, 'a> {
+        let key = key.into();
+
+        if self.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry::new(self, key))
+        } else {
+            Entry::Vacant(VacantEntry::new(self, key))
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, 'a> {
+        Keys {
+            inner: self.iter()
+        }
+    }
+
+    pub fn values(&self) -> Values<'_, 'a> {
+        Values {
+            inner: self.iter()
+        }
+    }
+
+    pub fn iter(&self) -> ObjectIter<'_, 'a> {
+        let mut pairs = Vec::new();
+
+        pairs.extend(self.int_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::Int(*value))));
+        pairs.extend(self.float_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::Float(*value))));
+        pairs.extend(self.bool_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::Bool(*value))));
+        pairs.extend(self.string_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::String(value))));
+        pairs.extend(self.array_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::Array(value))));
+        pairs.extend(self.object_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::Object(value))));
+        pairs.extend(self.null_pairs.iter().map(|(key, value)| (key.as_ref(), ArrayTypeRef::Null(*value))));
+
+        ObjectIter {
+            inner: pairs.into_iter()
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> ObjectIterMut<'_, 'a> {
+        let mut pairs = Vec::new();
+
+        pairs.extend(self.int_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::Int(value))));
+        pairs.extend(self.float_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::Float(value))));
+        pairs.extend(self.bool_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::Bool(value))));
+        pairs.extend(self.string_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::String(value))));
+        pairs.extend(self.array_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::Array(value))));
+        pairs.extend(self.object_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::Object(value))));
+        pairs.extend(self.null_pairs.iter_mut().map(|(key, value)| (key.as_ref(), ArrayTypeMut::Null(value))));
+
+        ObjectIterMut {
+            inner: pairs.into_iter()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.int_pairs.len() + self.float_pairs.len() + self.bool_pairs.len() +
+            self.string_pairs.len() + self.array_pairs.len() + self.object_pairs.len() +
+            self.null_pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -142,7 +311,8 @@ pub struct JsonArray<'a> {
     objects: Vec<ArrayItem<JsonObject<'a>>>,
     nulls: Vec<ArrayItem<Null>>,
 
-    item_count: usize
+    item_count: usize,
+    pub (crate) comments: Option<Box<Comments<usize>>>  // Like in objects
 }
 
 impl<'a> JsonArray<'a> {
@@ -155,7 +325,8 @@ impl<'a> JsonArray<'a> {
             arrays: Vec::new(),
             objects: Vec::new(),
             nulls: Vec::new(),
-            item_count: 0
+            item_count: 0,
+            comments: None
         }
     }
 
@@ -229,7 +400,20 @@ impl<'a> JsonArray<'a> {
         self.item_count += 1;
     }
 
-    pub fn remove(&mut self, index: usize) -> Result<ArrayType, &str> {
+    // Adds a value of any type to the end of the array
+    pub fn add(&mut self, value: ArrayType<'a>) {
+        match value {
+            ArrayType::Int(value) => self.add_int(value),
+            ArrayType::Float(value) => self.add_float(value),
+            ArrayType::Bool(value) => self.add_bool(value),
+            ArrayType::String(value) => self.add_string(value),
+            ArrayType::Array(value) => self.add_array(value),
+            ArrayType::Object(value) => self.add_object(value),
+            ArrayType::Null(value) => self.add_null(value)
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<ArrayType<'a>, &'static str> {
         let mut index_to_remove: isize = -1;
         let mut array_type = ArrayItemType::Int;
 
@@ -333,7 +517,7 @@ impl<'a> JsonArray<'a> {
         }
     }
 
-    pub fn get(&mut self, index: usize) -> Result<ArrayTypeRef, &str> {
+    pub fn get(&self, index: usize) -> Result<ArrayTypeRef<'_, 'a>, &'static str> {
         for item in self.ints.iter() {
             if item.index == index {
                 return Ok(ArrayTypeRef::Int(item.item));
@@ -373,142 +557,204 @@ impl<'a> JsonArray<'a> {
         Err("Index doesn't exist")
     }
 
-    pub fn set_int(&mut self, value: i32, index: usize) -> Result<(), &str> {
-        self.ints.push(
-            ArrayItem {
-                item: value,
-                index: index
-            }
-        );
-
-        for (i, item) in self.ints.iter().enumerate() {  // Not well thought yet
+    pub fn get_mut(&mut self, index: usize) -> Result<ArrayTypeMut<'_, 'a>, &'static str> {
+        for item in self.ints.iter_mut() {
             if item.index == index {
-                self.ints.remove(i);
-                break;
+                return Ok(ArrayTypeMut::Int(&mut item.item));
             }
         }
-        for (i, item) in self.floats.iter().enumerate() {
+        for item in self.floats.iter_mut() {
             if item.index == index {
-                self.floats.remove(i);
-                break;
+                return Ok(ArrayTypeMut::Float(&mut item.item));
             }
         }
-        for (i, item) in self.bools.iter().enumerate() {
+        for item in self.bools.iter_mut() {
             if item.index == index {
-                self.bools.remove(i);
-                break;
+                return Ok(ArrayTypeMut::Bool(&mut item.item));
             }
         }
-        for (i, item) in self.strings.iter().enumerate() {
+        for item in self.strings.iter_mut() {
             if item.index == index {
-                self.strings.remove(i);
-                break;
+                return Ok(ArrayTypeMut::String(&mut item.item));
             }
         }
-        for (i, item) in self.arrays.iter().enumerate() {
+        for item in self.arrays.iter_mut() {
             if item.index == index {
-                self.arrays.remove(i);
-                break;
+                return Ok(ArrayTypeMut::Array(&mut item.item));
             }
         }
-        for (i, item) in self.objects.iter().enumerate() {
+        for item in self.objects.iter_mut() {
             if item.index == index {
-                self.objects.remove(i);
-                break;
+                return Ok(ArrayTypeMut::Object(&mut item.item));
             }
         }
-        for (i, item) in self.nulls.iter().enumerate() {
+        for item in self.nulls.iter_mut() {
             if item.index == index {
-                self.nulls.remove(i);
-                break;
+                return Ok(ArrayTypeMut::Null(&mut item.item));
             }
         }
 
+        Err("Index doesn't exist")
+    }
+
+    // Inserts a value at the index, moving every item after it one position forward.
+    // The index can be at most the length of the array.
+    pub fn insert(&mut self, index: usize, value: ArrayType<'a>) -> Result<(), &'static str> {
+        if index > self.item_count {
+            return Err("Index doesn't exist");
+        }
+
+        self.fix_index_on_array_item_insertion(index);
 
+        match value {
+            ArrayType::Int(item) => self.ints.push(ArrayItem { item, index }),
+            ArrayType::Float(item) => self.floats.push(ArrayItem { item, index }),
+            ArrayType::Bool(item) => self.bools.push(ArrayItem { item, index }),
+            ArrayType::String(item) => self.strings.push(ArrayItem { item, index }),
+            ArrayType::Array(item) => self.arrays.push(ArrayItem { item, index }),
+            ArrayType::Object(item) => self.objects.push(ArrayItem { item, index }),
+            ArrayType::Null(item) => self.nulls.push(ArrayItem { item, index })
+        }
+        self.item_count += 1;
 
-        Err("Placeholder")
+        Ok(())
+    }
+
+    // Replaces the value at the index with a value of any type, returning the old one
+    pub fn set(&mut self, index: usize, value: ArrayType<'a>) -> Result<ArrayType<'a>, &'static str> {
+        let comments = self.comments(index).to_vec();  // Kept for the new value
+        let old_value = self.remove(index)?;
+        self.insert(index, value)?;
+        self.set_comments(index, comments);
+
+        Ok(old_value)
+    }
+
+    pub fn set_int(&mut self, value: i32, index: usize) -> Result<(), &'static str> {
+        self.set(index, ArrayType::Int(value)).map(|_| ())
     }
 
     pub fn set_float(&mut self, value: f32, index: usize) -> Result<(), &'static str> {
-        Err("Placeholder")
+        self.set(index, ArrayType::Float(value)).map(|_| ())
     }
 
     pub fn set_bool(&mut self, value: bool, index: usize) -> Result<(), &'static str> {
-        Err("Placeholder")
+        self.set(index, ArrayType::Bool(value)).map(|_| ())
     }
 
     pub fn set_string(&mut self, value: String, index: usize) -> Result<(), &'static str> {
-        Err("Placeholder")
+        self.set(index, ArrayType::String(value)).map(|_| ())
     }
 
-    pub fn set_array(&mut self, value: JsonArray, index: usize) -> Result<(), &'static str> {
-        Err("Placeholder")
+    pub fn set_array(&mut self, value: JsonArray<'a>, index: usize) -> Result<(), &'static str> {
+        self.set(index, ArrayType::Array(value)).map(|_| ())
     }
 
-    pub fn set_object(&mut self, value: JsonObject, index: usize) -> Result<(), &'static str> {
-        Err("Placeholder")
+    pub fn set_object(&mut self, value: JsonObject<'a>, index: usize) -> Result<(), &'static str> {
+        self.set(index, ArrayType::Object(value)).map(|_| ())
     }
 
     pub fn set_null(&mut self, value: Null, index: usize) -> Result<(), &'static str> {
-        Err("Placeholder")
+        self.set(index, ArrayType::Null(value)).map(|_| ())
+    }
+
+    // Items are stored by type, so they are sorted back by their index
+    // to be visited in the order in which they appear in the array
+    pub fn iter(&self) -> ArrayIter<'_, 'a> {
+        let mut items = Vec::with_capacity(self.item_count);
+
+        items.extend(self.ints.iter().map(|item| (item.index, ArrayTypeRef::Int(item.item))));
+        items.extend(self.floats.iter().map(|item| (item.index, ArrayTypeRef::Float(item.item))));
+        items.extend(self.bools.iter().map(|item| (item.index, ArrayTypeRef::Bool(item.item))));
+        items.extend(self.strings.iter().map(|item| (item.index, ArrayTypeRef::String(&item.item))));
+        items.extend(self.arrays.iter().map(|item| (item.index, ArrayTypeRef::Array(&item.item))));
+        items.extend(self.objects.iter().map(|item| (item.index, ArrayTypeRef::Object(&item.item))));
+        items.extend(self.nulls.iter().map(|item| (item.index, ArrayTypeRef::Null(item.item))));
+
+        items.sort_by_key(|(index, _)| *index);
+
+        ArrayIter {
+            inner: items.into_iter()
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> ArrayIterMut<'_, 'a> {
+        let mut items = Vec::with_capacity(self.item_count);
+
+        items.extend(self.ints.iter_mut().map(|item| (item.index, ArrayTypeMut::Int(&mut item.item))));
+        items.extend(self.floats.iter_mut().map(|item| (item.index, ArrayTypeMut::Float(&mut item.item))));
+        items.extend(self.bools.iter_mut().map(|item| (item.index, ArrayTypeMut::Bool(&mut item.item))));
+        items.extend(self.strings.iter_mut().map(|item| (item.index, ArrayTypeMut::String(&mut item.item))));
+        items.extend(self.arrays.iter_mut().map(|item| (item.index, ArrayTypeMut::Array(&mut item.item))));
+        items.extend(self.objects.iter_mut().map(|item| (item.index, ArrayTypeMut::Object(&mut item.item))));
+        items.extend(self.nulls.iter_mut().map(|item| (item.index, ArrayTypeMut::Null(&mut item.item))));
+
+        items.sort_by_key(|(index, _)| *index);
+
+        ArrayIterMut {
+            inner: items.into_iter()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.item_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item_count == 0
+    }
+
+    fn fix_index_on_array_item_insertion(&mut self, index: usize) {
+        self.shift_comments(index, true);
+
+        // Every item from the index onwards moves one position forward
+        for item in self.ints.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
+        for item in self.floats.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
+        for item in self.bools.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
+        for item in self.strings.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
+        for item in self.arrays.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
+        for item in self.objects.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
+        for item in self.nulls.iter_mut().filter(|item| item.index >= index) {
+            item.index += 1;
+        }
     }
 
     fn fix_index_on_array_item_deletion(&mut self, index: usize) {
-        if index == self.item_count - 1 {
-            return;
-        }
-
-        let mut index_to_fix = index + 1;
-
-        for _ in 0..self.item_count - index_to_fix {
-            for item in self.ints.iter_mut() {
-                if item.index == index_to_fix {
-                    item.index -= 1;
-                    index_to_fix += 1;
-                    assert!(index_to_fix != self.item_count + 1);
-                    continue;
-                }
-            }
-            for item in self.floats.iter_mut() {
-                if item.index == index_to_fix {
-                    item.index -= 1;
-                    index_to_fix += 1;
-                    assert!(index_to_fix != self.item_count + 1);
-                    continue;
-                }
-            }
-            for item in self.bools.iter_mut() {
-                if item.index == index_to_fix {
-                    item.index -= 1;
-                    index_to_fix += 1;
-                    assert!(index_to_fix != self.item_count + 1);
-                    continue;
-                }
-            }
-            for item in self.strings.iter_mut() {
-                if item.index == index_to_fix {
-                    item.index -= 1;
-                    index_to_fix += 1;
-                    assert!(index_to_fix != self.item_count + 1);
-                    continue;
-                }
-            }
-            for item in self.objects.iter_mut() {
-                if item.index == index_to_fix {
-                    item.index -= 1;
-                    index_to_fix += 1;
-                    assert!(index_to_fix != self.item_count + 1);
-                    continue;
-                }
-            }
-            for item in self.nulls.iter_mut() {
-                if item.index == index_to_fix {
-                    item.index -= 1;
-                    index_to_fix += 1;
-                    assert!(index_to_fix != self.item_count + 1);
-                    continue;
-                }
-            }
+        self.shift_comments(index, false);
+
+        // Every item that came after the removed one moves one position back
+        for item in self.ints.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
+        }
+        for item in self.floats.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
+        }
+        for item in self.bools.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
+        }
+        for item in self.strings.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
+        }
+        for item in self.arrays.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
+        }
+        for item in self.objects.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
+        }
+        for item in self.nulls.iter_mut().filter(|item| item.index > index) {
+            item.index -= 1;
         }
     }
 }
@@ -529,7 +775,7 @@ enum ArrayItemType {
     Null
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ArrayType<'a> {
     Int(i32),
     Float(f32),
@@ -540,7 +786,37 @@ pub enum ArrayType<'a> {
     Null(Null)
 }
 
-#[derive(Debug)]
+impl<'b> ArrayType<'b> {
+    pub fn type_name(&self) -> &'static str {
+        self.to_ref().type_name()
+    }
+
+    pub fn to_mut(&mut self) -> ArrayTypeMut<'_, 'b> {
+        match self {
+            ArrayType::Int(value) => ArrayTypeMut::Int(value),
+            ArrayType::Float(value) => ArrayTypeMut::Float(value),
+            ArrayType::Bool(value) => ArrayTypeMut::Bool(value),
+            ArrayType::String(value) => ArrayTypeMut::String(value),
+            ArrayType::Array(value) => ArrayTypeMut::Array(value),
+            ArrayType::Object(value) => ArrayTypeMut::Object(value),
+            ArrayType::Null(value) => ArrayTypeMut::Null(value)
+        }
+    }
+
+    pub fn to_ref(&self) -> ArrayTypeRef<'_, 'b> {
+        match self {
+            ArrayType::Int(value) => ArrayTypeRef::Int(*value),
+            ArrayType::Float(value) => ArrayTypeRef::Float(*value),
+            ArrayType::Bool(value) => ArrayTypeRef::Bool(*value),
+            ArrayType::String(value) => ArrayTypeRef::String(value),
+            ArrayType::Array(value) => ArrayTypeRef::Array(value),
+            ArrayType::Object(value) => ArrayTypeRef::Object(value),
+            ArrayType::Null(value) => ArrayTypeRef::Null(*value)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum ArrayTypeRef<'a, 'b> {
     Int(i32),
     Float(f32),
@@ -551,8 +827,219 @@ pub enum ArrayTypeRef<'a, 'b> {
     Null(Null)
 }
 
+#[derive(Debug)]
+pub enum ArrayTypeMut<'a, 'b> {
+    Int(&'a mut i32),
+    Float(&'a mut f32),
+    Bool(&'a mut bool),
+    String(&'a mut String),
+    Array(&'a mut JsonArray<'b>),
+    Object(&'a mut JsonObject<'b>),
+    Null(&'a mut Null)
+}
+
+impl<'a, 'b> ArrayTypeRef<'a, 'b> {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ArrayTypeRef::Int(_) => "int",
+            ArrayTypeRef::Float(_) => "float",
+            ArrayTypeRef::Bool(_) => "bool",
+            ArrayTypeRef::String(_) => "string",
+            ArrayTypeRef::Array(_) => "array",
+            ArrayTypeRef::Object(_) => "object",
+            ArrayTypeRef::Null(_) => "null"
+        }
+    }
+}
+
 // The null value in JSON
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Null;
 
+impl<'a> Default for JsonObject<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Default for JsonArray<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Keys<'r, 'a> {
+    inner: ObjectIter<'r, 'a>
+}
+
+impl<'r, 'a> Iterator for Keys<'r, 'a> {
+    type Item = &'r str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+pub struct Values<'r, 'a> {
+    inner: ObjectIter<'r, 'a>
+}
+
+impl<'r, 'a> Iterator for Values<'r, 'a> {
+    type Item = ArrayTypeRef<'r, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+}
 
+pub struct ObjectIter<'r, 'a> {
+    inner: std::vec::IntoIter<(&'r str, ArrayTypeRef<'r, 'a>)>
+}
+
+impl<'r, 'a> Iterator for ObjectIter<'r, 'a> {
+    type Item = (&'r str, ArrayTypeRef<'r, 'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+pub struct ObjectIterMut<'r, 'a> {
+    inner: std::vec::IntoIter<(&'r str, ArrayTypeMut<'r, 'a>)>
+}
+
+impl<'r, 'a> Iterator for ObjectIterMut<'r, 'a> {
+    type Item = (&'r str, ArrayTypeMut<'r, 'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+pub struct ObjectIntoIter<'a> {
+    inner: std::vec::IntoIter<(Cow<'a, str>, ArrayType<'a>)>
+}
+
+impl<'a> Iterator for ObjectIntoIter<'a> {
+    type Item = (Cow<'a, str>, ArrayType<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a> IntoIterator for JsonObject<'a> {
+    type Item = (Cow<'a, str>, ArrayType<'a>);
+    type IntoIter = ObjectIntoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut pairs = Vec::new();
+
+        pairs.extend(self.int_pairs.into_iter().map(|(key, value)| (key, ArrayType::Int(value))));
+        pairs.extend(self.float_pairs.into_iter().map(|(key, value)| (key, ArrayType::Float(value))));
+        pairs.extend(self.bool_pairs.into_iter().map(|(key, value)| (key, ArrayType::Bool(value))));
+        pairs.extend(self.string_pairs.into_iter().map(|(key, value)| (key, ArrayType::String(value))));
+        pairs.extend(self.array_pairs.into_iter().map(|(key, value)| (key, ArrayType::Array(value))));
+        pairs.extend(self.object_pairs.into_iter().map(|(key, value)| (key, ArrayType::Object(value))));
+        pairs.extend(self.null_pairs.into_iter().map(|(key, value)| (key, ArrayType::Null(value))));
+
+        ObjectIntoIter {
+            inner: pairs.into_iter()
+        }
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r JsonObject<'a> {
+    type Item = (&'r str, ArrayTypeRef<'r, 'a>);
+    type IntoIter = ObjectIter<'r, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r mut JsonObject<'a> {
+    type Item = (&'r str, ArrayTypeMut<'r, 'a>);
+    type IntoIter = ObjectIterMut<'r, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct ArrayIter<'r, 'a> {
+    inner: std::vec::IntoIter<(usize, ArrayTypeRef<'r, 'a>)>
+}
+
+impl<'r, 'a> Iterator for ArrayIter<'r, 'a> {
+    type Item = ArrayTypeRef<'r, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, item)| item)
+    }
+}
+
+pub struct ArrayIterMut<'r, 'a> {
+    inner: std::vec::IntoIter<(usize, ArrayTypeMut<'r, 'a>)>
+}
+
+impl<'r, 'a> Iterator for ArrayIterMut<'r, 'a> {
+    type Item = ArrayTypeMut<'r, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, item)| item)
+    }
+}
+
+pub struct ArrayIntoIter<'a> {
+    inner: std::vec::IntoIter<(usize, ArrayType<'a>)>
+}
+
+impl<'a> Iterator for ArrayIntoIter<'a> {
+    type Item = ArrayType<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, item)| item)
+    }
+}
+
+impl<'a> IntoIterator for JsonArray<'a> {
+    type Item = ArrayType<'a>;
+    type IntoIter = ArrayIntoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut items = Vec::with_capacity(self.item_count);
+
+        items.extend(self.ints.into_iter().map(|item| (item.index, ArrayType::Int(item.item))));
+        items.extend(self.floats.into_iter().map(|item| (item.index, ArrayType::Float(item.item))));
+        items.extend(self.bools.into_iter().map(|item| (item.index, ArrayType::Bool(item.item))));
+        items.extend(self.strings.into_iter().map(|item| (item.index, ArrayType::String(item.item))));
+        items.extend(self.arrays.into_iter().map(|item| (item.index, ArrayType::Array(item.item))));
+        items.extend(self.objects.into_iter().map(|item| (item.index, ArrayType::Object(item.item))));
+        items.extend(self.nulls.into_iter().map(|item| (item.index, ArrayType::Null(item.item))));
+
+        items.sort_by_key(|(index, _)| *index);
+
+        ArrayIntoIter {
+            inner: items.into_iter()
+        }
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r JsonArray<'a> {
+    type Item = ArrayTypeRef<'r, 'a>;
+    type IntoIter = ArrayIter<'r, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r mut JsonArray<'a> {
+    type Item = ArrayTypeMut<'r, 'a>;
+    type IntoIter = ArrayIterMut<'r, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
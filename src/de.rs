@@ -0,0 +1,407 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, Visitor, MapAccess, SeqAccess,
+                EnumAccess, VariantAccess, IntoDeserializer, Error as _};
+use serde::forward_to_deserialize_any;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, Null};
+use crate::tokenizer::{tokenize_with, Token, Position};
+use crate::options::{ParseOptions, DEFAULT_MAX_DEPTH};
+use crate::error::SerdeError;
+
+pub fn from_str<T: DeserializeOwned>(contents: &str) -> Result<T, SerdeError> {
+    from_str_with(contents, &ParseOptions::default())
+}
+
+// The duplicate key policy and scalar_root don't apply, as the target type decides
+// which keys and values it accepts. Kept comments are skipped.
+pub fn from_str_with<T: DeserializeOwned>(contents: &str, options: &ParseOptions) -> Result<T, SerdeError> {
+    let tokens = tokenize_with(contents.to_string(), options).map_err(SerdeError::custom)?;
+    let tokens = tokens.into_iter().filter(|(token, _)| !matches!(token, Token::Comment(_))).collect();
+
+    let mut deserializer = Deserializer::new(tokens);
+    deserializer.trailing_commas = options.trailing_commas;
+    deserializer.max_depth = options.max_depth;
+
+    let value = T::deserialize(&mut deserializer)?;
+
+    match deserializer.peek() {
+        (Token::Eof, _) => Ok(value),
+        (_, position) => Err(SerdeError::at("Unexpected token after the end of the document",
+                                            position))
+    }
+}
+
+// Deserializes Rust values straight from the tokens, without building an ArrayType first
+pub struct Deserializer {
+    tokens: Vec<(Token, Position)>,
+    current: usize,
+    trailing_commas: bool,
+    max_depth: Option<usize>,
+    depth: usize
+}
+
+impl Deserializer {
+    pub (crate) fn new(tokens: Vec<(Token, Position)>) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            trailing_commas: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            depth: 0
+        }
+    }
+
+    fn enter_nested(&mut self) -> Result<(), SerdeError> {
+        self.depth += 1;
+
+        match self.max_depth {
+            Some(max_depth) if self.depth > max_depth => {
+                Err(SerdeError::at(&format!("Nesting deeper than {} levels", max_depth), &self.peek().1))
+            }
+            _ => Ok(())
+        }
+    }
+
+    // Whether the next token is the given closing token, to allow a trailing comma before it
+    fn at_end(&self, closing: Token) -> bool {
+        std::mem::discriminant(&self.peek().0) == std::mem::discriminant(&closing)
+    }
+
+    fn peek(&self) -> &(Token, Position) {
+        &self.tokens[self.current]
+    }
+
+    // The last token is always Eof, so we never advance past it
+    fn next(&mut self) -> &(Token, Position) {
+        let token = &self.tokens[self.current];
+
+        if self.current < self.tokens.len() - 1 {
+            self.current += 1;
+        }
+
+        token
+    }
+
+    fn expect(&mut self, expected: Token, message: &str) -> Result<(), SerdeError> {
+        let (token, position) = self.next();
+
+        if std::mem::discriminant(token) == std::mem::discriminant(&expected) {
+            Ok(())
+        } else {
+            Err(SerdeError::at(message, position))
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.peek() {
+            (Token::LeftBrace, _) => {
+                self.enter_nested()?;
+                self.next();
+                let value = visitor.visit_map(Members { de: self, first: true })?;
+                self.expect(Token::RightBrace, "Expected a comma or a right brace")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            (Token::LeftBracket, _) => {
+                self.enter_nested()?;
+                self.next();
+                let value = visitor.visit_seq(Items { de: self, first: true })?;
+                self.expect(Token::RightBracket, "Expected a comma or a right bracket")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            (Token::String(_), _) => {
+                match self.next() {
+                    (Token::String(value), _) => visitor.visit_str(value),
+                    _ => unreachable!()
+                }
+            }
+            (Token::Number(number), position) => {
+                let position = position.clone();
+                let number = number.clone();
+                self.next();
+
+                if !number.contains(['.', 'e', 'E']) {
+                    if let Ok(value) = number.parse::<u64>() {
+                        return visitor.visit_u64(value);
+                    }
+                    if let Ok(value) = number.parse::<i64>() {
+                        return visitor.visit_i64(value);
+                    }
+                }
+
+                match number.parse::<f64>() {
+                    Ok(value) => visitor.visit_f64(value),
+                    Err(_) => Err(SerdeError::at("Invalid number format", &position))
+                }
+            }
+            (Token::Keyword(keyword), _) => {
+                let keyword = keyword.clone();
+                self.next();
+
+                match keyword.as_str() {
+                    "true" => visitor.visit_bool(true),
+                    "false" => visitor.visit_bool(false),
+                    _ => visitor.visit_unit()
+                }
+            }
+            (Token::Eof, position) => Err(SerdeError::at("Unexpected end of file", position)),
+            (_, position) => Err(SerdeError::at("Expected a value", position))
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.peek() {
+            (Token::Keyword(keyword), _) if keyword == "null" => {
+                self.next();
+                visitor.visit_none()
+            }
+            _ => visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str,
+                                                   visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Unit variants are strings, the others are objects with a single member
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str],
+                                         visitor: V) -> Result<V::Value, Self::Error> {
+        match self.peek() {
+            (Token::String(_), _) => {
+                match self.next() {
+                    (Token::String(variant), _) => visitor.visit_enum(variant.clone().into_deserializer()),
+                    _ => unreachable!()
+                }
+            }
+            (Token::LeftBrace, _) => {
+                self.next();
+                let value = visitor.visit_enum(Variant { de: self })?;
+                self.expect(Token::RightBrace, "Expected a right brace after the enum variant")?;
+                Ok(value)
+            }
+            (_, position) => Err(SerdeError::at("Expected a string or an object for an enum",
+                                                position))
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+struct Members<'a> {
+    de: &'a mut Deserializer,
+    first: bool
+}
+
+impl<'de, 'a> MapAccess<'de> for Members<'a> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        if let (Token::RightBrace, _) = self.de.peek() {
+            return Ok(None);
+        }
+
+        if !self.first {
+            self.de.expect(Token::Comma, "Expected a comma or a right brace")?;
+
+            if self.de.trailing_commas && self.de.at_end(Token::RightBrace) {
+                return Ok(None);
+            }
+        }
+        self.first = false;
+
+        match self.de.peek() {
+            (Token::String(_), _) => seed.deserialize(&mut *self.de).map(Some),
+            (_, position) => Err(SerdeError::at("Expected a key", position))
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        self.de.expect(Token::Colon, "Expected a colon")?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct Items<'a> {
+    de: &'a mut Deserializer,
+    first: bool
+}
+
+impl<'de, 'a> SeqAccess<'de> for Items<'a> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        if let (Token::RightBracket, _) = self.de.peek() {
+            return Ok(None);
+        }
+
+        if !self.first {
+            self.de.expect(Token::Comma, "Expected a comma or a right bracket")?;
+
+            if self.de.trailing_commas && self.de.at_end(Token::RightBracket) {
+                return Ok(None);
+            }
+        }
+        self.first = false;
+
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Variant<'a> {
+    de: &'a mut Deserializer
+}
+
+impl<'de, 'a> EnumAccess<'de> for Variant<'a> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = match self.de.peek() {
+            (Token::String(_), _) => seed.deserialize(&mut *self.de)?,
+            (_, position) => return Err(SerdeError::at("Expected the enum variant name", position))
+        };
+        self.de.expect(Token::Colon, "Expected a colon")?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for Variant<'a> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str],
+                                       visitor: V) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+// Deserialize implementations for the data structures
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = ArrayType<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(ArrayType::Bool(value))
+    }
+
+    // Ints that don't fit are kept as floats, like in load
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        match std::convert::TryFrom::try_from(value) {
+            Ok(value) => Ok(ArrayType::Int(value)),
+            Err(_) => Ok(ArrayType::Float(value as f32))
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        match std::convert::TryFrom::try_from(value) {
+            Ok(value) => Ok(ArrayType::Int(value)),
+            Err(_) => Ok(ArrayType::Float(value as f32))
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(ArrayType::Float(value as f32))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(ArrayType::String(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(ArrayType::String(value))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(ArrayType::Null(Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(ArrayType::Null(Null))
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut array = JsonArray::new();
+
+        while let Some(item) = seq.next_element::<ArrayType>()? {
+            array.add(item);
+        }
+
+        Ok(ArrayType::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut object = JsonObject::new();
+
+        while let Some((key, value)) = map.next_entry::<String, ArrayType>()? {
+            object.insert(key, value);
+        }
+
+        Ok(ArrayType::Object(object))
+    }
+}
+
+impl<'de, 'a> Deserialize<'de> for ArrayType<'a> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl<'de, 'a> Deserialize<'de> for JsonObject<'a> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.deserialize_map(ValueVisitor)? {
+            ArrayType::Object(object) => Ok(object),
+            _ => Err(D::Error::custom("Expected an object"))
+        }
+    }
+}
+
+impl<'de, 'a> Deserialize<'de> for JsonArray<'a> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.deserialize_seq(ValueVisitor)? {
+            ArrayType::Array(array) => Ok(array),
+            _ => Err(D::Error::custom("Expected an array"))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Null {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.deserialize_unit(ValueVisitor)? {
+            ArrayType::Null(null) => Ok(null),
+            _ => Err(D::Error::custom("Expected null"))
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::tokenizer::error_position;
+
+// An error found while recovering from errors, see load_str_recovering.
+// The error is the one the strict parser would have returned at this point.
+#[derive(Debug)]
+pub struct Diagnostic {
+    error: Box<dyn Error>,
+    line: i32,
+    column: i32
+}
+
+impl Diagnostic {
+    pub (crate) fn new(error: Box<dyn Error>) -> Self {
+        let (line, column) = error_position(error.as_ref());
+
+        Self {
+            error,
+            line,
+            column
+        }
+    }
+
+    pub fn error(&self) -> &dyn Error {
+        self.error.as_ref()
+    }
+
+    pub fn line(&self) -> i32 {
+        self.line
+    }
+
+    pub fn column(&self) -> i32 {
+        self.column
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(formatter)
+    }
+}
@@ -0,0 +1,154 @@
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef};
+use crate::traits::ToJson;
+use crate::pointer::escape;
+use crate::dumper;
+use crate::patch::longest_common_subsequence;
+
+// Structural differences between two documents. Paths are JSON Pointers;
+// removed and changed values point into the old document, added values into
+// the new one.
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added { path: String, value: ArrayType<'static> },
+    Removed { path: String, value: ArrayType<'static> },
+    Changed { path: String, old: ArrayType<'static>, new: ArrayType<'static> }
+}
+
+// How array items are matched up. Index compares items at the same position,
+// while Lcs keeps the longest common subsequence of items in place, so that an
+// insertion at the front doesn't show up as a change of every item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayAlignment {
+    #[default]
+    Index,
+    Lcs
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } |
+            Change::Removed { path, .. } |
+            Change::Changed { path, .. } => path
+        }
+    }
+}
+
+pub fn diff(old: &JsonObject, new: &JsonObject) -> Vec<Change> {
+    diff_with(old, new, ArrayAlignment::default())
+}
+
+pub fn diff_with(old: &JsonObject, new: &JsonObject, alignment: ArrayAlignment) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_objects("", old, new, alignment, &mut changes);
+
+    changes
+}
+
+// Renders the changes like a unified diff, with a header line per change
+// and every line of the pretty printed values prefixed with "-" or "+"
+pub fn render_diff(changes: &[Change]) -> String {
+    let mut output = String::new();
+
+    for change in changes {
+        output.push_str(&format!("@@ {} @@\n", change.path()));
+
+        match change {
+            Change::Added { value, .. } => render_value('+', value, &mut output),
+            Change::Removed { value, .. } => render_value('-', value, &mut output),
+            Change::Changed { old, new, .. } => {
+                render_value('-', old, &mut output);
+                render_value('+', new, &mut output);
+            }
+        }
+    }
+
+    output
+}
+
+fn render_value(prefix: char, value: &ArrayType, output: &mut String) {
+    let mut text = String::new();
+    dumper::dump_value(value.to_ref(), true, 0, &mut text);
+
+    for line in text.lines() {
+        output.push(prefix);
+        output.push(' ');
+        output.push_str(line);
+        output.push('\n');
+    }
+}
+
+fn diff_values(path: &str, old: ArrayTypeRef, new: ArrayTypeRef,
+               alignment: ArrayAlignment, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (ArrayTypeRef::Object(old), ArrayTypeRef::Object(new)) => {
+            diff_objects(path, old, new, alignment, changes)
+        }
+        (ArrayTypeRef::Array(old), ArrayTypeRef::Array(new)) => {
+            diff_arrays(path, old, new, alignment, changes)
+        }
+        (old, new) => {
+            if old != new {
+                changes.push(Change::Changed {
+                    path: path.to_string(),
+                    old: old.to_json(),
+                    new: new.to_json()
+                });
+            }
+        }
+    }
+}
+
+fn diff_objects(path: &str, old: &JsonObject, new: &JsonObject,
+                alignment: ArrayAlignment, changes: &mut Vec<Change>) {
+    let mut keys: Vec<&str> = old.keys().chain(new.keys().filter(|key| !old.contains_key(key))).collect();
+    keys.sort_unstable();
+
+    for key in keys {
+        let member_path = format!("{}/{}", path, escape(key));
+
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => diff_values(&member_path, old, new, alignment, changes),
+            (Some(old), None) => changes.push(Change::Removed { path: member_path, value: old.to_json() }),
+            (None, Some(new)) => changes.push(Change::Added { path: member_path, value: new.to_json() }),
+            (None, None) => ()
+        }
+    }
+}
+
+fn diff_arrays(path: &str, old: &JsonArray, new: &JsonArray,
+               alignment: ArrayAlignment, changes: &mut Vec<Change>) {
+    let old_items: Vec<ArrayTypeRef> = old.iter().collect();
+    let new_items: Vec<ArrayTypeRef> = new.iter().collect();
+
+    let common = match alignment {
+        ArrayAlignment::Index => Vec::new(),
+        ArrayAlignment::Lcs => longest_common_subsequence(&old_items, &new_items)
+    };
+
+    // Items between two matched pairs are compared pairwise; the rest are removed or added
+    let mut old_start = 0;
+    let mut new_start = 0;
+
+    let end = (old_items.len(), new_items.len());
+
+    for (old_end, new_end) in common.into_iter().chain(std::iter::once(end)) {
+        let paired = (old_end - old_start).min(new_end - new_start);
+
+        for i in 0..paired {
+            diff_values(&format!("{}/{}", path, old_start + i), old_items[old_start + i],
+                        new_items[new_start + i], alignment, changes);
+        }
+        for (i, item) in old_items.iter().enumerate().take(old_end).skip(old_start + paired) {
+            changes.push(Change::Removed { path: format!("{}/{}", path, i), value: item.to_json() });
+        }
+        for (i, item) in new_items.iter().enumerate().take(new_end).skip(new_start + paired) {
+            changes.push(Change::Added { path: format!("{}/{}", path, i), value: item.to_json() });
+        }
+
+        old_start = old_end + 1;
+        new_start = new_end + 1;
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayTypeRef};
+
+const INDENTATION: &str = "    ";
+
+// Writes a value as JSON text. With pretty set, every member and item goes on
+// its own line. Object keys are sorted, so that the output is reproducible.
+pub (crate) fn dump_value(value: ArrayTypeRef, pretty: bool, level: usize, output: &mut String) {
+    match value {
+        ArrayTypeRef::Int(value) => write!(output, "{}", value).expect("Writing to a string"),
+        ArrayTypeRef::Float(value) => dump_float(value, output),
+        ArrayTypeRef::Bool(value) => write!(output, "{}", value).expect("Writing to a string"),
+        ArrayTypeRef::String(value) => dump_string(value, output),
+        ArrayTypeRef::Array(value) => dump_array(value, pretty, level, output),
+        ArrayTypeRef::Object(value) => dump_object(value, pretty, level, output),
+        ArrayTypeRef::Null(_) => output.push_str("null")
+    }
+}
+
+// Writes the root of a document, with the comments before and after it
+pub (crate) fn dump_document(value: ArrayTypeRef, pretty: bool, output: &mut String) {
+    let (header, footer) = match value {
+        ArrayTypeRef::Object(object) => (object.header_comments(), object.footer_comments()),
+        ArrayTypeRef::Array(array) => (array.header_comments(), array.footer_comments()),
+        _ => (&[][..], &[][..])
+    };
+
+    for comment in header {
+        dump_comment(comment, pretty, output);
+        new_line(pretty, 0, output);
+    }
+
+    dump_value(value, pretty, 0, output);
+
+    for comment in footer {
+        new_line(pretty, 0, output);
+        dump_comment(comment, pretty, output);
+    }
+}
+
+pub (crate) fn dump_object(object: &JsonObject, pretty: bool, level: usize, output: &mut String) {
+    if object.is_empty() && object.trailing_comments().is_empty() {
+        output.push_str("{}");
+        return;
+    }
+
+    let mut members: Vec<_> = object.iter().collect();
+    members.sort_by_key(|(key, _)| *key);
+
+    output.push('{');
+
+    for (i, (key, value)) in members.into_iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        new_line(pretty, level + 1, output);
+
+        for comment in object.comments(key) {
+            dump_comment(comment, pretty, output);
+            new_line(pretty, level + 1, output);
+        }
+
+        dump_string(key, output);
+        output.push(':');
+        if pretty {
+            output.push(' ');
+        }
+        dump_value(value, pretty, level + 1, output);
+    }
+
+    for comment in object.trailing_comments() {
+        new_line(pretty, level + 1, output);
+        dump_comment(comment, pretty, output);
+    }
+
+    new_line(pretty, level, output);
+    output.push('}');
+}
+
+pub (crate) fn dump_array(array: &JsonArray, pretty: bool, level: usize, output: &mut String) {
+    if array.is_empty() && array.trailing_comments().is_empty() {
+        output.push_str("[]");
+        return;
+    }
+
+    output.push('[');
+
+    for (i, item) in array.iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        new_line(pretty, level + 1, output);
+
+        for comment in array.comments(i) {
+            dump_comment(comment, pretty, output);
+            new_line(pretty, level + 1, output);
+        }
+
+        dump_value(item, pretty, level + 1, output);
+    }
+
+    for comment in array.trailing_comments() {
+        new_line(pretty, level + 1, output);
+        dump_comment(comment, pretty, output);
+    }
+
+    new_line(pretty, level, output);
+    output.push(']');
+}
+
+pub (crate) fn dump_string(string: &str, output: &mut String) {
+    dump_quoted(string, '"', output);
+}
+
+// Writes a string between the given quotes, which are escaped inside it
+pub (crate) fn dump_quoted(string: &str, quote: char, output: &mut String) {
+    output.push(quote);
+
+    for character in string.chars() {
+        match character {
+            character if character == quote => {
+                output.push('\\');
+                output.push(quote);
+            }
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            character if (character as u32) < 0x20 => {
+                write!(output, "\\u{:04x}", character as u32).expect("Writing to a string")
+            }
+            character => output.push(character)
+        }
+    }
+
+    output.push(quote);
+}
+
+// Floats always keep a fractional part or an exponent, so that they are read back as floats.
+// NaN and infinity can't be represented in JSON and become null.
+pub (crate) fn dump_float(value: f32, output: &mut String) {
+    if !value.is_finite() {
+        output.push_str("null");
+        return;
+    }
+
+    let number = format!("{:?}", value);
+    output.push_str(&number);
+
+    if !number.contains(['.', 'e', 'E']) {
+        output.push_str(".0");
+    }
+}
+
+// Without line breaks a line comment would swallow the rest of the output,
+// so compact output turns it into a block comment
+fn dump_comment(comment: &str, pretty: bool, output: &mut String) {
+    match comment.strip_prefix("//") {
+        Some(text) if !pretty => {
+            output.push_str("/*");
+            output.push_str(&text.replace("*/", "* /"));
+            output.push_str(" */");
+        }
+        _ => output.push_str(comment)
+    }
+}
+
+pub (crate) fn new_line(pretty: bool, level: usize, output: &mut String) {
+    if pretty {
+        output.push('\n');
+        for _ in 0..level {
+            output.push_str(INDENTATION);
+        }
+    }
+}
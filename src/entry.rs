@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+
+use crate::data_structure::{JsonObject, ArrayType, ArrayTypeRef, ArrayTypeMut};
+
+// A view into a single key of a JsonObject, which may or may not have a value
+pub enum Entry<'r, 'a> {
+    Occupied(OccupiedEntry<'r, 'a>),
+    Vacant(VacantEntry<'r, 'a>)
+}
+
+impl<'r, 'a> Entry<'r, 'a> {
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key()
+        }
+    }
+
+    pub fn or_insert(self, default: ArrayType<'a>) -> ArrayTypeMut<'r, 'a> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default)
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> ArrayTypeMut<'r, 'a>
+    where
+        F: FnOnce() -> ArrayType<'a>
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default())
+        }
+    }
+
+    pub fn and_modify<F>(mut self, modify: F) -> Self
+    where
+        F: FnOnce(ArrayTypeMut<'_, 'a>)
+    {
+        if let Entry::Occupied(entry) = &mut self {
+            modify(entry.get_mut());
+        }
+        self
+    }
+}
+
+pub struct OccupiedEntry<'r, 'a> {
+    object: &'r mut JsonObject<'a>,
+    key: Cow<'a, str>
+}
+
+impl<'r, 'a> OccupiedEntry<'r, 'a> {
+    pub (crate) fn new(object: &'r mut JsonObject<'a>, key: Cow<'a, str>) -> Self {
+        Self {
+            object,
+            key
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn get(&self) -> ArrayTypeRef<'_, 'a> {
+        self.object.get(&self.key).expect("Entry is occupied")
+    }
+
+    pub fn get_mut(&mut self) -> ArrayTypeMut<'_, 'a> {
+        self.object.get_mut(&self.key).expect("Entry is occupied")
+    }
+
+    pub fn into_mut(self) -> ArrayTypeMut<'r, 'a> {
+        self.object.get_mut(&self.key).expect("Entry is occupied")
+    }
+
+    // The new value doesn't have to be of the same type as the old one
+    pub fn insert(&mut self, value: ArrayType<'a>) -> ArrayType<'a> {
+        self.object.insert(self.key.clone(), value).expect("Entry is occupied")
+    }
+
+    pub fn remove(self) -> ArrayType<'a> {
+        self.object.delete(&self.key).expect("Entry is occupied")
+    }
+}
+
+pub struct VacantEntry<'r, 'a> {
+    object: &'r mut JsonObject<'a>,
+    key: Cow<'a, str>
+}
+
+impl<'r, 'a> VacantEntry<'r, 'a> {
+    pub (crate) fn new(object: &'r mut JsonObject<'a>, key: Cow<'a, str>) -> Self {
+        Self {
+            object,
+            key
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn insert(self, value: ArrayType<'a>) -> ArrayTypeMut<'r, 'a> {
+        self.object.insert(self.key.clone(), value);
+        self.object.get_mut(&self.key).expect("Value was just inserted")
+    }
+}
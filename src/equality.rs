@@ -0,0 +1,185 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef};
+
+// Equality is structural: key order in objects doesn't matter, array order does.
+// By default an int never equals a float; the *_numeric methods compare numbers by value.
+
+impl<'a> JsonObject<'a> {
+    pub fn eq_numeric(&self, other: &JsonObject) -> bool {
+        objects_equal(self, other, true)
+    }
+}
+
+impl<'a> JsonArray<'a> {
+    pub fn eq_numeric(&self, other: &JsonArray) -> bool {
+        arrays_equal(self, other, true)
+    }
+}
+
+impl<'a> ArrayType<'a> {
+    pub fn eq_numeric(&self, other: &ArrayType) -> bool {
+        values_equal(self.to_ref(), other.to_ref(), true)
+    }
+}
+
+impl<'r, 'a> ArrayTypeRef<'r, 'a> {
+    pub fn eq_numeric(&self, other: &ArrayTypeRef) -> bool {
+        values_equal(*self, *other, true)
+    }
+}
+
+impl<'a, 'b> PartialEq<JsonObject<'b>> for JsonObject<'a> {
+    fn eq(&self, other: &JsonObject<'b>) -> bool {
+        objects_equal(self, other, false)
+    }
+}
+
+impl<'a> Eq for JsonObject<'a> {}
+
+impl<'a, 'b> PartialEq<JsonArray<'b>> for JsonArray<'a> {
+    fn eq(&self, other: &JsonArray<'b>) -> bool {
+        arrays_equal(self, other, false)
+    }
+}
+
+impl<'a> Eq for JsonArray<'a> {}
+
+impl<'a, 'b> PartialEq<ArrayType<'b>> for ArrayType<'a> {
+    fn eq(&self, other: &ArrayType<'b>) -> bool {
+        values_equal(self.to_ref(), other.to_ref(), false)
+    }
+}
+
+impl<'a> Eq for ArrayType<'a> {}
+
+impl<'r, 'a, 's, 'b> PartialEq<ArrayTypeRef<'s, 'b>> for ArrayTypeRef<'r, 'a> {
+    fn eq(&self, other: &ArrayTypeRef<'s, 'b>) -> bool {
+        values_equal(*self, *other, false)
+    }
+}
+
+impl<'r, 'a> Eq for ArrayTypeRef<'r, 'a> {}
+
+// Object members are hashed independently and then combined with an
+// order-independent sum, so that equal objects hash equally
+impl<'a> Hash for JsonObject<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut sum: u64 = 0;
+
+        for (key, value) in self.iter() {
+            let mut member_hasher = DefaultHasher::new();
+            key.hash(&mut member_hasher);
+            value.hash(&mut member_hasher);
+            sum = sum.wrapping_add(member_hasher.finish());
+        }
+
+        self.len().hash(state);
+        sum.hash(state);
+    }
+}
+
+impl<'a> Hash for JsonArray<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+
+        for item in self.iter() {
+            item.hash(state);
+        }
+    }
+}
+
+impl<'a> Hash for ArrayType<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_ref().hash(state);
+    }
+}
+
+impl<'r, 'a> Hash for ArrayTypeRef<'r, 'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ArrayTypeRef::Int(value) => {
+                0u8.hash(state);
+                value.hash(state);
+            }
+            ArrayTypeRef::Float(value) => {
+                1u8.hash(state);
+                float_bits(*value).hash(state);
+            }
+            ArrayTypeRef::Bool(value) => {
+                2u8.hash(state);
+                value.hash(state);
+            }
+            ArrayTypeRef::String(value) => {
+                3u8.hash(state);
+                value.hash(state);
+            }
+            ArrayTypeRef::Array(value) => {
+                4u8.hash(state);
+                value.hash(state);
+            }
+            ArrayTypeRef::Object(value) => {
+                5u8.hash(state);
+                value.hash(state);
+            }
+            ArrayTypeRef::Null(_) => 6u8.hash(state)
+        }
+    }
+}
+
+fn objects_equal(left: &JsonObject, right: &JsonObject, numeric: bool) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    left.iter().all(|(key, value)| {
+        match right.get(key) {
+            Some(other_value) => values_equal(value, other_value, numeric),
+            None => false
+        }
+    })
+}
+
+fn arrays_equal(left: &JsonArray, right: &JsonArray, numeric: bool) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    left.iter().zip(right.iter()).all(|(item, other_item)| values_equal(item, other_item, numeric))
+}
+
+fn values_equal(left: ArrayTypeRef, right: ArrayTypeRef, numeric: bool) -> bool {
+    match (left, right) {
+        (ArrayTypeRef::Int(left), ArrayTypeRef::Int(right)) => left == right,
+        (ArrayTypeRef::Float(left), ArrayTypeRef::Float(right)) => {
+            float_bits(left) == float_bits(right)
+        }
+        (ArrayTypeRef::Int(int), ArrayTypeRef::Float(float)) |
+        (ArrayTypeRef::Float(float), ArrayTypeRef::Int(int)) => {
+            numeric && f64::from(int) == f64::from(float)
+        }
+        (ArrayTypeRef::Bool(left), ArrayTypeRef::Bool(right)) => left == right,
+        (ArrayTypeRef::String(left), ArrayTypeRef::String(right)) => left == right,
+        (ArrayTypeRef::Array(left), ArrayTypeRef::Array(right)) => {
+            arrays_equal(left, right, numeric)
+        }
+        (ArrayTypeRef::Object(left), ArrayTypeRef::Object(right)) => {
+            objects_equal(left, right, numeric)
+        }
+        (ArrayTypeRef::Null(_), ArrayTypeRef::Null(_)) => true,
+        _ => false
+    }
+}
+
+// Floats are compared by their bits so that equality stays reflexive (NaN equals NaN).
+// Both zeros are the same number in JSON, so they are folded into one.
+fn float_bits(value: f32) -> u32 {
+    if value == 0.0 {
+        0
+    } else if value.is_nan() {
+        f32::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
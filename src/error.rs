@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::tokenizer::Position;
+
+// Error for everything that goes wrong while converting with serde
+#[derive(Debug)]
+pub struct SerdeError {
+    message: String,
+    position: Option<(i32, i32)>
+}
+
+impl SerdeError {
+    pub (crate) fn at(message: &str, position: &Position) -> Self {
+        Self {
+            message: message.to_string(),
+            position: Some((position.line, position.column))
+        }
+    }
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "SerdeError: {}", self.message)?;
+
+        if let Some((line, column)) = self.position {
+            write!(formatter, "\nLine: {}, column: {}", line, column)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for SerdeError {}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self {
+            message: message.to_string(),
+            position: None
+        }
+    }
+}
+
+impl serde::de::Error for SerdeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self {
+            message: message.to_string(),
+            position: None
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+
+// Infers a JSON Schema (draft 2020-12) that all of the samples are valid against.
+// Values found at the same location are merged into one shape, so a member that
+// is an int in one sample and a string in another gets both types. Array items
+// are merged the same way, which also covers arrays of mixed types.
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferOptions {
+    // Strings get an enum when they have at most this many distinct values
+    // and at least one of the values was seen more than once
+    pub max_enum_values: usize
+}
+
+impl Default for InferOptions {
+    fn default() -> Self {
+        InferOptions {
+            max_enum_values: 5
+        }
+    }
+}
+
+// Everything seen at one location of the samples
+#[derive(Debug, Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    number: bool,
+    strings: Option<Strings>,
+    items: Option<Box<Shape>>,  // Set once an array was seen, even an empty one
+    array_items: usize,  // The number of items seen, to tell empty arrays apart
+    object: Option<Properties>
+}
+
+#[derive(Debug, Default)]
+struct Strings {
+    count: usize,
+    values: BTreeSet<String>,
+    too_many: bool  // Stop collecting once there are too many values for an enum
+}
+
+#[derive(Debug, Default)]
+struct Properties {
+    count: usize,  // How many objects were seen
+    members: BTreeMap<String, (Shape, usize)>  // Member shapes and how many objects had them
+}
+
+pub fn infer_schema<'r, 'a: 'r>(samples: impl IntoIterator<Item = &'r JsonObject<'a>>) -> JsonObject<'static> {
+    infer_schema_with(samples, &InferOptions::default())
+}
+
+pub fn infer_schema_with<'r, 'a: 'r>(samples: impl IntoIterator<Item = &'r JsonObject<'a>>,
+                                     options: &InferOptions) -> JsonObject<'static> {
+    let mut shape = Shape::default();
+
+    for sample in samples {
+        shape.add(ArrayTypeRef::Object(sample), options);
+    }
+
+    let mut schema = shape.to_schema();
+    schema.insert("$schema", ArrayType::from(SCHEMA_DIALECT));
+
+    schema
+}
+
+impl Shape {
+    fn add(&mut self, value: ArrayTypeRef, options: &InferOptions) {
+        match value {
+            ArrayTypeRef::Null(_) => self.null = true,
+            ArrayTypeRef::Bool(_) => self.boolean = true,
+            ArrayTypeRef::Int(_) => self.integer = true,
+            ArrayTypeRef::Float(_) => self.number = true,
+            ArrayTypeRef::String(string) => {
+                let strings = self.strings.get_or_insert_with(Strings::default);
+                strings.count += 1;
+
+                if !strings.too_many {
+                    strings.values.insert(string.clone());
+                    strings.too_many = strings.values.len() > options.max_enum_values;
+                }
+            }
+            ArrayTypeRef::Array(array) => {
+                let items = self.items.get_or_insert_with(Box::default);
+                for item in array.iter() {
+                    items.add(item, options);
+                }
+                self.array_items += array.len();
+            }
+            ArrayTypeRef::Object(object) => {
+                let properties = self.object.get_or_insert_with(Properties::default);
+                properties.count += 1;
+
+                for (key, value) in object.iter() {
+                    let (shape, count) = properties.members.entry(key.to_string()).or_default();
+                    shape.add(value, options);
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    fn to_schema(&self) -> JsonObject<'static> {
+        let mut schema = JsonObject::new();
+        let mut types = Vec::new();
+
+        if self.null {
+            types.push("null");
+        }
+        if self.boolean {
+            types.push("boolean");
+        }
+        if self.number {
+            types.push("number");
+        } else if self.integer {
+            types.push("integer");
+        }
+        if self.strings.is_some() {
+            types.push("string");
+        }
+        if self.items.is_some() {
+            types.push("array");
+        }
+        if self.object.is_some() {
+            types.push("object");
+        }
+
+        match types.as_slice() {
+            [] => return schema,  // Nothing was seen, so anything goes
+            [name] => {
+                schema.insert("type", ArrayType::from(*name));
+            }
+            names => {
+                let names: JsonArray = names.iter().map(|name| ArrayType::from(*name)).collect();
+                schema.insert("type", ArrayType::Array(names));
+            }
+        }
+
+        // Enums are only used for strings, optionally nullable, as they would exclude any other value
+        if let Some(strings) = &self.strings {
+            let only_strings = !self.boolean && !self.integer && !self.number &&
+                               self.items.is_none() && self.object.is_none();
+            let is_enum = !strings.too_many && strings.count > strings.values.len();
+
+            if only_strings && is_enum {
+                let mut values: JsonArray = strings.values.iter()
+                    .map(|value| ArrayType::from(value.as_str()))
+                    .collect();
+                if self.null {
+                    values.add(ArrayType::Null(Null));
+                }
+                schema.insert("enum", ArrayType::Array(values));
+            }
+        }
+
+        if let Some(items) = &self.items {
+            if self.array_items > 0 {
+                schema.insert("items", ArrayType::Object(items.to_schema()));
+            }
+        }
+
+        if let Some(properties) = &self.object {
+            let mut members = JsonObject::new();
+            let mut required = JsonArray::new();
+
+            for (key, (shape, count)) in &properties.members {
+                members.insert(key.clone(), ArrayType::Object(shape.to_schema()));
+
+                if *count == properties.count {
+                    required.add(ArrayType::from(key.as_str()));
+                }
+            }
+
+            schema.insert("properties", ArrayType::Object(members));
+            if !required.is_empty() {
+                schema.insert("required", ArrayType::Array(required));
+            }
+        }
+
+        schema
+    }
+}
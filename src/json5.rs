@@ -0,0 +1,400 @@
+use std::error::Error;
+use std::fs::read_to_string;
+
+use crate::tokenizer::{Token, Position, ParseError, InvalidStringError};
+use crate::tokenizer::{advance, build_comment, build_unicode_escape};
+use crate::parser::parse_with;
+use crate::options::ParseOptions;
+use crate::data_structure::{JsonObject, JsonArray, ArrayTypeRef};
+use crate::dumper::{dump_float, dump_quoted, new_line};
+
+// JSON5 (https://spec.json5.org): JSON with unquoted keys, single quoted and multi-line
+// strings, trailing commas, comments, hex numbers, leading or trailing decimal points,
+// plus signs, Infinity and NaN. The tokens are the same as for JSON, so the regular
+// parser builds the objects. Unicode escapes in unquoted keys aren't supported.
+
+pub fn load_json5<'object>(file: String) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let contents = read_to_string(file)?;
+
+    load_json5_str(contents)
+}
+
+pub fn load_json5_str<'object>(contents: String) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let tokens = tokenize_json5(contents)?;
+
+    let options = ParseOptions { trailing_commas: true, ..ParseOptions::default() };
+
+    parse_with(tokens, &options)
+}
+
+pub fn dump_json5(object: JsonObject) -> String {
+    let mut output = String::new();
+    dump_object(&object, false, 0, &mut output);
+
+    output
+}
+
+pub fn dump_json5_pretty(object: JsonObject) -> String {
+    let mut output = String::new();
+    dump_object(&object, true, 0, &mut output);
+
+    output
+}
+
+// Tokenizing
+
+fn tokenize_json5(contents: String) -> Result<Vec<(Token, Position)>, Box<dyn Error>> {
+    let mut tokens: Vec<(Token, Position)> = Vec::new();
+    let mut last_word: Option<String> = None;  // The last token if it was an unquoted word
+
+    let mut current_character: Option<char> = None;
+    let mut current_position = Position { index: -1, line: 1, column: -1 };
+
+    let contents_chars: Vec<char> = contents.chars().collect();
+
+    advance(&contents_chars, &mut current_character, &mut current_position);
+
+    while let Some(character) = current_character {
+        let start_position = current_position.clone();
+
+        match character {
+            '{' => tokens.push((Token::LeftBrace, start_position)),
+            '}' => tokens.push((Token::RightBrace, start_position)),
+            '[' => tokens.push((Token::LeftBracket, start_position)),
+            ']' => tokens.push((Token::RightBracket, start_position)),
+            ',' => tokens.push((Token::Comma, start_position)),
+
+            // Any word followed by a colon is a key, even true, null or Infinity
+            ':' => {
+                if let (Some(word), Some(last)) = (last_word.take(), tokens.last_mut()) {
+                    last.0 = Token::Identifier(word);
+                }
+                tokens.push((Token::Colon, start_position));
+            }
+
+            '"' | '\'' => {
+                let string = build_string(&contents_chars, &mut current_character,
+                                          &mut current_position)?;
+                tokens.push((Token::String(string), start_position));
+            }
+
+            '/' => {
+                build_comment(&contents_chars, &mut current_character, &mut current_position)?;
+                advance(&contents_chars, &mut current_character, &mut current_position);
+                continue;  // Comments don't change what the last token was
+            }
+
+            '-' | '+' | '.' |
+            '0'..='9' => {
+                let number = build_number(&contents_chars, &mut current_character,
+                                          &mut current_position)?;
+                tokens.push((Token::Number(number), start_position));
+
+                last_word = None;
+                continue;
+            }
+
+            character if is_identifier_start(character) => {
+                let word = build_word(&contents_chars, &mut current_character, &mut current_position);
+
+                let token = match word.as_str() {
+                    "true" | "false" | "null" => Token::Keyword(word.clone()),
+                    "Infinity" | "NaN" => Token::Number(word.clone()),
+                    _ => Token::Identifier(word.clone())
+                };
+                tokens.push((token, start_position));
+
+                last_word = Some(word);
+                continue;
+            }
+
+            character if character.is_whitespace() || character == '\u{feff}' => {
+                advance(&contents_chars, &mut current_character, &mut current_position);
+                continue;
+            }
+
+            _ => return Err(Box::new(
+                    ParseError::new(format!("Unidentified character: {}", character),
+                                    current_position.line,
+                                    current_position.column))
+                 )
+        }
+        last_word = None;
+        advance(&contents_chars, &mut current_character, &mut current_position);
+    }
+
+    tokens.push((Token::Eof, current_position));
+
+    Ok(tokens)
+}
+
+fn is_identifier_start(character: char) -> bool {
+    character.is_alphabetic() || character == '$' || character == '_'
+}
+
+fn is_identifier_part(character: char) -> bool {
+    is_identifier_start(character) || character.is_alphanumeric()
+}
+
+fn build_word(contents_chars: &[char], current_character: &mut Option<char>,
+              current_position: &mut Position) -> String {
+    let mut word = String::new();
+
+    while let Some(character) = current_character.filter(|character| is_identifier_part(*character)) {
+        word.push(character);
+        advance(contents_chars, current_character, current_position);
+    }
+
+    word
+}
+
+// Strings end at the quote they started with. A backslash before a line break
+// continues the string on the next line.
+fn build_string(contents_chars: &[char], current_character: &mut Option<char>,
+                current_position: &mut Position) -> Result<String, Box<dyn Error>> {
+    let quote = current_character.unwrap();
+    let mut string = String::new();
+
+    loop {
+        advance(contents_chars, current_character, current_position);
+
+        let character = match *current_character {
+            Some(character) => character,
+            None => return Err(Box::new(InvalidStringError::new("Missing closing quotes",
+                                                                current_position.line,
+                                                                current_position.column)))
+        };
+
+        match character {
+            character if character == quote => break,
+            '\n' | '\r' => return Err(Box::new(InvalidStringError::new("Unexpected end of string",
+                                                                       current_position.line,
+                                                                       current_position.column))),
+            '\\' => {
+                advance(contents_chars, current_character, current_position);
+
+                match *current_character {
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('v') => string.push('\u{b}'),
+                    Some('0') if !next_is_digit(contents_chars, current_position) => string.push('\0'),
+                    Some('x') => {
+                        let character = read_hex_escape(contents_chars, current_character,
+                                                        current_position)?;
+                        string.push(character);
+                    }
+                    Some('u') => {
+                        let character = build_unicode_escape(contents_chars, current_character,
+                                                             current_position)?;
+                        string.push(character);
+                    }
+                    Some('\r') => {
+                        if next_is(contents_chars, current_position, '\n') {
+                            advance(contents_chars, current_character, current_position);
+                        }
+                    }
+                    Some('\n') | Some('\u{2028}') | Some('\u{2029}') => (),
+                    Some('0'..='9') => return Err(Box::new(
+                        InvalidStringError::new("Unknown escape character in string",
+                                                current_position.line,
+                                                current_position.column))
+                    ),
+                    Some(character) => string.push(character),  // Any other character stands for itself
+                    None => return Err(Box::new(InvalidStringError::new("Missing closing quotes",
+                                                                        current_position.line,
+                                                                        current_position.column)))
+                }
+            }
+            character => string.push(character)
+        }
+    }
+
+    Ok(string)
+}
+
+fn next_is(contents_chars: &[char], current_position: &Position, expected: char) -> bool {
+    contents_chars.get(current_position.index as usize + 1) == Some(&expected)
+}
+
+fn next_is_digit(contents_chars: &[char], current_position: &Position) -> bool {
+    contents_chars.get(current_position.index as usize + 1).is_some_and(|character| character.is_ascii_digit())
+}
+
+// Reads the two hex digits after \x
+fn read_hex_escape(contents_chars: &[char], current_character: &mut Option<char>,
+                   current_position: &mut Position) -> Result<char, Box<dyn Error>> {
+    let mut code = 0;
+
+    for _ in 0..2 {
+        advance(contents_chars, current_character, current_position);
+
+        match current_character.and_then(|character| character.to_digit(16)) {
+            Some(digit) => code = code * 16 + digit,
+            None => return Err(Box::new(InvalidStringError::new("Invalid hex escape",
+                                                                current_position.line,
+                                                                current_position.column)))
+        }
+    }
+
+    Ok(char::from_u32(code).expect("Two hex digits are a valid character"))
+}
+
+// Numbers are returned in a form the parser reads: hex numbers are converted to
+// decimal and plus signs are dropped. Decimal points may lead or trail
+// (".5" and "5."), which Rust already accepts.
+fn build_number(contents_chars: &[char], current_character: &mut Option<char>,
+                current_position: &mut Position) -> Result<String, Box<dyn Error>> {
+    let mut lexeme = String::new();
+
+    while let Some(character) = *current_character {
+        let is_exponent_sign = (character == '+' || character == '-') &&
+                               (lexeme.is_empty() || (lexeme.ends_with(['e', 'E']) && !is_hex(&lexeme)));
+
+        if character.is_ascii_alphanumeric() || character == '.' || is_exponent_sign {
+            lexeme.push(character);
+            advance(contents_chars, current_character, current_position);
+        } else {
+            break;
+        }
+    }
+
+    let (negative, body) = match lexeme.strip_prefix('-') {
+        Some(body) => (true, body),
+        None => (false, lexeme.strip_prefix('+').unwrap_or(&lexeme))
+    };
+    let sign = if negative { "-" } else { "" };
+
+    let number = if body == "Infinity" {
+        format!("{}Infinity", sign)
+    } else if body == "NaN" {
+        body.to_string()
+    } else if is_hex(body) {
+        match u64::from_str_radix(&body[2..], 16) {
+            Ok(value) => format!("{}{}", sign, value),
+            Err(_) => return Err(invalid_number(current_position))
+        }
+    } else if is_decimal(body) {
+        format!("{}{}", sign, body)
+    } else {
+        return Err(invalid_number(current_position));
+    };
+
+    Ok(number)
+}
+
+fn is_hex(number: &str) -> bool {
+    let number = number.trim_start_matches(['-', '+']);
+    number.starts_with("0x") || number.starts_with("0X")
+}
+
+// Digits with an optional decimal point and exponent, where the integer part
+// has no leading zeros and at least one side of the point has digits
+fn is_decimal(number: &str) -> bool {
+    let (mantissa, exponent) = match number.find(['e', 'E']) {
+        Some(index) => (&number[..index], Some(&number[index + 1..])),
+        None => (number, None)
+    };
+
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (mantissa, "")
+    };
+
+    let all_digits = |part: &str| part.chars().all(|character| character.is_ascii_digit());
+    let exponent_valid = exponent.is_none_or(|exponent| {
+        let digits = exponent.trim_start_matches(['+', '-']);
+        exponent.len() - digits.len() <= 1 && !digits.is_empty() && all_digits(digits)
+    });
+
+    all_digits(integer) && all_digits(fraction) && !(integer.is_empty() && fraction.is_empty()) &&
+    !(integer.len() > 1 && integer.starts_with('0')) && exponent_valid
+}
+
+fn invalid_number(current_position: &Position) -> Box<dyn Error> {
+    Box::new(ParseError::new("Invalid number format".to_string(),
+                             current_position.line,
+                             current_position.column))
+}
+
+// Dumping. Keys are only quoted when they aren't identifiers, and strings use
+// single quotes when that avoids escaping double quotes.
+
+fn dump_value(value: ArrayTypeRef, pretty: bool, level: usize, output: &mut String) {
+    match value {
+        ArrayTypeRef::Float(value) if value.is_nan() => output.push_str("NaN"),
+        ArrayTypeRef::Float(value) if value.is_infinite() => {
+            output.push_str(if value > 0.0 { "Infinity" } else { "-Infinity" })
+        }
+        ArrayTypeRef::Float(value) => dump_float(value, output),
+        ArrayTypeRef::String(value) => dump_string(value, output),
+        ArrayTypeRef::Array(value) => dump_array(value, pretty, level, output),
+        ArrayTypeRef::Object(value) => dump_object(value, pretty, level, output),
+        value => crate::dumper::dump_value(value, pretty, level, output)
+    }
+}
+
+fn dump_object(object: &JsonObject, pretty: bool, level: usize, output: &mut String) {
+    if object.is_empty() {
+        output.push_str("{}");
+        return;
+    }
+
+    let mut members: Vec<_> = object.iter().collect();
+    members.sort_by_key(|(key, _)| *key);
+
+    output.push('{');
+
+    for (i, (key, value)) in members.into_iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        new_line(pretty, level + 1, output);
+
+        let is_identifier = key.chars().next().is_some_and(is_identifier_start) &&
+                            key.chars().all(is_identifier_part);
+        if is_identifier {
+            output.push_str(key);
+        } else {
+            dump_string(key, output);
+        }
+
+        output.push(':');
+        if pretty {
+            output.push(' ');
+        }
+        dump_value(value, pretty, level + 1, output);
+    }
+
+    new_line(pretty, level, output);
+    output.push('}');
+}
+
+fn dump_array(array: &JsonArray, pretty: bool, level: usize, output: &mut String) {
+    if array.is_empty() {
+        output.push_str("[]");
+        return;
+    }
+
+    output.push('[');
+
+    for (i, item) in array.iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        new_line(pretty, level + 1, output);
+
+        dump_value(item, pretty, level + 1, output);
+    }
+
+    new_line(pretty, level, output);
+    output.push(']');
+}
+
+fn dump_string(string: &str, output: &mut String) {
+    let quote = if string.contains('"') && !string.contains('\'') { '\'' } else { '"' };
+
+    dump_quoted(string, quote, output);
+}
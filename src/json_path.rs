@@ -0,0 +1,967 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+use crate::pointer::escape;
+use crate::regex::Regex;
+
+// JSONPath (RFC 9535) queries, like "$.store.book[?@.price < 10].title".
+// Supported are name, wildcard, index, slice and filter selectors, child and
+// descendant segments and the length(), count(), value(), match() and search() functions.
+// Patterns are matched with the same engine as JSON Schema patterns.
+// Object members are visited in key order, so that results are reproducible.
+
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    segments: Vec<Segment>
+}
+
+// A node selected by a query, along with its location in the document
+#[derive(Debug, Clone)]
+pub struct QueryMatch<'r, 'a> {
+    location: Vec<Location>,
+    value: ArrayTypeRef<'r, 'a>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Location {
+    Member(String),
+    Index(usize)
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    descendant: bool,
+    selectors: Vec<Selector>
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice { start: Option<i64>, end: Option<i64>, step: Option<i64> },
+    Filter(Expression)
+}
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Or(Vec<Expression>),
+    And(Vec<Expression>),
+    Not(Box<Expression>),
+    Comparison(Comparable, Operator, Comparable),
+    Exists(Query),
+    Match(Box<Match>)
+}
+
+// match() must match the whole text, while search() looks for the pattern anywhere in it
+#[derive(Debug, Clone)]
+struct Match {
+    text: Comparable,
+    pattern: Comparable,
+    compiled: Option<Regex>,  // Literal patterns are compiled once, when parsing
+    whole: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+#[derive(Debug, Clone)]
+enum Comparable {
+    Number(f64),
+    Literal(Box<ArrayType<'static>>),
+    Query(Query),  // Always a singular query
+    Function(Function)
+}
+
+#[derive(Debug, Clone)]
+enum Function {
+    Length(Box<Comparable>),
+    Count(Query),
+    Value(Query)
+}
+
+// A query inside a filter, relative to the current node (@) or to the root ($)
+#[derive(Debug, Clone)]
+struct Query {
+    absolute: bool,
+    segments: Vec<Segment>
+}
+
+// The result of evaluating a comparable, where Nothing stands for an empty node list
+#[derive(Clone, Copy)]
+enum Operand<'r, 'a> {
+    Nothing,
+    Number(f64),
+    Value(ArrayTypeRef<'r, 'a>)
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<JsonPath, JsonPathError> {
+        let path_chars: Vec<char> = path.chars().collect();
+        let mut current = 0;
+
+        expect(&path_chars, &mut current, '$')?;
+        let segments = parse_segments(&path_chars, &mut current)?;
+
+        if current < path_chars.len() {
+            return Err(JsonPathError::new("Unexpected character", current));
+        }
+
+        Ok(JsonPath { segments })
+    }
+
+    pub fn query<'r, 'a>(&self, root: ArrayTypeRef<'r, 'a>) -> Vec<QueryMatch<'r, 'a>> {
+        let start = QueryMatch { location: Vec::new(), value: root };
+
+        select(&self.segments, vec![start], root)
+    }
+}
+
+impl<'a> JsonObject<'a> {
+    pub fn query(&self, path: &str) -> Result<Vec<QueryMatch<'_, 'a>>, JsonPathError> {
+        Ok(JsonPath::parse(path)?.query(ArrayTypeRef::Object(self)))
+    }
+}
+
+impl<'a> JsonArray<'a> {
+    pub fn query(&self, path: &str) -> Result<Vec<QueryMatch<'_, 'a>>, JsonPathError> {
+        Ok(JsonPath::parse(path)?.query(ArrayTypeRef::Array(self)))
+    }
+}
+
+impl<'r, 'a> QueryMatch<'r, 'a> {
+    pub fn value(&self) -> ArrayTypeRef<'r, 'a> {
+        self.value
+    }
+
+    // Normalized path, like "$['store']['book'][0]"
+    pub fn path(&self) -> String {
+        let mut path = String::from("$");
+
+        for location in &self.location {
+            match location {
+                Location::Member(name) => {
+                    path.push_str("['");
+                    for character in name.chars() {
+                        match character {
+                            '\'' => path.push_str("\\'"),
+                            '\\' => path.push_str("\\\\"),
+                            '\n' => path.push_str("\\n"),
+                            '\r' => path.push_str("\\r"),
+                            '\t' => path.push_str("\\t"),
+                            '\u{8}' => path.push_str("\\b"),
+                            '\u{c}' => path.push_str("\\f"),
+                            character if (character as u32) < 0x20 => {
+                                path.push_str(&format!("\\u{:04x}", character as u32))
+                            }
+                            character => path.push(character)
+                        }
+                    }
+                    path.push_str("']");
+                }
+                Location::Index(index) => path.push_str(&format!("[{}]", index))
+            }
+        }
+
+        path
+    }
+
+    // The same location as a JSON Pointer, like "/store/book/0"
+    pub fn pointer(&self) -> String {
+        self.location.iter().map(|location| match location {
+            Location::Member(name) => format!("/{}", escape(name)),
+            Location::Index(index) => format!("/{}", index)
+        }).collect()
+    }
+
+    fn child(&self, location: Location, value: ArrayTypeRef<'r, 'a>) -> QueryMatch<'r, 'a> {
+        let mut child_location = self.location.clone();
+        child_location.push(location);
+
+        QueryMatch { location: child_location, value }
+    }
+
+    // Members in key order or items in index order
+    fn children(&self) -> Vec<QueryMatch<'r, 'a>> {
+        match self.value {
+            ArrayTypeRef::Object(object) => {
+                let mut members: Vec<_> = object.iter().collect();
+                members.sort_by_key(|(key, _)| *key);
+
+                members.into_iter()
+                    .map(|(key, value)| self.child(Location::Member(key.to_string()), value))
+                    .collect()
+            }
+            ArrayTypeRef::Array(array) => array.iter().enumerate()
+                .map(|(i, item)| self.child(Location::Index(i), item))
+                .collect(),
+            _ => Vec::new()
+        }
+    }
+}
+
+// Evaluation
+
+fn select<'r, 'a>(segments: &[Segment], nodes: Vec<QueryMatch<'r, 'a>>,
+                  root: ArrayTypeRef<'r, 'a>) -> Vec<QueryMatch<'r, 'a>> {
+    let mut nodes = nodes;
+
+    for segment in segments {
+        let mut selected = Vec::new();
+
+        for node in &nodes {
+            if segment.descendant {
+                for descendant in descendants(node) {
+                    apply_selectors(&segment.selectors, &descendant, root, &mut selected);
+                }
+            } else {
+                apply_selectors(&segment.selectors, node, root, &mut selected);
+            }
+        }
+
+        nodes = selected;
+    }
+
+    nodes
+}
+
+// The node itself followed by all of its descendants, depth first
+fn descendants<'r, 'a>(node: &QueryMatch<'r, 'a>) -> Vec<QueryMatch<'r, 'a>> {
+    let mut result = vec![node.clone()];
+
+    for child in node.children() {
+        result.extend(descendants(&child));
+    }
+
+    result
+}
+
+fn apply_selectors<'r, 'a>(selectors: &[Selector], node: &QueryMatch<'r, 'a>,
+                           root: ArrayTypeRef<'r, 'a>, selected: &mut Vec<QueryMatch<'r, 'a>>) {
+    for selector in selectors {
+        match (selector, node.value) {
+            (Selector::Name(name), ArrayTypeRef::Object(object)) => {
+                if let Some(value) = object.get(name) {
+                    selected.push(node.child(Location::Member(name.clone()), value));
+                }
+            }
+            (Selector::Wildcard, _) => selected.extend(node.children()),
+            (Selector::Index(index), ArrayTypeRef::Array(array)) => {
+                if let Some(i) = normalize_index(*index, array.len()) {
+                    let item = array.get(i).expect("Index was checked");
+                    selected.push(node.child(Location::Index(i), item));
+                }
+            }
+            (Selector::Slice { start, end, step }, ArrayTypeRef::Array(array)) => {
+                for i in slice_indices(*start, *end, *step, array.len()) {
+                    let item = array.get(i).expect("Index was checked");
+                    selected.push(node.child(Location::Index(i), item));
+                }
+            }
+            (Selector::Filter(expression), _) => {
+                for child in node.children() {
+                    if evaluate(expression, child.value, root) {
+                        selected.push(child);
+                    }
+                }
+            }
+            _ => ()
+        }
+    }
+}
+
+fn normalize_index(index: i64, length: usize) -> Option<usize> {
+    let index = if index < 0 { length as i64 + index } else { index };
+
+    if index >= 0 && index < length as i64 {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+fn slice_indices(start: Option<i64>, end: Option<i64>, step: Option<i64>, length: usize) -> Vec<usize> {
+    let length = length as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |index: i64| if index >= 0 { index } else { length + index };
+    let mut indices = Vec::new();
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, length);
+        let upper = normalize(end.unwrap_or(length)).clamp(0, length);
+
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            // A step past the end ends the slice, even if it overflows
+            i = i.saturating_add(step);
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(length - 1)).clamp(-1, length - 1);
+        let lower = normalize(end.unwrap_or(-length - 1)).clamp(-1, length - 1);
+
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i = i.saturating_add(step);
+        }
+    }
+
+    indices
+}
+
+fn evaluate(expression: &Expression, current: ArrayTypeRef, root: ArrayTypeRef) -> bool {
+    match expression {
+        Expression::Or(expressions) => expressions.iter().any(|expression| evaluate(expression, current, root)),
+        Expression::And(expressions) => expressions.iter().all(|expression| evaluate(expression, current, root)),
+        Expression::Not(expression) => !evaluate(expression, current, root),
+        Expression::Comparison(left, operator, right) => {
+            let left = operand(left, current, root);
+            let right = operand(right, current, root);
+
+            match operator {
+                Operator::Equal => equal(left, right),
+                Operator::NotEqual => !equal(left, right),
+                Operator::Less => less(left, right),
+                Operator::LessOrEqual => less(left, right) || equal(left, right),
+                Operator::Greater => less(right, left),
+                Operator::GreaterOrEqual => less(right, left) || equal(left, right)
+            }
+        }
+        Expression::Exists(query) => !run_query(query, current, root).is_empty(),
+        Expression::Match(function) => {
+            let text = match operand(&function.text, current, root) {
+                Operand::Value(ArrayTypeRef::String(text)) => text,
+                _ => return false
+            };
+
+            match (&function.compiled, operand(&function.pattern, current, root)) {
+                (Some(regex), _) => regex.is_match(text),
+                (None, Operand::Value(ArrayTypeRef::String(pattern))) => {
+                    compile_pattern(pattern, function.whole).is_ok_and(|regex| regex.is_match(text))
+                }
+                _ => false
+            }
+        }
+    }
+}
+
+// Patterns of match() are anchored at both ends. They are checked on their own first,
+// so that a pattern like "a)|(b" doesn't become valid once it is wrapped in a group.
+fn compile_pattern(pattern: &str, whole: bool) -> Result<Regex, String> {
+    let regex = Regex::new(pattern)?;
+
+    if whole {
+        Regex::new(&format!("^(?:{})$", pattern))
+    } else {
+        Ok(regex)
+    }
+}
+
+fn run_query<'r, 'a>(query: &Query, current: ArrayTypeRef<'r, 'a>,
+                     root: ArrayTypeRef<'r, 'a>) -> Vec<QueryMatch<'r, 'a>> {
+    let start = if query.absolute { root } else { current };
+
+    select(&query.segments, vec![QueryMatch { location: Vec::new(), value: start }], root)
+}
+
+fn operand<'r, 'a>(comparable: &'r Comparable, current: ArrayTypeRef<'r, 'a>,
+                   root: ArrayTypeRef<'r, 'a>) -> Operand<'r, 'a> {
+    match comparable {
+        Comparable::Number(number) => Operand::Number(*number),
+        Comparable::Literal(value) => Operand::Value(value.to_ref()),
+        Comparable::Query(query) => single_node(run_query(query, current, root)),
+        Comparable::Function(Function::Length(argument)) => {
+            match operand(argument, current, root) {
+                Operand::Value(ArrayTypeRef::String(string)) => Operand::Number(string.chars().count() as f64),
+                Operand::Value(ArrayTypeRef::Array(array)) => Operand::Number(array.len() as f64),
+                Operand::Value(ArrayTypeRef::Object(object)) => Operand::Number(object.len() as f64),
+                _ => Operand::Nothing
+            }
+        }
+        Comparable::Function(Function::Count(query)) => {
+            Operand::Number(run_query(query, current, root).len() as f64)
+        }
+        Comparable::Function(Function::Value(query)) => single_node(run_query(query, current, root))
+    }
+}
+
+fn single_node<'r, 'a>(nodes: Vec<QueryMatch<'r, 'a>>) -> Operand<'r, 'a> {
+    if nodes.len() == 1 {
+        Operand::Value(nodes[0].value)
+    } else {
+        Operand::Nothing
+    }
+}
+
+fn as_number(operand: Operand) -> Option<f64> {
+    match operand {
+        Operand::Number(number) => Some(number),
+        Operand::Value(ArrayTypeRef::Int(value)) => Some(value as f64),
+        Operand::Value(ArrayTypeRef::Float(value)) => Some(value as f64),
+        _ => None
+    }
+}
+
+// Floats are stored as f32, so a number compared with one is rounded to an f32 first.
+// Otherwise a price of 8.95 in a document wouldn't equal the literal 8.95.
+fn as_numbers(left: Operand, right: Operand) -> Option<(f64, f64)> {
+    let narrow = |number: Operand, other: Operand| match (number, other) {
+        (Operand::Number(number), Operand::Value(ArrayTypeRef::Float(_))) => Some(number as f32 as f64),
+        _ => as_number(number)
+    };
+
+    Some((narrow(left, right)?, narrow(right, left)?))
+}
+
+fn equal(left: Operand, right: Operand) -> bool {
+    if let Some((left, right)) = as_numbers(left, right) {
+        return left == right;
+    }
+
+    match (left, right) {
+        (Operand::Nothing, Operand::Nothing) => true,
+        (Operand::Value(left), Operand::Value(right)) => left.eq_numeric(&right),
+        _ => false
+    }
+}
+
+fn less(left: Operand, right: Operand) -> bool {
+    if let Some((left, right)) = as_numbers(left, right) {
+        return left < right;
+    }
+
+    match (left, right) {
+        (Operand::Value(ArrayTypeRef::String(left)), Operand::Value(ArrayTypeRef::String(right))) => left < right,
+        _ => false
+    }
+}
+
+// Parsing
+
+fn parse_segments(path_chars: &[char], current: &mut usize) -> Result<Vec<Segment>, JsonPathError> {
+    let mut segments = Vec::new();
+
+    loop {
+        // Blanks may separate segments, but only if a segment follows
+        let mut next = *current;
+        skip_blanks(path_chars, &mut next);
+
+        match (path_chars.get(next), path_chars.get(next + 1)) {
+            (Some('.'), Some('.')) => {
+                *current = next + 2;
+                let selectors = match path_chars.get(*current) {
+                    Some('[') => parse_bracketed(path_chars, current)?,
+                    _ => vec![parse_shorthand(path_chars, current)?]
+                };
+                segments.push(Segment { descendant: true, selectors });
+            }
+            (Some('.'), _) => {
+                *current = next + 1;
+                let selectors = vec![parse_shorthand(path_chars, current)?];
+                segments.push(Segment { descendant: false, selectors });
+            }
+            (Some('['), _) => {
+                *current = next;
+                let selectors = parse_bracketed(path_chars, current)?;
+                segments.push(Segment { descendant: false, selectors });
+            }
+            _ => break
+        }
+    }
+
+    Ok(segments)
+}
+
+// A wildcard or a member name after a dot
+fn parse_shorthand(path_chars: &[char], current: &mut usize) -> Result<Selector, JsonPathError> {
+    match path_chars.get(*current) {
+        Some('*') => {
+            *current += 1;
+            Ok(Selector::Wildcard)
+        }
+        Some(&character) if is_name_first(character) => {
+            let mut name = String::new();
+            while let Some(&character) = path_chars.get(*current) {
+                if !is_name_first(character) && !character.is_ascii_digit() {
+                    break;
+                }
+                name.push(character);
+                *current += 1;
+            }
+            Ok(Selector::Name(name))
+        }
+        _ => Err(JsonPathError::new("Expected a member name or a wildcard", *current))
+    }
+}
+
+fn is_name_first(character: char) -> bool {
+    character.is_ascii_alphabetic() || character == '_' || !character.is_ascii()
+}
+
+fn parse_bracketed(path_chars: &[char], current: &mut usize) -> Result<Vec<Selector>, JsonPathError> {
+    let mut selectors = Vec::new();
+
+    *current += 1;  // Skip the left bracket
+
+    loop {
+        skip_blanks(path_chars, current);
+        selectors.push(parse_selector(path_chars, current)?);
+        skip_blanks(path_chars, current);
+
+        match path_chars.get(*current) {
+            Some(',') => *current += 1,
+            Some(']') => {
+                *current += 1;
+                break;
+            }
+            _ => return Err(JsonPathError::new("Expected a comma or a right bracket", *current))
+        }
+    }
+
+    Ok(selectors)
+}
+
+fn parse_selector(path_chars: &[char], current: &mut usize) -> Result<Selector, JsonPathError> {
+    match path_chars.get(*current) {
+        Some('\'') | Some('"') => Ok(Selector::Name(parse_string(path_chars, current)?)),
+        Some('*') => {
+            *current += 1;
+            Ok(Selector::Wildcard)
+        }
+        Some('?') => {
+            *current += 1;
+            skip_blanks(path_chars, current);
+            Ok(Selector::Filter(parse_or(path_chars, current)?))
+        }
+        Some(&character) if character == '-' || character == ':' || character.is_ascii_digit() => {
+            let start = parse_optional_int(path_chars, current)?;
+            skip_blanks(path_chars, current);
+
+            if path_chars.get(*current) != Some(&':') {
+                return match start {
+                    Some(index) => Ok(Selector::Index(index)),
+                    None => Err(JsonPathError::new("Expected an index", *current))
+                };
+            }
+            *current += 1;
+            skip_blanks(path_chars, current);
+            let end = parse_optional_int(path_chars, current)?;
+            skip_blanks(path_chars, current);
+
+            let mut step = None;
+            if path_chars.get(*current) == Some(&':') {
+                *current += 1;
+                skip_blanks(path_chars, current);
+                step = parse_optional_int(path_chars, current)?;
+            }
+
+            Ok(Selector::Slice { start, end, step })
+        }
+        _ => Err(JsonPathError::new("Expected a selector", *current))
+    }
+}
+
+// Integers don't have leading zeros, and "-0" isn't allowed either. They must be
+// exact in an IEEE 754 double, like RFC 9535 asks: from -(2^53 - 1) to 2^53 - 1.
+const MAX_INT: i64 = (1 << 53) - 1;
+
+fn parse_optional_int(path_chars: &[char], current: &mut usize) -> Result<Option<i64>, JsonPathError> {
+    let start = *current;
+    let mut number = String::new();
+
+    if path_chars.get(*current) == Some(&'-') {
+        number.push('-');
+        *current += 1;
+    }
+    while let Some(&character) = path_chars.get(*current) {
+        if !character.is_ascii_digit() {
+            break;
+        }
+        number.push(character);
+        *current += 1;
+    }
+
+    if number.is_empty() {
+        return Ok(None);
+    }
+
+    let digits = number.trim_start_matches('-');
+    if digits.is_empty() || digits.len() > 1 && digits.starts_with('0') || number == "-0" {
+        return Err(JsonPathError::new("Invalid integer", start));
+    }
+
+    match number.parse::<i64>() {
+        Ok(value) if (-MAX_INT..=MAX_INT).contains(&value) => Ok(Some(value)),
+        _ => Err(JsonPathError::new("Integer out of range", start))
+    }
+}
+
+fn parse_string(path_chars: &[char], current: &mut usize) -> Result<String, JsonPathError> {
+    let start = *current;
+    let quote = path_chars[*current];
+    let mut string = String::new();
+
+    *current += 1;
+
+    loop {
+        let character = match path_chars.get(*current) {
+            Some(&character) => character,
+            None => return Err(JsonPathError::new("Unterminated string", start))
+        };
+        *current += 1;
+
+        if character == quote {
+            break;
+        }
+
+        if character != '\\' {
+            string.push(character);
+            continue;
+        }
+
+        let escaped = match path_chars.get(*current) {
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('/') => '/',
+            Some('\\') => '\\',
+            Some('\'') if quote == '\'' => '\'',
+            Some('"') if quote == '"' => '"',
+            Some('u') => {
+                *current += 1;
+                string.push(parse_unicode_escape(path_chars, current)?);
+                continue;
+            }
+            _ => return Err(JsonPathError::new("Invalid escape sequence", *current))
+        };
+        string.push(escaped);
+        *current += 1;
+    }
+
+    Ok(string)
+}
+
+// Reads the hex digits after "\u", including a following low surrogate
+fn parse_unicode_escape(path_chars: &[char], current: &mut usize) -> Result<char, JsonPathError> {
+    let start = *current;
+    let high = read_hex(path_chars, current)?;
+
+    if !(0xD800..0xDC00).contains(&high) {
+        return char::from_u32(high).ok_or_else(|| JsonPathError::new("Invalid unicode escape", start));
+    }
+
+    if path_chars.get(*current) != Some(&'\\') || path_chars.get(*current + 1) != Some(&'u') {
+        return Err(JsonPathError::new("Missing low surrogate", start));
+    }
+    *current += 2;
+    let low = read_hex(path_chars, current)?;
+
+    if !(0xDC00..0xE000).contains(&low) {
+        return Err(JsonPathError::new("Invalid low surrogate", start));
+    }
+
+    char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+        .ok_or_else(|| JsonPathError::new("Invalid unicode escape", start))
+}
+
+fn read_hex(path_chars: &[char], current: &mut usize) -> Result<u32, JsonPathError> {
+    let digits: String = path_chars.iter().skip(*current).take(4).collect();
+
+    if digits.len() != 4 || !digits.chars().all(|character| character.is_ascii_hexdigit()) {
+        return Err(JsonPathError::new("Expected 4 hex digits", *current));
+    }
+    *current += 4;
+
+    Ok(u32::from_str_radix(&digits, 16).expect("Digits were checked"))
+}
+
+fn parse_or(path_chars: &[char], current: &mut usize) -> Result<Expression, JsonPathError> {
+    let mut expressions = vec![parse_and(path_chars, current)?];
+
+    while consume(path_chars, current, "||") {
+        expressions.push(parse_and(path_chars, current)?);
+    }
+
+    if expressions.len() == 1 {
+        Ok(expressions.remove(0))
+    } else {
+        Ok(Expression::Or(expressions))
+    }
+}
+
+fn parse_and(path_chars: &[char], current: &mut usize) -> Result<Expression, JsonPathError> {
+    let mut expressions = vec![parse_basic(path_chars, current)?];
+
+    while consume(path_chars, current, "&&") {
+        expressions.push(parse_basic(path_chars, current)?);
+    }
+
+    if expressions.len() == 1 {
+        Ok(expressions.remove(0))
+    } else {
+        Ok(Expression::And(expressions))
+    }
+}
+
+// A negation, a parenthesized expression, an existence test or a comparison
+fn parse_basic(path_chars: &[char], current: &mut usize) -> Result<Expression, JsonPathError> {
+    skip_blanks(path_chars, current);
+    let start = *current;
+
+    if consume(path_chars, current, "!") {
+        skip_blanks(path_chars, current);
+        return Ok(Expression::Not(Box::new(parse_basic(path_chars, current)?)));
+    }
+
+    for (name, whole) in [("match(", true), ("search(", false)] {
+        if consume(path_chars, current, name) {
+            return parse_match(whole, path_chars, current);
+        }
+    }
+
+    if consume(path_chars, current, "(") {
+        let expression = parse_or(path_chars, current)?;
+        skip_blanks(path_chars, current);
+        expect(path_chars, current, ')')?;
+        return Ok(expression);
+    }
+
+    let left = parse_comparable(path_chars, current, false)?;
+
+    let operator = match parse_operator(path_chars, current) {
+        Some(operator) => operator,
+        None => {
+            return match left {
+                Comparable::Query(query) => Ok(Expression::Exists(query)),
+                _ => Err(JsonPathError::new("Expected a comparison", start))
+            };
+        }
+    };
+
+    skip_blanks(path_chars, current);
+    let right = parse_comparable(path_chars, current, true)?;
+
+    Ok(Expression::Comparison(left, operator, right))
+}
+
+fn parse_operator(path_chars: &[char], current: &mut usize) -> Option<Operator> {
+    let operators = [
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("<", Operator::Less),
+        (">", Operator::Greater)
+    ];
+
+    operators.iter()
+        .find(|(text, _)| consume(path_chars, current, text))
+        .map(|(_, operator)| *operator)
+}
+
+// With singular set, queries must select at most one node so that they can be compared.
+// On the left side of a comparison that is only checked once an operator follows.
+fn parse_comparable(path_chars: &[char], current: &mut usize,
+                    singular: bool) -> Result<Comparable, JsonPathError> {
+    let start = *current;
+
+    match path_chars.get(*current) {
+        Some('@') | Some('$') => {
+            let query = parse_query(path_chars, current)?;
+
+            let is_singular = query.segments.iter().all(|segment| {
+                !segment.descendant && segment.selectors.len() == 1 &&
+                matches!(segment.selectors[0], Selector::Name(_) | Selector::Index(_))
+            });
+            let followed_by_operator = {
+                let mut next = *current;
+                skip_blanks(path_chars, &mut next);
+                parse_operator(path_chars, &mut next).is_some()
+            };
+
+            if !is_singular && (singular || followed_by_operator) {
+                return Err(JsonPathError::new("Only singular queries can be compared", start));
+            }
+
+            Ok(Comparable::Query(query))
+        }
+        Some('\'') | Some('"') => {
+            let string = parse_string(path_chars, current)?;
+            Ok(Comparable::Literal(Box::new(ArrayType::String(string))))
+        }
+        Some(&character) if character == '-' || character.is_ascii_digit() => {
+            Ok(Comparable::Number(parse_number(path_chars, current)?))
+        }
+        Some(&character) if character.is_ascii_lowercase() => {
+            let mut name = String::new();
+            while let Some(&character) = path_chars.get(*current) {
+                if !character.is_ascii_lowercase() && !character.is_ascii_digit() && character != '_' {
+                    break;
+                }
+                name.push(character);
+                *current += 1;
+            }
+
+            match name.as_str() {
+                "true" => Ok(Comparable::Literal(Box::new(ArrayType::Bool(true)))),
+                "false" => Ok(Comparable::Literal(Box::new(ArrayType::Bool(false)))),
+                "null" => Ok(Comparable::Literal(Box::new(ArrayType::Null(Null)))),
+                _ => Ok(Comparable::Function(parse_function(&name, start, path_chars, current)?))
+            }
+        }
+        _ => Err(JsonPathError::new("Expected a literal, a query or a function", start))
+    }
+}
+
+// The arguments of match() or search(), which are a test on their own and can't be compared
+fn parse_match(whole: bool, path_chars: &[char], current: &mut usize) -> Result<Expression, JsonPathError> {
+    skip_blanks(path_chars, current);
+    let text = parse_comparable(path_chars, current, true)?;
+    skip_blanks(path_chars, current);
+    expect(path_chars, current, ',')?;
+    skip_blanks(path_chars, current);
+
+    let start = *current;
+    let pattern = parse_comparable(path_chars, current, true)?;
+    let compiled = match &pattern {
+        Comparable::Literal(literal) => match literal.as_ref() {
+            ArrayType::String(pattern) => Some(compile_pattern(pattern, whole)
+                .map_err(|message| JsonPathError::new(&format!("Invalid pattern: {}", message), start))?),
+            _ => None
+        },
+        _ => None
+    };
+
+    skip_blanks(path_chars, current);
+    expect(path_chars, current, ')')?;
+
+    if parse_operator(path_chars, &mut current.clone()).is_some() {
+        return Err(JsonPathError::new("The result of match() or search() can't be compared", *current));
+    }
+
+    Ok(Expression::Match(Box::new(Match { text, pattern, compiled, whole })))
+}
+
+fn parse_query(path_chars: &[char], current: &mut usize) -> Result<Query, JsonPathError> {
+    let absolute = path_chars[*current] == '$';
+    *current += 1;
+
+    Ok(Query { absolute, segments: parse_segments(path_chars, current)? })
+}
+
+fn parse_function(name: &str, start: usize, path_chars: &[char],
+                  current: &mut usize) -> Result<Function, JsonPathError> {
+    expect(path_chars, current, '(')?;
+    skip_blanks(path_chars, current);
+
+    let function = match name {
+        "length" => Function::Length(Box::new(parse_comparable(path_chars, current, true)?)),
+        "count" | "value" => {
+            if !matches!(path_chars.get(*current), Some('@') | Some('$')) {
+                return Err(JsonPathError::new("Expected a query", *current));
+            }
+            let query = parse_query(path_chars, current)?;
+
+            if name == "count" {
+                Function::Count(query)
+            } else {
+                Function::Value(query)
+            }
+        }
+        _ => return Err(JsonPathError::new(&format!("Unknown function: {}", name), start))
+    };
+
+    skip_blanks(path_chars, current);
+    expect(path_chars, current, ')')?;
+
+    Ok(function)
+}
+
+fn parse_number(path_chars: &[char], current: &mut usize) -> Result<f64, JsonPathError> {
+    let start = *current;
+    let mut number = String::new();
+
+    while let Some(&character) = path_chars.get(*current) {
+        if !character.is_ascii_digit() && !['-', '+', '.', 'e', 'E'].contains(&character) {
+            break;
+        }
+        number.push(character);
+        *current += 1;
+    }
+
+    let digits = number.trim_start_matches('-');
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") &&
+       !digits.starts_with("0e") && !digits.starts_with("0E") {
+        return Err(JsonPathError::new("Invalid number", start));
+    }
+
+    match number.parse() {
+        Ok(value) if !number.ends_with('.') && !number.starts_with('.') => Ok(value),
+        _ => Err(JsonPathError::new("Invalid number", start))
+    }
+}
+
+fn skip_blanks(path_chars: &[char], current: &mut usize) {
+    while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = path_chars.get(*current) {
+        *current += 1;
+    }
+}
+
+// Skips the blanks and the text if it comes next
+fn consume(path_chars: &[char], current: &mut usize, text: &str) -> bool {
+    let mut next = *current;
+    skip_blanks(path_chars, &mut next);
+
+    for character in text.chars() {
+        if path_chars.get(next) != Some(&character) {
+            return false;
+        }
+        next += 1;
+    }
+
+    *current = next;
+    true
+}
+
+fn expect(path_chars: &[char], current: &mut usize, character: char) -> Result<(), JsonPathError> {
+    if path_chars.get(*current) == Some(&character) {
+        *current += 1;
+        Ok(())
+    } else {
+        Err(JsonPathError::new(&format!("Expected '{}'", character), *current))
+    }
+}
+
+#[derive(Debug)]
+pub struct JsonPathError {
+    message: String,
+    position: usize  // Character index in the path
+}
+
+impl JsonPathError {
+    fn new(message: &str, position: usize) -> Self {
+        JsonPathError {
+            message: message.to_string(),
+            position
+        }
+    }
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "JsonPathError: {}\nPosition: {}", self.message, self.position)
+    }
+}
+
+impl Error for JsonPathError {}
@@ -1,29 +1,160 @@
 #![allow(unused)]
 
+#[macro_use]
+mod macros;
 mod data_structure;
+mod conversion;
+mod equality;
+mod entry;
+mod options;
+mod comments;
+mod diagnostic;
 mod tokenizer;
 mod parser;
+mod dumper;
+mod json5;
+mod cst;
+mod ndjson;
+mod stream;
+mod traits;
+mod pointer;
+mod patch;
+mod merge_patch;
+mod json_path;
+mod diff;
+mod merge;
+mod regex;
+mod schema;
+mod infer;
+mod codegen;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+mod error;
+#[cfg(feature = "msgpack")]
+mod msgpack;
 
 use std::fs::read_to_string;
 use std::error::Error;
 
-use data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef};
-use tokenizer::{tokenize, ParseError};
+pub use data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, ArrayTypeMut, Null};
+pub use data_structure::{Keys, Values, ObjectIter, ObjectIterMut, ObjectIntoIter};
+pub use data_structure::{ArrayIter, ArrayIterMut, ArrayIntoIter};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use conversion::ConversionError;
+pub use pointer::PointerError;
+pub use patch::{PatchOperation, PatchError, PatchErrorKind, apply_patch, create_patch};
+pub use merge_patch::{merge_patch, create_merge_patch};
+pub use json_path::{JsonPath, QueryMatch, JsonPathError};
+pub use diff::{Change, ArrayAlignment, diff, diff_with, render_diff};
+pub use merge::{MergeStrategy, ArrayMerge, ConflictResolution, MergeError, deep_merge, deep_merge_arrays};
+pub use schema::{JsonSchema, ValidationError, SchemaError};
+pub use infer::{InferOptions, infer_schema, infer_schema_with};
+pub use codegen::{generate_rust_from_schema, generate_rust_from_sample};
+pub use options::{ParseOptions, CommentMode, DuplicateKeys};
+pub use cst::{Document, SyntaxToken, SyntaxKind};
+pub use ndjson::{NdjsonOptions, NdjsonReader, NdjsonWriter, read_ndjson, read_ndjson_with};
+pub use stream::{JsonStream, read_json_stream, read_json_stream_with, read_json_seq, read_json_seq_with};
+pub use json5::{load_json5, load_json5_str, dump_json5, dump_json5_pretty};
+pub use diagnostic::Diagnostic;
+pub use tokenizer::{ParseError, InvalidStringError, EofError, DuplicateKeyError};
+pub use traits::{ToJson, FromJson};
+#[cfg(feature = "derive")]
+pub use rusty_json_derive::{ToJson, FromJson};
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_string_pretty, to_value, Serializer};
+#[cfg(feature = "serde")]
+pub use de::{from_str, from_str_with, Deserializer};
+#[cfg(feature = "serde")]
+pub use error::SerdeError;
+#[cfg(feature = "msgpack")]
+pub use msgpack::{to_msgpack, to_msgpack_value, from_msgpack, from_msgpack_value, MsgpackError};
+use tokenizer::{tokenize, tokenize_with, tokenize_recovering};
+use parser::{parse, parse_with, parse_value_with, find_duplicates, parse_recovering};
 
 pub fn load<'object>(file: String) -> Result<JsonObject<'object>, Box<dyn Error>> {
     let contents = read_to_string(file)?;
-    let tokens = tokenize(contents);
 
-    match tokens {
-        Ok(token_arr) => println!("{:#?}", token_arr),
-        Err(error) => return Err(error)
-    }
+    load_str(contents)
+}
+
+pub fn load_str<'object>(contents: String) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let tokens = tokenize(contents)?;
+
+    parse(tokens)
+}
+
+pub fn load_with<'object>(file: String, options: &ParseOptions) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let contents = read_to_string(file)?;
+
+    load_str_with(contents, options)
+}
+
+pub fn load_str_with<'object>(contents: String,
+                              options: &ParseOptions) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let tokens = tokenize_with(contents, options)?;
+
+    parse_with(tokens, options)
+}
+
+// Loads a document that isn't necessarily an object
+pub fn load_value_with<'object>(file: String, options: &ParseOptions) -> Result<ArrayType<'object>, Box<dyn Error>> {
+    let contents = read_to_string(file)?;
+
+    load_value_str_with(contents, options)
+}
+
+pub fn load_value_str_with<'object>(contents: String,
+                                    options: &ParseOptions) -> Result<ArrayType<'object>, Box<dyn Error>> {
+    let tokens = tokenize_with(contents, options)?;
+
+    parse_value_with(tokens, options)
+}
+
+// Reports every error in a document instead of stopping at the first one. Members
+// and items with errors are left out of the returned object, the rest is kept.
+pub fn load_recovering<'object>(file: String, options: &ParseOptions)
+                                -> Result<(JsonObject<'object>, Vec<Diagnostic>), Box<dyn Error>> {
+    let contents = read_to_string(file)?;
+
+    Ok(load_str_recovering(contents, options))
+}
+
+pub fn load_str_recovering<'object>(contents: String,
+                                    options: &ParseOptions) -> (JsonObject<'object>, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+    let tokens = tokenize_recovering(contents, options, Some(&mut diagnostics))
+        .expect("Errors are collected when recovering");
+
+    let (object, mut diagnostics) = parse_recovering(tokens, options, diagnostics);
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line(), diagnostic.column()));
+
+    (object, diagnostics)
+}
 
-    Ok(JsonObject::new())
+// Lists every repeated key in a document, instead of stopping at the first one
+// like the DuplicateKeys::Error policy does. Other errors still stop the parsing.
+pub fn find_duplicate_keys(contents: String,
+                           options: &ParseOptions) -> Result<Vec<DuplicateKeyError>, Box<dyn Error>> {
+    let tokens = tokenize_with(contents, options)?;
+
+    find_duplicates(tokens, options)
 }
 
 pub fn dump(object: JsonObject) -> String {
-    String::new()
+    let mut output = String::new();
+    dumper::dump_document(ArrayTypeRef::Object(&object), false, &mut output);
+
+    output
+}
+
+pub fn dump_pretty(object: JsonObject) -> String {
+    let mut output = String::new();
+    dumper::dump_document(ArrayTypeRef::Object(&object), true, &mut output);
+
+    output
 }
 
 #[cfg(test)]
@@ -59,10 +190,8 @@ mod tests {
         // println!("{:#?}", array);
 
         let num = array.get(0);
-        if let Ok(value) = num {
-            if let ArrayTypeRef::Int(val) = value {
-                println!("{}", val);
-            }
+        if let Ok(ArrayTypeRef::Int(val)) = num {
+            println!("{}", val);
         }
 
         match array.remove(0) {
@@ -85,6 +214,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn real_life_example() {
         let mut base = JsonObject::new();
         let mut new_base = base.clone();
@@ -110,4 +240,1289 @@ mod tests {
             println!("{:#?}", new_base);
         }
     }
+
+    #[test]
+    fn iteration() {
+        let mut array = JsonArray::new();
+        array.add_int(1);
+        array.add_string(String::from("two"));
+        array.add_bool(true);
+        array.add_array(JsonArray::new());
+        array.add_int(5);
+
+        array.remove(1).expect("Index doesn't exist");
+
+        let mut items = array.iter();
+        assert!(matches!(items.next(), Some(ArrayTypeRef::Int(1))));
+        assert!(matches!(items.next(), Some(ArrayTypeRef::Bool(true))));
+        assert!(matches!(items.next(), Some(ArrayTypeRef::Array(_))));
+        assert!(matches!(items.next(), Some(ArrayTypeRef::Int(5))));
+        assert!(items.next().is_none());
+
+        for item in array.iter_mut() {
+            if let ArrayTypeMut::Int(value) = item {
+                *value *= 10;
+            }
+        }
+
+        let ints: Vec<i32> = array.into_iter()
+            .filter_map(|item| if let ArrayType::Int(value) = item { Some(value) } else { None })
+            .collect();
+        assert_eq!(ints, vec![10, 50]);
+
+        let mut object = JsonObject::new();
+        object.insert_int("age", 18);
+        object.insert_string("name", String::from("Simon"));
+        object.insert_null("something", Null);
+
+        let mut keys: Vec<&str> = object.keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["age", "name", "something"]);
+        assert_eq!(object.values().count(), 3);
+        assert_eq!(object.len(), 3);
+
+        for (key, value) in &mut object {
+            if let ArrayTypeMut::String(value) = value {
+                value.push('!');
+            }
+        }
+        assert_eq!(object.get_string("name").expect("Is none"), "Simon!");
+
+        for (key, value) in object {
+            if key == "age" {
+                assert!(matches!(value, ArrayType::Int(18)));
+            }
+        }
+    }
+
+    #[test]
+    fn entry() {
+        let mut config = JsonObject::new();
+        config.insert_int("retries", 3);
+
+        for key in &["retries", "timeout", "retries"] {
+            config.entry(*key)
+                .and_modify(|value| {
+                    if let ArrayTypeMut::Int(value) = value {
+                        *value += 1;
+                    }
+                })
+                .or_insert(ArrayType::Int(30));
+        }
+        assert_eq!(config.get_int("retries").expect("Is none"), 5);
+        assert_eq!(config.get_int("timeout").expect("Is none"), 30);
+
+        config.entry("name").or_insert_with(|| ArrayType::String(String::from("Simon")));
+        assert_eq!(config.get_string("name").expect("Is none"), "Simon");
+
+        // Replacing a value with one of another type doesn't leave the old one behind
+        if let Entry::Occupied(mut entry) = config.entry("timeout") {
+            assert!(matches!(entry.insert(ArrayType::Bool(false)), ArrayType::Int(30)));
+        }
+        assert!(config.get_int("timeout").is_none());
+        assert!(matches!(config.get("timeout"), Some(ArrayTypeRef::Bool(false))));
+        assert_eq!(config.len(), 3);
+    }
+
+    #[test]
+    fn json_macro() {
+        let name = "Simon";
+        let friends = 4;
+
+        let mut object = json!({
+            "name": name,
+            "age": 18,
+            "height": 1.8,
+            "male": true,
+            "something": null,
+            "friends": friends - 1,
+            "things": [
+                { "name": "guitar", "type": "instrument" },
+                { "name": "Rubik's Cube", "type": "toy", },
+            ],
+            "foo": {
+                "bar": {
+                    "rust": [true, false, null, -1, [],]
+                }
+            },
+            (name): {},
+        });
+
+        assert_eq!(object.len(), 9);
+        assert_eq!(object.get_string("name").expect("Is none"), "Simon");
+        assert_eq!(object.get_int("friends").expect("Is none"), 3);
+        assert!(object.get_null("something").is_some());
+        assert!(object.get_object("Simon").expect("Is none").is_empty());
+
+        let things = object.get_array("things").expect("Is none");
+        assert_eq!(things.len(), 2);
+        if let Ok(ArrayTypeRef::Object(thing)) = things.get(1) {
+            assert_eq!(thing.get_string("type").expect("Is none"), "toy");
+        } else {
+            panic!("Not an object");
+        }
+
+        let mut foo = object.get_object("foo").expect("Is none");
+        let rust = foo.get_object("bar").expect("Is none").get_array("rust").expect("Is none");
+        assert_eq!(rust.len(), 5);
+        assert!(matches!(rust.get(3), Ok(ArrayTypeRef::Int(-1))));
+
+        assert!(matches!(json!(null), ArrayType::Null(_)));
+        assert!(matches!(json!("text"), ArrayType::String(_)));
+        assert!(json!([]).is_empty());
+    }
+
+    #[test]
+    fn conversions() {
+        use std::collections::{HashMap, BTreeMap};
+        use std::convert::{TryFrom, TryInto};
+
+        let mut ages = HashMap::new();
+        ages.insert(String::from("Simon"), 18u8);
+        ages.insert(String::from("Nobody"), 0u8);
+
+        let object = JsonObject::try_from(ArrayType::from(ages.clone())).expect("Not an object");
+        assert_eq!(object.get_int("Simon").expect("Is none"), 18);
+
+        let back: HashMap<String, u8> = ArrayType::Object(object).try_into().expect("Conversion");
+        assert_eq!(back, ages);
+
+        let array = ArrayType::from(vec![Some(1), None, Some(3)]);
+        let back: Vec<Option<i32>> = array.clone().try_into().expect("Conversion");
+        assert_eq!(back, vec![Some(1), None, Some(3)]);
+        assert!(Vec::<i32>::try_from(array).is_err());
+
+        let tuple = ArrayType::from((true, "two", 3.0f64));
+        let back: (bool, String, f32) = tuple.try_into().expect("Conversion");
+        assert_eq!(back, (true, String::from("two"), 3.0));
+
+        let mut sorted = BTreeMap::new();
+        sorted.insert(String::from("a"), vec![1, 2]);
+        assert!(matches!(ArrayType::from(sorted), ArrayType::Object(_)));
+
+        assert!(matches!(ArrayType::from(7usize), ArrayType::Int(7)));
+        assert!(u8::try_from(ArrayType::Int(-1)).is_err());
+        assert_eq!(f64::try_from(ArrayType::Int(2)).expect("Conversion"), 2.0);
+
+        // Ints beyond a JSON int and floats beyond the f32 range are kept as strings
+        let numbers = json!({ "count": 7usize, "id": 5_000_000_000i64, "max": u64::MAX, "huge": 1e300f64 });
+        assert_eq!(numbers.get_string("id").expect("Is none"), "5000000000");
+        assert_eq!(u64::try_from(ArrayType::from(u64::MAX)).expect("Conversion"), u64::MAX);
+        assert_eq!(i64::try_from(ArrayType::from(i64::MIN)).expect("Conversion"), i64::MIN);
+        assert_eq!(f64::try_from(ArrayType::from(1e300f64)).expect("Conversion"), 1e300);
+        assert!(i64::try_from(ArrayType::from("12")).is_err());
+        assert_eq!(dump(numbers),
+                   r#"{"count":7,"huge":"1e300","id":"5000000000","max":"18446744073709551615"}"#);
+
+        assert!(matches!(ArrayType::from(0.1f64), ArrayType::Float(value) if value == 0.1f32));
+        assert!(matches!(ArrayType::from(f64::NEG_INFINITY), ArrayType::Float(value) if value.is_infinite()));
+
+        let error = String::try_from(ArrayType::Int(1)).unwrap_err();
+        assert_eq!(error.to_string(), "ConversionError: expected string, found int");
+
+        // Ints beyond a JSON int go through strings and come back exactly
+        assert_eq!(i64::from_json(5_000_000_000i64.to_json().to_ref()).expect("Convert"), 5_000_000_000);
+        assert_eq!(u64::from_json(u64::MAX.to_json().to_ref()).expect("Convert"), u64::MAX);
+        assert_eq!(i128::from_json(i128::MIN.to_json().to_ref()).expect("Convert"), i128::MIN);
+        assert!(matches!(7u64.to_json(), ArrayType::Int(7)));
+        assert!(i64::from_json(ArrayTypeRef::String(&String::from("7"))).is_err());
+        assert!(u8::from_json(ArrayTypeRef::String(&String::from("300"))).is_err());
+
+        // So do floats beyond the f32 range, instead of becoming infinities that dump as null
+        assert_eq!(1e300f64.to_json(), ArrayType::from("1e300"));
+        assert_eq!(f64::from_json(1e300f64.to_json().to_ref()).expect("Convert"), 1e300);
+        assert_eq!(f64::from_json((-1e39f64).to_json().to_ref()).expect("Convert"), -1e39);
+        assert!(f64::from_json(ArrayTypeRef::String(&String::from("1.5"))).is_err());
+    }
+
+    #[test]
+    fn equality() {
+        use std::collections::HashSet;
+
+        let first = json!({
+            "name": "Simon",
+            "array": [1, 2.5, { "a": null }],
+            "dict": { "x": true, "y": false }
+        });
+        let second = json!({
+            "dict": { "y": false, "x": true },
+            "array": [1, 2.5, { "a": null }],
+            "name": "Simon"
+        });
+        let third = json!({
+            "dict": { "y": false, "x": true },
+            "array": [1.0, 2.5, { "a": null }],
+            "name": "Simon"
+        });
+
+        assert_eq!(first, second);
+        assert_ne!(first, third);
+        assert!(first.eq_numeric(&third));
+        assert_ne!(json!([1, 2]), json!([2, 1]));
+        assert_eq!(json!(-0.0), json!(0.0));
+        assert!(!json!(1).eq_numeric(&json!(1.5)));
+
+        let mut set = HashSet::new();
+        set.insert(first);
+        set.insert(second);
+        set.insert(third);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn load_and_dump() {
+        let mut object = load(String::from("samples/all.json")).expect("Couldn't load");
+
+        assert_eq!(object.len(), 12);
+        assert_eq!(object.get_float("var2").expect("Is none"), 3.1415);
+        assert!(object.get_null("var11").is_some());
+
+        let var12 = object.get_array("var12").expect("Is none");
+        let mut items = var12.iter();
+        assert!(matches!(items.next(), Some(ArrayTypeRef::Bool(true))));
+        assert!(matches!(items.next(), Some(ArrayTypeRef::Int(2))));
+        assert!(matches!(items.next(), Some(ArrayTypeRef::String(_))));
+
+        let reloaded = load_str(dump(object.clone())).expect("Couldn't load");
+        assert_eq!(reloaded, object);
+        let reloaded = load_str(dump_pretty(object.clone())).expect("Couldn't load");
+        assert_eq!(reloaded, object);
+
+        let object = json!({ "b": [1, -2.5e3, "\"quoted\"\n"], "a": {}, "c": 3.0 });
+        assert_eq!(dump(object.clone()), r#"{"a":{},"b":[1,-2500.0,"\"quoted\"\n"],"c":3.0}"#);
+        assert_eq!(dump_pretty(json!({ "a": [1, 2], "b": [] })),
+                   "{\n    \"a\": [\n        1,\n        2\n    ],\n    \"b\": []\n}");
+
+        let escaped = load_str(String::from(r#"{"emoji": "\ud83e\udd17", "tab": "\u0009"}"#))
+            .expect("Couldn't load");
+        assert_eq!(escaped.get_string("emoji").expect("Is none"), "🤗");
+        assert_eq!(escaped.get_string("tab").expect("Is none"), "\t");
+
+        assert!(load(String::from("samples/errors.json")).is_err());
+        assert!(load_str(String::from(r#"{"a": 1,}"#)).is_err());
+        assert!(load_str(String::from(r#"{"a": 1"#)).is_err());
+        assert!(load_str(String::from("[1, 2]")).is_err());
+
+        // Ints beyond an i32 are still valid JSON, and are kept as floats
+        let big = load_str(String::from(r#"{"id": 3000000000, "max": 2147483647}"#)).unwrap();
+        assert_eq!(big.get_float("id"), Some(3e9));
+        assert_eq!(big.get_int("max"), Some(i32::MAX));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        use std::collections::HashMap;
+        use serde::{Serialize, Deserialize};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Kind {
+            Instrument,
+            Toy { fun: u8 },
+            Other(String)
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Thing {
+            name: String,
+            #[serde(rename = "type")]
+            kind: Kind
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Person {
+            name: String,
+            age: u32,
+            height: f64,
+            nickname: Option<String>,
+            things: Vec<Thing>,
+            scores: HashMap<String, i64>,
+            position: (i32, i32)
+        }
+
+        let mut scores = HashMap::new();
+        scores.insert(String::from("chess"), -3);
+
+        let person = Person {
+            name: String::from("Simon"),
+            age: 18,
+            height: 1.5,
+            nickname: None,
+            things: vec![
+                Thing { name: String::from("guitar"), kind: Kind::Instrument },
+                Thing { name: String::from("Rubik's Cube"), kind: Kind::Toy { fun: 10 } },
+                Thing { name: String::from("rock"), kind: Kind::Other(String::from("?")) }
+            ],
+            scores,
+            position: (1, -1)
+        };
+
+        let text = to_string(&person).expect("Serialize");
+        assert_eq!(from_str::<Person>(&text).expect("Deserialize"), person);
+        let text = to_string_pretty(&person).expect("Serialize");
+        assert_eq!(from_str::<Person>(&text).expect("Deserialize"), person);
+
+        let object: JsonObject = from_str(&text).expect("Deserialize");
+        assert_eq!(object.get_int("age").expect("Is none"), 18);
+        assert_eq!(load_str(text).expect("Couldn't load"), object);
+        assert_eq!(to_string(&object).expect("Serialize"), dump(object));
+
+        assert!(from_str::<Person>(r#"{"name": "Simon"}"#).is_err());
+        assert!(from_str::<Vec<i32>>("[1, 2,]").is_err());
+        assert!(from_str::<Vec<i32>>("[1, 2] 3").is_err());
+        assert_eq!(from_str::<Vec<i32>>(" [1, 2] ").expect("Deserialize"), vec![1, 2]);
+
+        // Numbers are written exactly, even those an ArrayType can't hold
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Measure {
+            id: u64,
+            offset: i64,
+            value: f64
+        }
+
+        let measure = Measure { id: 5_000_000_000, offset: i64::MIN, value: 0.1 };
+        let text = to_string(&measure).expect("Serialize");
+        assert_eq!(text, r#"{"id":5000000000,"offset":-9223372036854775808,"value":0.1}"#);
+        assert_eq!(from_str::<Measure>(&text).expect("Deserialize"), measure);
+        assert_eq!(to_string(&u64::MAX).expect("Serialize"), "18446744073709551615");
+        assert_eq!(to_string(&1e300f64).expect("Serialize"), "1e300");
+        assert_eq!(to_string_pretty(&vec![(); 0]).expect("Serialize"), "[]");
+
+        let mut ids = HashMap::new();
+        ids.insert(7u64, true);
+        assert_eq!(to_string(&ids).expect("Serialize"), r#"{"7":true}"#);
+        assert_eq!(to_value(&5_000_000_000u64).expect("Serialize"), ArrayType::from("5000000000"));
+        assert_eq!(from_str::<ArrayType>("3000000000").expect("Deserialize"), ArrayType::Float(3e9));
+
+        let deep = "[".repeat(200_000);
+        assert!(from_str::<ArrayType>(&deep).is_err());
+        assert!(from_str::<JsonObject>(&format!("{{\"a\": {}", deep)).is_err());
+    }
+
+    #[test]
+    fn pointer() {
+        let mut object = json!({
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "m~n": 8,
+            "nested": { "array": [{ "deep": true }] }
+        });
+
+        assert!(matches!(object.pointer(""), Some(ArrayTypeRef::Object(_))));
+        assert!(matches!(object.pointer("/foo/0"), Some(ArrayTypeRef::String(value)) if value == "bar"));
+        assert!(matches!(object.pointer("/"), Some(ArrayTypeRef::Int(0))));
+        assert!(matches!(object.pointer("/a~1b"), Some(ArrayTypeRef::Int(1))));
+        assert!(matches!(object.pointer("/m~0n"), Some(ArrayTypeRef::Int(8))));
+        assert!(matches!(object.pointer("/nested/array/0/deep"), Some(ArrayTypeRef::Bool(true))));
+        assert!(object.pointer("/foo/01").is_none());
+        assert!(object.pointer("/foo/2").is_none());
+        assert!(object.pointer("foo").is_none());
+
+        if let Some(ArrayTypeMut::Bool(deep)) = object.pointer_mut("/nested/array/0/deep") {
+            *deep = false;
+        }
+        assert!(matches!(object.pointer("/nested/array/0/deep"), Some(ArrayTypeRef::Bool(false))));
+
+        object.pointer_insert("/foo/1", ArrayType::Int(5)).expect("Insert");
+        object.pointer_insert("/foo/-", ArrayType::Null(Null)).expect("Insert");
+        assert_eq!(object.get_array("foo").expect("Is none"), json!(["bar", 5, "baz", null]));
+
+        let old = object.pointer_insert("/nested/array", ArrayType::Bool(true)).expect("Insert");
+        assert!(matches!(old, Some(ArrayType::Array(_))));
+        assert!(object.pointer_insert("/missing/key", ArrayType::Int(1)).is_err());
+        assert!(object.pointer_insert("/foo/9", ArrayType::Int(1)).is_err());
+
+        assert!(matches!(object.pointer_remove("/foo/0"), Ok(ArrayType::String(_))));
+        assert!(matches!(object.pointer_remove("/a~1b"), Ok(ArrayType::Int(1))));
+        assert!(object.pointer_remove("/a~1b").is_err());
+        assert!(object.pointer_remove("").is_err());
+        assert_eq!(object.get_array("foo").expect("Is none"), json!([5, "baz", null]));
+
+        let mut value = ArrayType::Array(json!([1, [2, 3]]));
+        assert!(matches!(value.pointer("/1/1"), Some(ArrayTypeRef::Int(3))));
+        value.pointer_insert("", ArrayType::Int(7)).expect("Insert");
+        assert_eq!(value, ArrayType::Int(7));
+    }
+
+    #[test]
+    fn patch() {
+        let mut document = json!({
+            "name": "Simon",
+            "tags": ["a", "b"],
+            "address": { "city": "Paris" }
+        });
+
+        let patch_document = load_str(r#"{"patch": [
+            { "op": "test", "path": "/name", "value": "Simon" },
+            { "op": "add", "path": "/tags/1", "value": "c" },
+            { "op": "remove", "path": "/tags/0" },
+            { "op": "replace", "path": "/address/city", "value": "Lyon" },
+            { "op": "copy", "from": "/address", "path": "/previous" },
+            { "op": "move", "from": "/name", "path": "/address/owner" }
+        ]}"#.to_string()).expect("Load");
+        let patch = Vec::<PatchOperation>::from_json(patch_document.get("patch").expect("Is none"))
+            .expect("Convert");
+
+        apply_patch(&mut document, &patch).expect("Apply");
+        assert_eq!(document, json!({
+            "tags": ["c", "b"],
+            "address": { "city": "Lyon", "owner": "Simon" },
+            "previous": { "city": "Lyon" }
+        }));
+
+        // A failing operation rolls back the ones before it
+        let failing = vec![
+            PatchOperation::Remove { path: "/tags".to_string() },
+            PatchOperation::Test { path: "/previous/city".to_string(), value: ArrayType::from("Paris") }
+        ];
+        let error = apply_patch(&mut document, &failing).expect_err("Test fails");
+        assert_eq!(error.operation(), 1);
+        assert!(document.contains_key("tags"));
+        assert!(apply_patch(&mut document, &[PatchOperation::Move {
+            from: "/address".to_string(),
+            path: "/address/inner".to_string()
+        }]).is_err());
+
+        let from = json!({ "a": 1, "b": [1, 2, 3], "c": { "d": true }, "e/f": null });
+        let to = json!({ "a": 1, "b": [1, 5], "c": { "d": false, "g": "new" } });
+        let generated = create_patch(&from, &to);
+        assert_eq!(generated, vec![
+            PatchOperation::Replace { path: "/b/1".to_string(), value: ArrayType::Int(5) },
+            PatchOperation::Remove { path: "/b/2".to_string() },
+            PatchOperation::Replace { path: "/c/d".to_string(), value: ArrayType::Bool(false) },
+            PatchOperation::Add { path: "/c/g".to_string(), value: ArrayType::from("new") },
+            PatchOperation::Remove { path: "/e~1f".to_string() }
+        ]);
+
+        let mut patched = from.clone();
+        apply_patch(&mut patched, &generated).expect("Apply");
+        assert_eq!(patched, to);
+        assert_eq!(Vec::<PatchOperation>::from_json(generated.to_json().to_ref()).expect("Convert"), generated);
+
+        // Inserting or removing an item in the middle doesn't touch the items after it
+        let from = json!({ "list": [1, 2, 3, 4], "names": ["a", "b", "c"] });
+        let to = json!({ "list": [0, 1, 2, 3, 4], "names": ["a", "c", "d"] });
+        let generated = create_patch(&from, &to);
+        assert_eq!(generated, vec![
+            PatchOperation::Add { path: "/list/0".to_string(), value: ArrayType::Int(0) },
+            PatchOperation::Remove { path: "/names/1".to_string() },
+            PatchOperation::Add { path: "/names/2".to_string(), value: ArrayType::from("d") }
+        ]);
+
+        let mut patched = from.clone();
+        apply_patch(&mut patched, &generated).expect("Apply");
+        assert_eq!(patched, to);
+
+        // Long arrays are aligned on their common start and end, without a table of every pair of items
+        let mut from = JsonObject::new();
+        from.insert("list", ArrayType::Array((0..20_000).collect()));
+        let mut to = JsonObject::new();
+        to.insert("list", ArrayType::Array((0..10_000).chain(std::iter::once(-1)).chain(10_000..20_000).collect()));
+        assert_eq!(create_patch(&from, &to),
+                   vec![PatchOperation::Add { path: "/list/10000".to_string(), value: ArrayType::Int(-1) }]);
+
+        from.insert("list", ArrayType::Array((0..3000).collect()));
+        to.insert("list", ArrayType::Array((0..3000).rev().collect()));
+        let generated = create_patch(&from, &to);
+        assert_eq!(generated.len(), 3000);
+        let mut patched = from.clone();
+        apply_patch(&mut patched, &generated).expect("Apply");
+        assert_eq!(patched, to);
+
+        let invalid = load_str(r#"{"patch": [{ "op": "copy", "from": "name", "path": "/copy" }]}"#.to_string())
+            .expect("Load");
+        assert!(Vec::<PatchOperation>::from_json(invalid.get("patch").expect("Is none")).is_err());
+    }
+
+    #[test]
+    fn merge_patch() {
+        // Example from RFC 7386
+        let mut target = json!({
+            "title": "Goodbye!",
+            "author": { "givenName": "John", "familyName": "Doe" },
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let patch = json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": { "familyName": null },
+            "tags": ["example"]
+        });
+        let original = target.clone();
+
+        super::merge_patch(&mut target, &patch);
+        let expected = json!({
+            "title": "Hello!",
+            "author": { "givenName": "John" },
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        });
+        assert_eq!(target, expected);
+
+        let generated = create_merge_patch(&original, &expected);
+        assert_eq!(generated, patch);
+
+        let mut restored = expected.clone();
+        super::merge_patch(&mut restored, &create_merge_patch(&expected, &original));
+        assert_eq!(restored, original);
+
+        let mut target = json!({ "a": 1 });
+        super::merge_patch(&mut target, &json!({ "a": { "b": null, "c": 2 } }));
+        assert_eq!(target, json!({ "a": { "c": 2 } }));
+    }
+
+    #[test]
+    fn json_path() {
+        let store = json!({
+            "store": {
+                "book": [
+                    { "category": "reference", "author": "Nigel Rees", "price": 8.95 },
+                    { "category": "fiction", "author": "Evelyn Waugh", "price": 12.99 },
+                    { "category": "fiction", "author": "Herman Melville", "isbn": "0-553-21311-3", "price": 8.99 },
+                    { "category": "fiction", "author": "J. R. R. Tolkien", "isbn": "0-395-19395-8", "price": 22.99 }
+                ],
+                "bicycle": { "color": "red", "price": 399 }
+            }
+        });
+
+        let authors: Vec<String> = store.query("$.store.book[*].author").expect("Query").iter()
+            .map(|found| String::from_json(found.value()).expect("Convert"))
+            .collect();
+        assert_eq!(authors, ["Nigel Rees", "Evelyn Waugh", "Herman Melville", "J. R. R. Tolkien"]);
+
+        let prices = store.query("$..price").expect("Query");
+        assert_eq!(prices.len(), 5);
+        assert_eq!(prices[0].path(), "$['store']['bicycle']['price']");
+        assert_eq!(prices[1].pointer(), "/store/book/0/price");
+
+        let cheap = store.query("$.store.book[?@.price < 10 && @.category == 'fiction'].author").expect("Query");
+        assert_eq!(cheap.len(), 1);
+        assert!(matches!(cheap[0].value(), ArrayTypeRef::String(author) if author == "Herman Melville"));
+        assert_eq!(cheap[0].path(), "$['store']['book'][2]['author']");
+
+        assert_eq!(store.query("$..book[?@.isbn]").expect("Query").len(), 2);
+        assert_eq!(store.query("$..book[?!@.isbn]").expect("Query").len(), 2);
+        assert_eq!(store.query("$..book[-1:]").expect("Query")[0].path(), "$['store']['book'][3]");
+        assert_eq!(store.query("$..book[::-2]").expect("Query").len(), 2);
+        assert_eq!(store.query("$..book[0, 2]").expect("Query").len(), 2);
+        assert_eq!(store.query("$..book[?length(@.author) > 12]").expect("Query").len(), 2);
+        assert_eq!(store.query("$.store[?count(@.*) == 2]").expect("Query").len(), 1);
+        assert_eq!(store.query("$['store'][\"bicycle\"].color").expect("Query").len(), 1);
+        assert!(store.query("$.missing").expect("Query").is_empty());
+
+        // match() tests the whole text and search() any part of it, with I-Regexp patterns
+        assert_eq!(store.query("$..book[?match(@.author, 'H.*')]").expect("Query").len(), 1);
+        assert_eq!(store.query("$..book[?match(@.author, 'Rees')]").expect("Query").len(), 0);
+        assert_eq!(store.query("$..book[?search(@.author, 'e[lr]')].price").expect("Query").len(), 3);
+        assert_eq!(store.query("$..book[?!search(@.isbn, '^0-3')]").expect("Query").len(), 3);
+        assert_eq!(store.query("$..book[?match(@.category, @.category)]").expect("Query").len(), 4);
+        assert_eq!(store.query("$..book[?match(@.price, '8.*')]").expect("Query").len(), 0);
+        assert!(JsonPath::parse(r#"$[?match(@.a, "x.*")]"#).is_ok());
+        assert!(store.query("$..book[?match(@.author, 'a)|(b')]").is_err());
+        assert!(store.query("$..book[?match(@.author, 'a') == true]").is_err());
+
+        assert!(store.query("store").is_err());
+        assert!(store.query("$.store[").is_err());
+        assert!(store.query("$..book[?@..price == 1]").is_err());
+        assert!(store.query("$..book[?unknown(@) == 1]").is_err());
+        assert!(store.query("$..book[01]").is_err());
+
+        // Slices with huge steps or bounds don't overflow
+        assert_eq!(store.query("$..book[1::9007199254740991]").expect("Query").len(), 1);
+        assert_eq!(store.query("$..book[-9007199254740991:9007199254740991:-9007199254740991]")
+                       .expect("Query").len(), 0);
+        assert_eq!(store.query("$..book[9007199254740991::-9007199254740991]").expect("Query").len(), 1);
+        assert!(store.query("$..book[1::9223372036854775807]").is_err());
+        assert!(store.query("$..book[9007199254740992]").is_err());
+
+        // Stored floats compare with literals at their own precision
+        let reference = store.query("$..book[?@.price == 8.95].author").expect("Query");
+        assert_eq!(reference.len(), 1);
+        assert_eq!(reference[0].path(), "$['store']['book'][0]['author']");
+        assert_eq!(store.query("$..book[?@.price < 8.95]").expect("Query").len(), 0);
+        assert_eq!(store.query("$..book[?@.price <= 8.95]").expect("Query").len(), 1);
+        assert_eq!(store.query("$..book[?@.price != 12.99]").expect("Query").len(), 3);
+    }
+
+    #[test]
+    fn diff() {
+        let old = json!({
+            "name": "service",
+            "replicas": 2,
+            "ports": [80, 443],
+            "env": { "DEBUG": true, "REGION": "eu" }
+        });
+        let new = json!({
+            "name": "service",
+            "replicas": 3,
+            "ports": [22, 80, 443],
+            "env": { "REGION": "us" },
+            "image": "service:2"
+        });
+
+        let changes = super::diff(&old, &new);
+        assert_eq!(changes, vec![
+            Change::Removed { path: "/env/DEBUG".to_string(), value: ArrayType::Bool(true) },
+            Change::Changed { path: "/env/REGION".to_string(), old: ArrayType::from("eu"), new: ArrayType::from("us") },
+            Change::Added { path: "/image".to_string(), value: ArrayType::from("service:2") },
+            Change::Changed { path: "/ports/0".to_string(), old: ArrayType::Int(80), new: ArrayType::Int(22) },
+            Change::Changed { path: "/ports/1".to_string(), old: ArrayType::Int(443), new: ArrayType::Int(80) },
+            Change::Added { path: "/ports/2".to_string(), value: ArrayType::Int(443) },
+            Change::Changed { path: "/replicas".to_string(), old: ArrayType::Int(2), new: ArrayType::Int(3) }
+        ]);
+
+        let changes = diff_with(&old, &new, ArrayAlignment::Lcs);
+        let ports: Vec<&Change> = changes.iter().filter(|change| change.path().starts_with("/ports")).collect();
+        assert_eq!(ports, [&Change::Added { path: "/ports/0".to_string(), value: ArrayType::Int(22) }]);
+
+        assert!(super::diff(&old, &old.clone()).is_empty());
+
+        let mut long = JsonObject::new();
+        long.insert("a", ArrayType::Array((0..20_000).collect()));
+        let mut shifted = JsonObject::new();
+        shifted.insert("a", ArrayType::Array((1..20_000).chain(std::iter::once(0)).collect()));
+        let changes = diff_with(&long, &shifted, ArrayAlignment::Lcs);
+        assert_eq!(changes.len(), 20_000);
+        assert_eq!(changes[0], Change::Changed { path: "/a/0".to_string(), old: ArrayType::Int(0), new: ArrayType::Int(1) });
+
+        let report = render_diff(&diff_with(&json!({ "a": [1] }), &json!({ "a": [1, { "b": 2 }] }),
+                                            ArrayAlignment::Lcs));
+        assert_eq!(report, "@@ /a/1 @@\n+ {\n+     \"b\": 2\n+ }\n");
+    }
+
+    #[test]
+    fn deep_merge() {
+        let base = json!({
+            "name": "service",
+            "replicas": 2,
+            "tags": ["web", "eu"],
+            "limits": { "cpu": 1, "memory": "1G" },
+            "users": [{ "id": 1, "role": "admin" }, { "id": 2, "role": "dev" }]
+        });
+        let overlay = json!({
+            "replicas": 3,
+            "tags": ["eu", "prod"],
+            "limits": { "cpu": 2.5 },
+            "users": [{ "id": 2, "role": "ops" }, { "id": 3, "role": "dev" }]
+        });
+
+        let mut merged = base.clone();
+        let overridden = super::deep_merge(&mut merged, &overlay, &MergeStrategy::default()).expect("Merge");
+        assert_eq!(overridden, ["/limits/cpu", "/replicas", "/tags", "/users"]);
+        assert_eq!(merged.get_array("tags").expect("Is none"), json!(["eu", "prod"]));
+        assert_eq!(merged.get_object("limits").expect("Is none"), json!({ "cpu": 2.5, "memory": "1G" }));
+
+        let strategy = MergeStrategy { arrays: ArrayMerge::Deduplicate, ..MergeStrategy::default() };
+        let mut merged = base.clone();
+        super::deep_merge(&mut merged, &overlay, &strategy).expect("Merge");
+        assert_eq!(merged.get_array("tags").expect("Is none"), json!(["web", "eu", "prod"]));
+
+        let strategy = MergeStrategy { arrays: ArrayMerge::ByKey("id".to_string()), ..MergeStrategy::default() };
+        let mut merged = base.clone();
+        let overridden = super::deep_merge(&mut merged, &overlay, &strategy).expect("Merge");
+        assert!(overridden.contains(&"/users/1/role".to_string()));
+        assert_eq!(merged.get_array("users").expect("Is none"),
+                   json!([{ "id": 1, "role": "admin" }, { "id": 2, "role": "ops" }, { "id": 3, "role": "dev" }]));
+
+        // Merged items stay in place with their comments
+        let mut users = merged.get_array("users").expect("Is none").clone();
+        users.set_comments(1, vec!["// Operators".to_string()]);
+        let overridden = deep_merge_arrays(&mut users, &json!([{ "id": 2, "role": "dev" }]), &strategy)
+            .expect("Merge");
+        assert_eq!(overridden, ["/1/role"]);
+        assert_eq!(users.comments(1), ["// Operators"]);
+
+        let mut array = json!([1, 2]);
+        let strategy = MergeStrategy { arrays: ArrayMerge::Concatenate, ..MergeStrategy::default() };
+        let overridden = deep_merge_arrays(&mut array, &json!([2, 3]), &strategy).expect("Merge");
+        assert_eq!(array, json!([1, 2, 2, 3]));
+        assert!(overridden.is_empty());
+
+        let conflict = json!({ "limits": "unlimited", "name": "other" });
+        let strategy = MergeStrategy { conflicts: ConflictResolution::Error, ..MergeStrategy::default() };
+        let mut merged = base.clone();
+        let error = super::deep_merge(&mut merged, &conflict, &strategy).expect_err("Type conflict");
+        assert_eq!(error.path(), "/limits");
+        assert_eq!(merged, base);
+
+        let strategy = MergeStrategy { conflicts: ConflictResolution::PreferBase, ..MergeStrategy::default() };
+        let overridden = super::deep_merge(&mut merged, &conflict, &strategy).expect("Merge");
+        assert_eq!(overridden, ["/name"]);
+        assert_eq!(merged.get_object("limits").expect("Is none"), json!({ "cpu": 1, "memory": "1G" }));
+    }
+
+    #[test]
+    fn schema() {
+        let schema = load_str(r##"{
+            "$defs": {
+                "port": { "type": "integer", "minimum": 1, "maximum": 65535 }
+            },
+            "type": "object",
+            "required": ["name", "ports"],
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z][a-z0-9-]*$", "maxLength": 16 },
+                "ports": { "type": "array", "items": { "$ref": "#/$defs/port" }, "uniqueItems": true },
+                "mode": { "enum": ["fast", "safe"] },
+                "ratio": { "type": "number", "exclusiveMaximum": 1, "multipleOf": 0.1 },
+                "owner": { "oneOf": [{ "type": "string" }, { "type": "null" }] },
+                "tags": { "type": "array", "items": { "not": { "const": "" } } }
+            },
+            "additionalProperties": false
+        }"##.to_string()).expect("Load");
+        let schema = JsonSchema::new(&schema).expect("Compile");
+
+        let valid = json!({ "name": "web-1", "ports": [80, 443], "mode": "safe", "ratio": 0.3, "owner": null });
+        assert!(schema.is_valid(&valid));
+
+        let invalid = json!({
+            "name": "Web_1",
+            "ports": [80, 0, 80],
+            "mode": "slow",
+            "ratio": 0.25,
+            "owner": 5,
+            "tags": ["a", ""],
+            "extra": true
+        });
+        let errors = schema.validate(&invalid).expect_err("Invalid");
+        let paths: Vec<(&str, &str)> = errors.iter()
+            .map(|error| (error.instance_path(), error.schema_path()))
+            .collect();
+        assert_eq!(paths, [
+            ("/extra", "/additionalProperties"),
+            ("/mode", "/properties/mode/enum"),
+            ("/name", "/properties/name/pattern"),
+            ("/owner", "/properties/owner/oneOf"),
+            ("/ports/1", "/properties/ports/items/$ref/minimum"),
+            ("/ports", "/properties/ports/uniqueItems"),
+            ("/ratio", "/properties/ratio/multipleOf"),
+            ("/tags/1", "/properties/tags/items/not")
+        ]);
+
+        let errors = schema.validate(&json!({ "name": 5 })).expect_err("Invalid");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].schema_path(), "/properties/name/type");
+        assert_eq!(errors[1].message(), "Missing required property \"ports\"");
+
+        assert!(JsonSchema::new(&json!({ "pattern": "(unclosed" })).is_err());
+        let words = JsonSchema::new(&json!({ "patternProperties": { "^x-\\w+$": { "type": "integer" } } }))
+            .expect("Compile");
+        assert!(words.is_valid(&json!({ "x-a1": 1, "other": "a" })));
+        assert!(!words.is_valid(&json!({ "x-a1": "a" })));
+
+        // Matching takes linear time and no recursion, even on long texts
+        let matches = |pattern: &str, text: String| {
+            let mut schema = json!({ "properties": { "text": {} } });
+            schema.pointer_insert("/properties/text/pattern", ArrayType::from(pattern)).expect("Insert");
+            let mut instance = JsonObject::new();
+            instance.insert("text", ArrayType::String(text));
+
+            JsonSchema::new(&schema).expect("Compile").is_valid(&instance)
+        };
+        assert!(matches("^[a-z]+$", "a".repeat(200_000)));
+        assert!(!matches("^[a-z]+$", "a".repeat(200_000) + "!"));
+        assert!(matches("^(a+)+$", "a".repeat(200_000)));
+        assert!(!matches("^(a+)+$", "a".repeat(200_000) + "!"));
+        assert!(matches("^(?:ab|a)*?c{2,3}\\b", "aabccc".to_string()));
+        assert!(!matches("^(?:ab|a)*?c{2,3}\\b", "aabcccc".to_string()));
+        assert!(JsonSchema::new(&json!({ "pattern": "(a{1000}){1000}" })).is_err());
+
+        // Only patterns in subschemas are compiled, including those behind references
+        let constant = JsonSchema::new(&json!({ "const": { "pattern": "(" } })).expect("Compile");
+        assert!(constant.is_valid(&json!({ "pattern": "(" })));
+        assert!(JsonSchema::new(&json!({ "enum": [{ "pattern": "[" }], "default": { "pattern": "(" } })).is_ok());
+        let referenced = JsonSchema::new(&json!({ "x-names": { "pattern": "^[a-z]+$" }, "$ref": "#/x-names" }))
+            .expect("Compile");
+        assert!(referenced.validate_value(ArrayTypeRef::String(&"A".to_string())).is_err());
+
+        // References count towards a loop only while they stay on the same value
+        let tree = JsonSchema::new(&json!({ "type": "object", "properties": { "c": { "$ref": "#" } } }))
+            .expect("Compile");
+        let mut deep = JsonObject::new();
+        for _ in 0..100 {
+            let mut parent = JsonObject::new();
+            parent.insert("c", ArrayType::Object(deep));
+            deep = parent;
+        }
+        assert!(tree.is_valid(&deep));
+        assert!(tree.validate(&json!({ "c": { "c": 1 } })).is_err());
+
+        let looping = JsonSchema::new(&json!({ "$defs": { "a": { "$ref": "#/$defs/a" } }, "$ref": "#/$defs/a" }))
+            .expect("Compile");
+        let errors = looping.validate(&json!({})).expect_err("Loop");
+        assert_eq!(errors[0].message(), "Too many nested references");
+    }
+
+    #[test]
+    fn infer_schema() {
+        let all = load(String::from("samples/all.json")).expect("Couldn't load");
+        let schema = super::infer_schema(std::slice::from_ref(&all));
+
+        assert_eq!(schema.pointer("/properties/var12/items/type").map(ArrayType::from),
+                   Some(ArrayType::Array(json!(["boolean", "integer", "string"]))));
+        assert_eq!(schema.pointer("/properties/var6/items/type").map(ArrayType::from),
+                   Some(ArrayType::from("integer")));
+        assert_eq!(schema.pointer("/properties/var11/type").map(ArrayType::from), Some(ArrayType::from("null")));
+        assert!(schema.pointer("/properties/var10/properties/dict/properties").is_some());
+        assert!(JsonSchema::new(&schema).expect("Compile").is_valid(&all));
+
+        let samples = vec![
+            json!({ "id": 1, "status": "active", "score": 3, "nickname": "a" }),
+            json!({ "id": 2, "status": "disabled", "score": 4.5 }),
+            json!({ "id": 3, "status": "active", "score": null, "nickname": 7 })
+        ];
+        let schema = super::infer_schema(&samples);
+
+        assert_eq!(schema.get_array("required").expect("Is none"), json!(["id", "score", "status"]));
+        assert_eq!(schema.pointer("/properties/status/enum").map(ArrayType::from),
+                   Some(ArrayType::Array(json!(["active", "disabled"]))));
+        assert_eq!(schema.pointer("/properties/score/type").map(ArrayType::from),
+                   Some(ArrayType::Array(json!(["null", "number"]))));
+        assert_eq!(schema.pointer("/properties/nickname/type").map(ArrayType::from),
+                   Some(ArrayType::Array(json!(["integer", "string"]))));
+
+        let validator = JsonSchema::new(&schema).expect("Compile");
+        assert!(samples.iter().all(|sample| validator.is_valid(sample)));
+        assert!(!validator.is_valid(&json!({ "id": 4, "status": "unknown", "score": 1 })));
+
+        let options = InferOptions { max_enum_values: 1 };
+        let schema = infer_schema_with(&samples, &options);
+        assert!(schema.pointer("/properties/status/enum").is_none());
+    }
+
+    #[test]
+    fn codegen() {
+        let sample = json!({
+            "userName": "simon",
+            "type": "admin",
+            "address": { "city": "Paris", "zip": null },
+            "tags": ["a", "b"],
+            "mixed": [1, "two"]
+        });
+        let code = generate_rust_from_sample(&sample, "User");
+
+        assert!(code.starts_with("use rusty_json::{ToJson, FromJson};\nuse rusty_json::{ArrayType, Null};\n"));
+        assert!(code.contains("#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]\npub struct User {\n"));
+        assert!(code.contains("    pub address: Address,\n"));
+        assert!(code.contains("    pub mixed: Vec<ArrayType<'static>>,\n"));
+        assert!(code.contains("    #[json(rename = \"type\")]\n    pub type_: String,\n"));
+        assert!(code.contains("    #[json(rename = \"userName\")]\n    pub user_name: String\n}"));
+        assert!(code.contains("pub struct Address {\n    pub city: String,\n    pub zip: Null\n}"));
+        assert!(code.find("pub struct User").expect("Is none") < code.find("pub struct Address").expect("Is none"));
+
+        let schema = json!({
+            "$defs": {
+                "node": {
+                    "properties": { "next": { "$ref": "#/$defs/node" }, "value": { "type": "integer" } },
+                    "required": ["value"]
+                }
+            },
+            "properties": {
+                "head": { "$ref": "#/$defs/node" },
+                "state": { "type": ["string", "null"], "enum": ["on", "off-line", null] }
+            },
+            "required": ["head"]
+        });
+        let code = generate_rust_from_schema(&schema, "List");
+
+        assert!(code.contains("pub struct List {\n    pub head: Node,\n    pub state: Option<State>\n}"));
+        assert!(code.contains("pub struct Node {\n    pub next: Option<Box<Node>>,\n    pub value: i32\n}"));
+        assert!(code.contains("pub enum State {\n    #[json(rename = \"on\")]\n    On,\n    \
+                               #[json(rename = \"off-line\")]\n    OffLine\n}"));
+    }
+
+    #[test]
+    fn jsonc() {
+        let contents = "{\n    // Editor settings\n    \"tabs\": 4, /* spaces */\n    \"theme\": \"dark\"\n    // The end\n}";
+
+        assert!(load_str(contents.to_string()).is_err());
+
+        let skipped = ParseOptions { comments: CommentMode::Skip, ..ParseOptions::default() };
+        let object = load_str_with(contents.to_string(), &skipped).expect("Comments are skipped");
+        assert_eq!(object, json!({ "tabs": 4, "theme": "dark" }));
+        assert!(object.comments("tabs").is_empty());
+
+        let kept = ParseOptions { comments: CommentMode::Keep, ..ParseOptions::default() };
+        let object = load_str_with(contents.to_string(), &kept).expect("Comments are kept");
+        assert_eq!(object.comments("tabs"), ["// Editor settings"]);
+        assert_eq!(object.comments("theme"), ["/* spaces */"]);
+        assert_eq!(object.trailing_comments(), ["// The end"]);
+
+        assert_eq!(dump_pretty(object.clone()), contents.replace(" /* spaces */", "\n    /* spaces */"));
+        assert_eq!(dump(object), "{/* Editor settings */\"tabs\":4,/* spaces */\"theme\":\"dark\"/* The end */}");
+
+        assert!(load_str_with("{\"a\": 1 /* open".to_string(), &skipped).is_err());
+
+        // Comments around the document and inside arrays are kept too
+        let contents = "// header\n{ \"b\": [1, // c\n 2] } // tail";
+        let object = load_str_with(contents.to_string(), &kept).expect("Comments are kept");
+        assert_eq!(object.header_comments(), ["// header"]);
+        assert_eq!(object.footer_comments(), ["// tail"]);
+        let array = object.get_array("b").expect("Is none");
+        assert!(array.comments(0).is_empty());
+        assert_eq!(array.comments(1), ["// c"]);
+        assert_eq!(dump_pretty(object.clone()),
+                   "// header\n{\n    \"b\": [\n        1,\n        // c\n        2\n    ]\n}\n// tail");
+        assert_eq!(dump(object.clone()), "/* header */{\"b\":[1,/* c */2]}/* tail */");
+        assert_eq!(load_str_with(dump_pretty(object.clone()), &kept).expect("Comments are kept"), object);
+
+        let mut array = array.clone();
+        array.insert(0, ArrayType::Int(0)).expect("Insert");
+        assert_eq!(array.comments(2), ["// c"]);
+        array.remove(1).expect("Remove");
+        assert_eq!(array.comments(1), ["// c"]);
+        array.set(1, ArrayType::Int(3)).expect("Set");
+        assert_eq!(array.comments(1), ["// c"]);
+        array.remove(1).expect("Remove");
+        assert!(array.comments(1).is_empty());
+
+        // Comments inside a member or after an item belong to it, and are written before it
+        let object = load_str_with("{\"a\": /* one */ 1, \"b\": 2 /* two */, \"c\": [3 /* three */, 4]}".to_string(),
+                                   &kept).expect("Comments are kept");
+        assert_eq!(object.comments("a"), ["/* one */"]);
+        assert_eq!(object.comments("b"), ["/* two */"]);
+        assert_eq!(object.get_array("c").expect("Is none").comments(0), ["/* three */"]);
+        assert_eq!(dump(object), "{/* one */\"a\":1,/* two */\"b\":2,\"c\":[/* three */3,4]}");
+
+        // A scalar document has nowhere to keep them
+        let value = load_value_str_with("// one\n1".to_string(), &ParseOptions { scalar_root: true, ..kept.clone() })
+            .expect("Comments are dropped");
+        assert_eq!(value, ArrayType::Int(1));
+    }
+
+    #[test]
+    fn json5() {
+        let contents = "// Build settings\n{\n    name: 'rusty \"json\"',\n    'quoted key': \"line \\\n continued\",\n    \
+                        mask: 0xFF, negative: -0x10, half: .5, whole: 5., positive: +1,\n    \
+                        limits: [Infinity, -Infinity, NaN,],\n    null: null, /* trailing comma */\n}";
+        let object = load_json5_str(contents.to_string()).expect("Valid JSON5");
+
+        assert_eq!(object.get_string("name").expect("Is none"), "rusty \"json\"");
+        assert_eq!(object.get_string("quoted key").expect("Is none"), "line  continued");
+        assert_eq!(object.get_int("mask"), Some(255));
+        assert_eq!(object.get_int("negative"), Some(-16));
+        assert_eq!(object.get_float("half"), Some(0.5));
+        assert_eq!(object.get_float("whole"), Some(5.0));
+        assert_eq!(object.get_int("positive"), Some(1));
+        assert!(object.get_null("null").is_some());
+
+        let limits = object.get_array("limits").expect("Is none");
+        assert_eq!(limits.get(0).expect("Is none"), ArrayTypeRef::Float(f32::INFINITY));
+        assert!(matches!(limits.get(2), Ok(ArrayTypeRef::Float(value)) if value.is_nan()));
+
+        let dumped = dump_json5(object.clone());
+        assert!(dumped.starts_with("{half:0.5,limits:[Infinity,-Infinity,NaN],mask:255,name:'rusty \"json\"',"));
+        assert!(dumped.ends_with("\"quoted key\":\"line  continued\",whole:5.0}"));
+        assert_eq!(dump(load_json5_str(dump_json5_pretty(object.clone())).expect("Valid JSON5")), dump(object));
+
+        assert!(load_json5_str("{a: 007}".to_string()).is_err());
+        assert!(load_json5_str("{a: b}".to_string()).is_err());
+        assert!(load_str("{a: 1}".to_string()).is_err());
+    }
+
+    #[test]
+    fn parse_options() {
+        let strict = ParseOptions::default();
+        let load = |contents: &str, options: &ParseOptions| load_str_with(contents.to_string(), options);
+
+        assert!(load("{\"a\": [1, 2,],}", &strict).is_err());
+        let lenient = ParseOptions { trailing_commas: true, ..ParseOptions::default() };
+        assert_eq!(load("{\"a\": [1, 2,],}", &lenient).expect("Trailing commas"), json!({ "a": [1, 2] }));
+
+        let duplicates = "{\"a\": 1, \"a\": \"x\"}";
+        assert_eq!(load(duplicates, &strict).expect("Last wins"), json!({ "a": "x" }));
+        let first_wins = ParseOptions { duplicate_keys: DuplicateKeys::FirstWins, ..ParseOptions::default() };
+        assert_eq!(load(duplicates, &first_wins).expect("First wins"), json!({ "a": 1 }));
+        let error = ParseOptions { duplicate_keys: DuplicateKeys::Error, ..ParseOptions::default() };
+        assert!(load(duplicates, &error).is_err());
+
+        assert!(load("{\"a\": \"tab\there\"}", &strict).is_err());
+        let control = ParseOptions { control_characters: true, ..ParseOptions::default() };
+        assert_eq!(load("{\"a\": \"tab\there\"}", &control).expect("Control characters"),
+                   json!({ "a": "tab\there" }));
+
+        assert!(load("{\"a\": 007}", &strict).is_err());
+        assert_eq!(load("{\"a\": -0.5, \"b\": 0}", &strict).expect("No leading zeros"), json!({ "a": -0.5, "b": 0 }));
+        let zeros = ParseOptions { leading_zeros: true, ..ParseOptions::default() };
+        assert_eq!(load("{\"a\": 007}", &zeros).expect("Leading zeros"), json!({ "a": 7 }));
+
+        assert!(load("{\"a\": NaN}", &strict).is_err());
+        let non_finite = ParseOptions { non_finite_numbers: true, ..ParseOptions::default() };
+        let object = load("{\"a\": [NaN, Infinity, -Infinity]}", &non_finite).expect("Non-finite numbers");
+        let array = object.get_array("a").expect("Is none");
+        assert!(matches!(array.get(0), Ok(ArrayTypeRef::Float(value)) if value.is_nan()));
+        assert_eq!(array.get(2).expect("Is none"), ArrayTypeRef::Float(f32::NEG_INFINITY));
+
+        let limited = ParseOptions {
+            max_depth: Some(2),
+            max_string_length: Some(3),
+            max_number_length: Some(4),
+            ..ParseOptions::default()
+        };
+        assert!(load("{\"abc\": [12345]}", &limited).is_err());
+        assert!(load("{\"abcd\": [1]}", &limited).is_err());
+        assert!(load("{\"abc\": [[1]]}", &limited).is_err());
+        assert!(load("{\"abc\": [1234]}", &limited).is_ok());
+
+        // Nesting is limited by default, so that deep documents fail instead of overflowing the stack
+        assert_eq!(ParseOptions::default().max_depth, Some(128));
+        let deep = format!("{{\"a\": {}", "[".repeat(200_000));
+        let error = load_str(deep.clone()).expect_err("Too deep");
+        assert_eq!(error.to_string(), "ParseError: Nesting deeper than 128 levels\nLine: 1, column: 133");
+        let (_, diagnostics) = load_str_recovering(deep, &ParseOptions::default());
+        assert_eq!(diagnostics[0].to_string(), error.to_string());
+
+        let nested = format!("{{\"a\": {}{}}}", "[".repeat(200), "]".repeat(200));
+        assert!(load_str(nested.clone()).is_err());
+        let unlimited = ParseOptions { max_depth: None, ..ParseOptions::default() };
+        assert!(load_str_with(nested, &unlimited).is_ok());
+
+        assert!(load_value_str_with("\"lone\"".to_string(), &strict).is_err());
+        assert!(matches!(load_value_str_with("[1]".to_string(), &strict), Ok(ArrayType::Array(_))));
+        let scalar = ParseOptions { scalar_root: true, ..ParseOptions::default() };
+        assert!(matches!(load_value_str_with("\"lone\"".to_string(), &scalar), Ok(ArrayType::String(_))));
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let contents = "{\n    \"a\": 1,\n    \"b\": {\"c\": true, \"c\": null},\n    \"a\": \"x\",\n    \"a\": [1]\n}";
+
+        let object = load_str(contents.to_string()).expect("Last wins");
+        assert_eq!(object.len(), 2);
+        assert!(object.get_int("a").is_none() && object.get_string("a").is_none());
+        assert_eq!(object.get_array("a").expect("Is none").len(), 1);
+
+        let error = ParseOptions { duplicate_keys: DuplicateKeys::Error, ..ParseOptions::default() };
+        let message = load_str_with(contents.to_string(), &error).expect_err("Duplicate key").to_string();
+        assert_eq!(message, "DuplicateKeyError: \"c\" is already used at line 3, column 10\nLine: 3, column: 21");
+
+        let duplicates = find_duplicate_keys(contents.to_string(), &error).expect("Valid JSON");
+        let found: Vec<_> = duplicates.iter()
+            .map(|duplicate| (duplicate.key(), duplicate.first(), duplicate.second()))
+            .collect();
+        assert_eq!(found, [("c", (3, 10), (3, 21)), ("a", (2, 4), (4, 4)), ("a", (2, 4), (5, 4))]);
+
+        assert!(find_duplicate_keys("{\"a\": 1}".to_string(), &error).expect("Valid JSON").is_empty());
+        assert!(find_duplicate_keys("{\"a\": }".to_string(), &error).is_err());
+    }
+
+    #[test]
+    fn error_recovery() {
+        let contents = "{\n    \"name\": \"rusty\",\n    \"version\": 1.2.3,\n    \"tags\": [\"json\", tru, \"parser\" 7],\n    \
+                        \"bad\\q\": 1,\n    \"nested\": {\"a\": , \"b\": 2}\n    \"last\": null\n";
+        let (object, diagnostics) = load_str_recovering(contents.to_string(), &ParseOptions::default());
+
+        let found: Vec<_> = diagnostics.iter()
+            .map(|diagnostic| (diagnostic.line(), diagnostic.column(), diagnostic.to_string()))
+            .map(|(line, column, message)| (line, column, message.lines().next().unwrap_or("").to_string()))
+            .collect();
+        assert_eq!(found, [
+            (3, 18, "ParseError: Invalid number format".to_string()),
+            (4, 24, "ParseError: Invalid keyword".to_string()),
+            (4, 35, "ParseError: Expected a comma or a right bracket".to_string()),
+            (5, 9, "InvalidStringError: Unknown escape character in string".to_string()),
+            (6, 20, "ParseError: Expected a value".to_string()),
+            (7, 4, "ParseError: Expected a comma or a right brace".to_string()),
+            (8, 0, "EOFError: Unexpected end of file".to_string())
+        ]);
+
+        assert_eq!(object, json!({
+            "name": "rusty",
+            "tags": ["json", "parser", 7],
+            "nested": { "b": 2 },
+            "last": null
+        }));
+
+        assert!(load_str_recovering("{\"a\": [1, 2]}".to_string(), &ParseOptions::default()).1.is_empty());
+        assert!(load_str(contents.to_string()).is_err());
+    }
+
+    #[test]
+    fn concrete_syntax_tree() {
+        let source = "{\n  // Service settings\n  \"name\": \"api\",\n  \"port\":   8080,\n  \"ratio\": 1.50,\n  \
+                      \"hosts\": [\"a\", \"b\"]\n}\n";
+        let options = ParseOptions { comments: CommentMode::Skip, ..ParseOptions::default() };
+        let mut document = Document::parse_with(source.to_string(), &options).expect("Valid JSONC");
+
+        assert_eq!(document.to_string(), source);
+        assert_eq!(document.raw("/ratio"), Some("1.50"));
+        assert_eq!(document.span("/hosts/1"), Some(93..96));
+        let comments: Vec<_> = document.tokens().iter()
+            .filter(|token| token.kind() == SyntaxKind::Comment)
+            .map(|token| &source[token.span()])
+            .collect();
+        assert_eq!(comments, ["// Service settings"]);
+        assert_eq!(document.tokens().iter().map(|token| token.span().len()).sum::<usize>(), source.len());
+
+        document.set("/port", &ArrayType::Int(9090)).expect("Member exists");
+        assert_eq!(document.to_string(), source.replace("8080", "9090"));
+
+        document.insert("/hosts/-", &ArrayType::from("c")).expect("Array exists");
+        document.insert("/tls", &ArrayType::Object(json!({ "enabled": true }))).expect("Object exists");
+        assert_eq!(document.to_string(), "{\n  // Service settings\n  \"name\": \"api\",\n  \"port\":   9090,\n  \
+                                          \"ratio\": 1.50,\n  \"hosts\": [\"a\", \"b\", \"c\"],\n  \"tls\": {\n    \
+                                          \"enabled\": true\n  }\n}\n");
+
+        document.remove("/name").expect("Member exists");
+        document.remove("/hosts/0").expect("Item exists");
+        document.remove("/tls").expect("Member exists");
+        assert_eq!(document.to_string(), "{\n  // Service settings\n  \"port\":   9090,\n  \"ratio\": 1.50,\n  \
+                                          \"hosts\": [\"b\", \"c\"]\n}\n");
+        assert_eq!(document.to_object(), json!({ "port": 9090, "ratio": 1.5, "hosts": ["b", "c"] }));
+
+        // Comments on the same line go with the member, the ones above the next member stay
+        let source = "{\n  \"a\": 1, // x\n  // About b\n  \"b\": 2, /* y */\n  \"c\": 3 // z\n}";
+        let mut commented = Document::parse_with(source.to_string(), &options).expect("Valid JSONC");
+        commented.remove("/a").expect("Member exists");
+        assert_eq!(commented.to_string(), "{\n  // About b\n  \"b\": 2, /* y */\n  \"c\": 3 // z\n}");
+        commented.remove("/c").expect("Member exists");
+        assert_eq!(commented.to_string(), "{\n  // About b\n  \"b\": 2 /* y */\n}");
+
+        assert!(document.set("/missing", &ArrayType::Int(1)).is_err());
+        assert!(document.remove("").is_err());
+        assert!(Document::parse("{\"a\": }".to_string()).is_err());
+    }
+
+    #[test]
+    fn ndjson() {
+        let input = "{\"level\": \"info\", \"id\": 1}\r\n[1, 2]\n\n\"done\"\n{\"level\": }\n42";
+        let values: Vec<_> = read_ndjson(input.as_bytes()).collect();
+        assert_eq!(values.len(), 6);
+        assert_eq!(*values[0].as_ref().unwrap(), ArrayType::Object(json!({ "level": "info", "id": 1 })));
+        assert_eq!(*values[1].as_ref().unwrap(), ArrayType::Array(json!([1, 2])));
+        assert_eq!(values[2].as_ref().unwrap_err().to_string(), "EOFError: Unexpected end of file\nLine: 3, column: 0");
+        assert_eq!(*values[3].as_ref().unwrap(), ArrayType::from("done"));
+        assert_eq!(values[4].as_ref().unwrap_err().to_string(), "ParseError: Expected a value\nLine: 5, column: 10");
+        assert_eq!(*values[5].as_ref().unwrap(), ArrayType::Int(42));
+
+        let options = NdjsonOptions { skip_blank_lines: true, skip_invalid_lines: true, ..NdjsonOptions::default() };
+        let mut reader = read_ndjson_with(input.as_bytes(), &options);
+        assert_eq!(reader.by_ref().filter(Result::is_ok).count(), 4);
+        assert_eq!(reader.line(), 6);
+
+        let mut writer = NdjsonWriter::new(Vec::new());
+        writer.write(&ArrayType::Object(json!({ "b": [1, null], "a": "x" }))).unwrap();
+        writer.write(&ArrayType::Bool(true)).unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "{\"a\":\"x\",\"b\":[1,null]}\ntrue\n");
+    }
+
+    #[test]
+    fn json_streams() {
+        let mut stream = read_json_stream("{\"a\": 1}{}\n[\"é\"] 42 \"x\"null".as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Object(json!({ "a": 1 })));
+        assert_eq!(stream.byte_offset(), 8);
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Object(json!({})));
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Array(json!(["é"])));
+        assert_eq!(stream.byte_offset(), 17);
+        let rest: Vec<_> = stream.by_ref().map(Result::unwrap).collect();
+        assert_eq!(rest, [ArrayType::Int(42), ArrayType::from("x"), ArrayType::Null(Null)]);
+        assert_eq!(stream.byte_offset(), 28);
+
+        // An error ends a concatenated stream
+        let values: Vec<_> = read_json_stream("[1] [2,] [3]".as_bytes()).collect();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[1].as_ref().unwrap_err().to_string(), "ParseError: Expected a value\nLine: 1, column: 7");
+
+        // RFC 7464: a bad or truncated record doesn't stop the ones after it
+        let sequence = "\u{1E}{\"a\": 1}\n\u{1E}\u{1E}[1, 2\n\u{1E}true false\n\u{1E}\"b\"\n";
+        let mut stream = read_json_seq(sequence.as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Object(json!({ "a": 1 })));
+        assert_eq!(stream.byte_offset(), 10);
+        assert_eq!(stream.next().unwrap().unwrap_err().to_string(),
+                   "EOFError: Unexpected end of file\nLine: 3, column: 0");
+        assert_eq!(stream.next().unwrap().unwrap_err().to_string(),
+                   "ParseError: Expected the end of the record\nLine: 3, column: 6");
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::from("b"));
+        assert_eq!(stream.byte_offset(), sequence.len());
+        assert!(stream.next().is_none());
+
+        // Values are returned as soon as they are read, like from a pipe that has nothing more yet
+        struct Pipe(Vec<&'static [u8]>);
+
+        impl std::io::Read for Pipe {
+            fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::WouldBlock, "Nothing more yet"));
+                }
+
+                let chunk = self.0.remove(0);
+                buffer[..chunk.len()].copy_from_slice(chunk);
+                Ok(chunk.len())
+            }
+        }
+
+        let pipe = Pipe(vec![b"{\"a\":", b" [1]}[\"\xc3", b"\xa9\"] 4", b"2 \"x", b"\" 1"]);
+        let mut stream = read_json_stream(std::io::BufReader::new(pipe));
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Object(json!({ "a": [1] })));
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Array(json!(["é"])));
+        assert_eq!(stream.byte_offset(), 16);
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Int(42));
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::from("x"));
+        assert_eq!(stream.next().unwrap().unwrap_err().to_string(), "Nothing more yet");
+        assert!(stream.next().is_none());
+
+        // Invalid UTF-8 only fails the value or the record it's in
+        let mut stream = read_json_stream(&b"[1] [\"\xff\"]"[..]);
+        assert_eq!(stream.next().unwrap().unwrap(), ArrayType::Array(json!([1])));
+        assert_eq!(stream.next().unwrap().unwrap_err().to_string(), "ParseError: Invalid UTF-8\nLine: 1, column: 6");
+        assert!(stream.next().is_none());
+
+        let values: Vec<_> = read_json_seq(&b"\x1e1\n\x1e\"\xff\"\n\x1e2\n"[..]).collect();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[1].as_ref().unwrap_err().to_string(), "ParseError: Invalid UTF-8\nLine: 2, column: 2");
+        assert_eq!(*values[2].as_ref().unwrap(), ArrayType::Int(2));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack() {
+        let encoded = |value: ArrayType| to_msgpack_value(&value);
+        assert_eq!(encoded(ArrayType::Int(127)), [0x7f]);
+        assert_eq!(encoded(ArrayType::Int(-32)), [0xe0]);
+        assert_eq!(encoded(ArrayType::Int(200)), [0xcc, 200]);
+        assert_eq!(encoded(ArrayType::Int(-100)), [0xd0, 0x9c]);
+        assert_eq!(encoded(ArrayType::Int(65535)), [0xcd, 0xff, 0xff]);
+        assert_eq!(encoded(ArrayType::Int(-129)), [0xd1, 0xff, 0x7f]);
+        assert_eq!(encoded(ArrayType::Int(70000)), [0xce, 0x00, 0x01, 0x11, 0x70]);
+        assert_eq!(encoded(ArrayType::Int(i32::MIN)), [0xd2, 0x80, 0x00, 0x00, 0x00]);
+        assert_eq!(encoded(ArrayType::Float(1.5)), [0xca, 0x3f, 0xc0, 0x00, 0x00]);
+        assert_eq!(encoded(ArrayType::from("hi")), [0xa2, b'h', b'i']);
+        assert_eq!(encoded(ArrayType::from("x".repeat(40)))[..2], [0xd9, 40]);
+
+        let object = json!({
+            "b": [1, -1.25, true, false, null, "é"],
+            "a": { "nested": [] },
+            "big": 100000,
+            "long": "y".repeat(300)
+        });
+        let bytes = to_msgpack(&object);
+        assert_eq!(bytes[..4], [0x84, 0xa1, b'a', 0x81]);
+        assert_eq!(from_msgpack(&bytes).expect("Valid MessagePack"), object);
+
+        // 64 bit and float 64 formats from other encoders
+        assert_eq!(from_msgpack_value(&[0xd3, 0, 0, 0, 0, 0, 0, 0, 42]).unwrap(), ArrayType::Int(42));
+        assert_eq!(from_msgpack_value(&[0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]).unwrap(), ArrayType::Float(1.5));
+        assert_eq!(from_msgpack_value(&[0x91, 0xcf, 0, 0, 0, 1, 0, 0, 0, 0]).unwrap_err().to_string(),
+                   "MsgpackError: Integer out of range for int\nOffset: 1");
+        assert_eq!(from_msgpack_value(&[0xcb, 0x7f, 0xe0, 0, 0, 0, 0, 0, 0]).unwrap_err().to_string(),
+                   "MsgpackError: Number out of range for float\nOffset: 0");
+
+        // Deep nesting is an error rather than a stack overflow
+        assert_eq!(from_msgpack_value(&vec![0x91; 500_000]).unwrap_err().to_string(),
+                   "MsgpackError: Nesting deeper than 128 levels\nOffset: 128");
+        let mut nested = vec![0x91; 128];
+        nested.push(0xc0);
+        assert!(from_msgpack_value(&nested).is_ok());
+
+        assert_eq!(from_msgpack(&[0x91, 0x01]).unwrap_err().to_string(), "MsgpackError: Expected a map\nOffset: 0");
+        assert_eq!(from_msgpack_value(&[0x92, 0x01]).unwrap_err().to_string(),
+                   "MsgpackError: Unexpected end of data\nOffset: 2");
+        assert_eq!(from_msgpack_value(&[0x81, 0x01, 0x02]).unwrap_err().offset(), 1);
+        assert_eq!(from_msgpack_value(&[0xc4, 0x00]).unwrap_err().to_string(),
+                   "MsgpackError: Binary data has no JSON value\nOffset: 0");
+        assert!(from_msgpack_value(&[0xc0, 0xc0]).is_err());
+    }
 }
@@ -0,0 +1,99 @@
+// Builds JSON values from JSON-like syntax
+//
+// Objects expand to a JsonObject, arrays to a JsonArray and anything else to
+// an ArrayType. Rust expressions can be interpolated anywhere a value is
+// expected, as long as they convert into an ArrayType. Keys are string
+// literals or parenthesized expressions.
+//
+// let object = json!({
+//     "name": "Simon",
+//     "age": age,
+//     "friends": ["Friend1", "Friend2"],
+//     "foo": { "bar": null },
+// });
+#[macro_export]
+macro_rules! json {
+    ({ $($tt:tt)* }) => {{
+        let mut object = $crate::JsonObject::new();
+        $crate::json_internal!(@object object ($($tt)*));
+        object
+    }};
+
+    ([ $($tt:tt)* ]) => {{
+        let mut array = $crate::JsonArray::new();
+        $crate::json_internal!(@array array () ($($tt)*));
+        array
+    }};
+
+    ($($value:tt)+) => {
+        $crate::json_internal!(@value $($value)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // Values
+
+    (@value null) => {
+        $crate::ArrayType::Null($crate::Null)
+    };
+
+    (@value { $($tt:tt)* }) => {
+        $crate::ArrayType::Object($crate::json!({ $($tt)* }))
+    };
+
+    (@value [ $($tt:tt)* ]) => {
+        $crate::ArrayType::Array($crate::json!([ $($tt)* ]))
+    };
+
+    (@value $value:expr) => {
+        $crate::ArrayType::from($value)
+    };
+
+    // Arrays: collect tokens until a comma, then add the value
+
+    (@array $array:ident () ()) => {};
+
+    (@array $array:ident ($($value:tt)+) ()) => {
+        $array.add($crate::json_internal!(@value $($value)+));
+    };
+
+    (@array $array:ident ($($value:tt)+) (, $($rest:tt)*)) => {
+        $array.add($crate::json_internal!(@value $($value)+));
+        $crate::json_internal!(@array $array () ($($rest)*));
+    };
+
+    (@array $array:ident ($($value:tt)*) ($next:tt $($rest:tt)*)) => {
+        $crate::json_internal!(@array $array ($($value)* $next) ($($rest)*));
+    };
+
+    // Objects: read a key and a colon, then collect the value like in arrays
+
+    (@object $object:ident ()) => {};
+
+    (@object $object:ident ($key:tt : $($rest:tt)*)) => {
+        $crate::json_internal!(@member $object $key () ($($rest)*));
+    };
+
+    (@member $object:ident $key:tt ($($value:tt)+) ()) => {
+        $object.insert($crate::json_internal!(@key $key), $crate::json_internal!(@value $($value)+));
+    };
+
+    (@member $object:ident $key:tt ($($value:tt)+) (, $($rest:tt)*)) => {
+        $object.insert($crate::json_internal!(@key $key), $crate::json_internal!(@value $($value)+));
+        $crate::json_internal!(@object $object ($($rest)*));
+    };
+
+    (@member $object:ident $key:tt ($($value:tt)*) ($next:tt $($rest:tt)*)) => {
+        $crate::json_internal!(@member $object $key ($($value)* $next) ($($rest)*));
+    };
+
+    (@key ($key:expr)) => {
+        $key
+    };
+
+    (@key $key:literal) => {
+        $key
+    };
+}
@@ -0,0 +1,207 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+use crate::traits::ToJson;
+use crate::pointer::escape;
+
+// Deep merge of an overlay into a base document, for layering configuration.
+// Objects are always merged member by member; how arrays and values of
+// different types are combined is up to the strategy.
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MergeStrategy {
+    pub arrays: ArrayMerge,
+    pub conflicts: ConflictResolution
+}
+
+// Concatenate and Deduplicate only append items, so they never override a base value
+// and add nothing to the paths that deep_merge returns
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ArrayMerge {
+    #[default]
+    Replace,
+    Concatenate,
+    Deduplicate,  // Appends the overlay items that the base doesn't contain yet
+    ByKey(String)  // Merges object items that have the same value for this member
+}
+
+// What happens when the base and the overlay have values of different types.
+// Ints and floats count as the same type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    Error,
+    #[default]
+    PreferOverlay,
+    PreferBase
+}
+
+// Merges the overlay into the base and returns the JSON Pointer paths of the base
+// values that were overridden. On error the base is left untouched.
+pub fn deep_merge(base: &mut JsonObject, overlay: &JsonObject,
+                  strategy: &MergeStrategy) -> Result<Vec<String>, MergeError> {
+    let mut overridden = Vec::new();
+    let mut merged = base.clone();
+
+    merge_objects("", &mut merged, overlay, strategy, &mut overridden)?;
+    *base = merged;
+
+    Ok(overridden)
+}
+
+pub fn deep_merge_arrays(base: &mut JsonArray, overlay: &JsonArray,
+                         strategy: &MergeStrategy) -> Result<Vec<String>, MergeError> {
+    let mut overridden = Vec::new();
+    let mut merged = ArrayType::Array(base.clone());
+
+    merge_values("", &mut merged, ArrayTypeRef::Array(overlay), strategy, &mut overridden)?;
+    if let ArrayType::Array(merged) = merged {
+        *base = merged;
+    }
+
+    Ok(overridden)
+}
+
+fn merge_values<'a>(path: &str, base: &mut ArrayType<'a>, overlay: ArrayTypeRef,
+                    strategy: &MergeStrategy, overridden: &mut Vec<String>) -> Result<(), MergeError> {
+    match (base, overlay) {
+        (ArrayType::Object(base), ArrayTypeRef::Object(overlay)) => {
+            merge_objects(path, base, overlay, strategy, overridden)
+        }
+        (ArrayType::Array(base), ArrayTypeRef::Array(overlay)) => {
+            merge_arrays(path, base, overlay, strategy, overridden)
+        }
+        (base, overlay) => {
+            let same_type = base.type_name() == overlay.type_name() ||
+                            is_number(base.to_ref()) && is_number(overlay);
+
+            if !same_type {
+                match strategy.conflicts {
+                    ConflictResolution::Error => {
+                        return Err(MergeError {
+                            path: path.to_string(),
+                            base: base.type_name(),
+                            overlay: overlay.type_name()
+                        });
+                    }
+                    ConflictResolution::PreferBase => return Ok(()),
+                    ConflictResolution::PreferOverlay => ()
+                }
+            }
+
+            if base.to_ref() != overlay {
+                *base = overlay.to_json();
+                overridden.push(path.to_string());
+            }
+
+            Ok(())
+        }
+    }
+}
+
+fn merge_objects(path: &str, base: &mut JsonObject, overlay: &JsonObject,
+                 strategy: &MergeStrategy, overridden: &mut Vec<String>) -> Result<(), MergeError> {
+    let mut members: Vec<_> = overlay.iter().collect();
+    members.sort_by_key(|(key, _)| *key);
+
+    for (key, value) in members {
+        match base.delete(key) {
+            Some(mut base_value) => {
+                let member_path = format!("{}/{}", path, escape(key));
+                let result = merge_values(&member_path, &mut base_value, value, strategy, overridden);
+                base.insert(key.to_string(), base_value);
+                result?;
+            }
+            None => {
+                base.insert(key.to_string(), value.to_json());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn merge_arrays(path: &str, base: &mut JsonArray, overlay: &JsonArray,
+                strategy: &MergeStrategy, overridden: &mut Vec<String>) -> Result<(), MergeError> {
+    match &strategy.arrays {
+        ArrayMerge::Replace => {
+            if base != overlay {
+                *base = match overlay.to_json() {
+                    ArrayType::Array(array) => array,
+                    _ => unreachable!("Arrays convert to arrays")
+                };
+                overridden.push(path.to_string());
+            }
+        }
+        ArrayMerge::Concatenate => {
+            for item in overlay.iter() {
+                base.add(item.to_json());
+            }
+        }
+        ArrayMerge::Deduplicate => {
+            for item in overlay.iter() {
+                if !base.iter().any(|existing| existing == item) {
+                    base.add(item.to_json());
+                }
+            }
+        }
+        ArrayMerge::ByKey(key) => {
+            for item in overlay.iter() {
+                let position = identity(item, key).and_then(|identity| {
+                    base.iter().position(|existing| identity_matches(existing, key, identity))
+                });
+
+                match position {
+                    // The item is put back with set, which keeps it in place along with its comments
+                    Some(index) => {
+                        let mut base_item = base.set(index, ArrayType::Null(Null)).expect("Index was found");
+                        let item_path = format!("{}/{}", path, index);
+                        let result = merge_values(&item_path, &mut base_item, item, strategy, overridden);
+                        base.set(index, base_item).expect("Index was found");
+                        result?;
+                    }
+                    None => base.add(item.to_json())
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn identity<'r, 'a>(item: ArrayTypeRef<'r, 'a>, key: &str) -> Option<ArrayTypeRef<'r, 'a>> {
+    match item {
+        ArrayTypeRef::Object(object) => object.get(key),
+        _ => None
+    }
+}
+
+fn identity_matches(item: ArrayTypeRef, key: &str, expected: ArrayTypeRef) -> bool {
+    identity(item, key) == Some(expected)
+}
+
+fn is_number(value: ArrayTypeRef) -> bool {
+    matches!(value, ArrayTypeRef::Int(_) | ArrayTypeRef::Float(_))
+}
+
+#[derive(Debug)]
+pub struct MergeError {
+    path: String,
+    base: &'static str,
+    overlay: &'static str
+}
+
+impl MergeError {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "MergeError: type conflict at \"{}\": {} in the base, {} in the overlay",
+               self.path, self.base, self.overlay)
+    }
+}
+
+impl Error for MergeError {}
@@ -0,0 +1,57 @@
+use crate::data_structure::{JsonObject, ArrayType, ArrayTypeRef, ArrayTypeMut, Null};
+use crate::traits::ToJson;
+
+// JSON Merge Patch (RFC 7386). A null member deletes the key, an object member
+// is merged recursively and anything else replaces the old value.
+pub fn merge_patch(target: &mut JsonObject, patch: &JsonObject) {
+    for (key, value) in patch.iter() {
+        match value {
+            ArrayTypeRef::Null(_) => {
+                target.delete(key);
+            }
+            ArrayTypeRef::Object(patch) => {
+                if let Some(ArrayTypeMut::Object(target)) = target.get_mut(key) {
+                    merge_patch(target, patch);
+                    continue;
+                }
+
+                let mut object = JsonObject::new();
+                merge_patch(&mut object, patch);
+                target.insert(key.to_string(), ArrayType::Object(object));
+            }
+            value => {
+                target.insert(key.to_string(), value.to_json());
+            }
+        }
+    }
+}
+
+// Builds the merge patch that turns one document into the other. Merge patches
+// can't set a member to null, so null members of the new document are left out.
+pub fn create_merge_patch(from: &JsonObject, to: &JsonObject) -> JsonObject<'static> {
+    let mut patch = JsonObject::new();
+
+    for key in from.keys() {
+        if !to.contains_key(key) {
+            patch.insert(key.to_string(), ArrayType::Null(Null));
+        }
+    }
+
+    for (key, value) in to.iter() {
+        match (from.get(key), value) {
+            (_, ArrayTypeRef::Null(_)) => (),
+            (Some(ArrayTypeRef::Object(from)), ArrayTypeRef::Object(to)) => {
+                let nested = create_merge_patch(from, to);
+                if !nested.is_empty() {
+                    patch.insert(key.to_string(), ArrayType::Object(nested));
+                }
+            }
+            (Some(old), new) if old == new => (),
+            (_, new) => {
+                patch.insert(key.to_string(), new.to_json());
+            }
+        }
+    }
+
+    patch
+}
@@ -0,0 +1,321 @@
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+use crate::options::DEFAULT_MAX_DEPTH;
+
+// MessagePack (https://msgpack.org) for the same values as JSON. Integers use their
+// smallest encoding, floats are float 32, and object keys are sorted like dump does,
+// so that the output is reproducible. Decoding takes every integer and float format,
+// but like in JSON documents, integers that don't fit in an i32 and floats beyond the
+// f32 range are errors. So are binary and extension types, which have no JSON value,
+// and nesting deeper than the 128 levels that load allows by default.
+
+pub fn to_msgpack(object: &JsonObject) -> Vec<u8> {
+    let mut output = Vec::new();
+    encode_object(object, &mut output);
+
+    output
+}
+
+pub fn to_msgpack_value(value: &ArrayType) -> Vec<u8> {
+    let mut output = Vec::new();
+    encode_value(value.to_ref(), &mut output);
+
+    output
+}
+
+pub fn from_msgpack(bytes: &[u8]) -> Result<JsonObject<'static>, MsgpackError> {
+    match from_msgpack_value(bytes)? {
+        ArrayType::Object(object) => Ok(object),
+        _ => Err(MsgpackError::new("Expected a map", 0))
+    }
+}
+
+pub fn from_msgpack_value(bytes: &[u8]) -> Result<ArrayType<'static>, MsgpackError> {
+    let mut decoder = Decoder { bytes, current: 0, depth: 0 };
+    let value = decoder.decode_value()?;
+
+    if decoder.current < bytes.len() {
+        return Err(MsgpackError::new("Unexpected bytes after the value", decoder.current));
+    }
+
+    Ok(value)
+}
+
+fn encode_value(value: ArrayTypeRef, output: &mut Vec<u8>) {
+    match value {
+        ArrayTypeRef::Int(value) => encode_int(value, output),
+        ArrayTypeRef::Float(value) => {
+            output.push(0xca);
+            output.extend_from_slice(&value.to_be_bytes());
+        }
+        ArrayTypeRef::Bool(value) => output.push(if value { 0xc3 } else { 0xc2 }),
+        ArrayTypeRef::String(value) => encode_string(value, output),
+        ArrayTypeRef::Array(value) => encode_array(value, output),
+        ArrayTypeRef::Object(value) => encode_object(value, output),
+        ArrayTypeRef::Null(_) => output.push(0xc0)
+    }
+}
+
+fn encode_int(value: i32, output: &mut Vec<u8>) {
+    match value {
+        -32..=0x7f => output.push(value as u8),  // Positive and negative fixint
+        0x80..=0xff => output.extend_from_slice(&[0xcc, value as u8]),
+        0x100..=0xffff => {
+            output.push(0xcd);
+            output.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x10000.. => {
+            output.push(0xce);
+            output.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        -128..=-33 => output.extend_from_slice(&[0xd0, value as u8]),
+        -32768..=-129 => {
+            output.push(0xd1);
+            output.extend_from_slice(&(value as i16).to_be_bytes());
+        }
+        _ => {
+            output.push(0xd2);
+            output.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+fn encode_string(value: &str, output: &mut Vec<u8>) {
+    let length = value.len();
+
+    match length {
+        0..=31 => output.push(0xa0 | length as u8),
+        32..=0xff => output.extend_from_slice(&[0xd9, length as u8]),
+        0x100..=0xffff => {
+            output.push(0xda);
+            output.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        _ => {
+            output.push(0xdb);
+            output.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+
+    output.extend_from_slice(value.as_bytes());
+}
+
+// Arrays and maps share the length formats
+fn encode_length(length: usize, fix: u8, formats: [u8; 2], output: &mut Vec<u8>) {
+    match length {
+        0..=15 => output.push(fix | length as u8),
+        16..=0xffff => {
+            output.push(formats[0]);
+            output.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        _ => {
+            output.push(formats[1]);
+            output.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+}
+
+fn encode_array(array: &JsonArray, output: &mut Vec<u8>) {
+    encode_length(array.len(), 0x90, [0xdc, 0xdd], output);
+
+    for item in array.iter() {
+        encode_value(item, output);
+    }
+}
+
+fn encode_object(object: &JsonObject, output: &mut Vec<u8>) {
+    encode_length(object.len(), 0x80, [0xde, 0xdf], output);
+
+    let mut members: Vec<_> = object.iter().collect();
+    members.sort_by_key(|(key, _)| *key);
+
+    for (key, value) in members {
+        encode_string(key, output);
+        encode_value(value, output);
+    }
+}
+
+struct Decoder<'b> {
+    bytes: &'b [u8],
+    current: usize,
+    depth: usize  // How many arrays and maps the current value is in
+}
+
+impl<'b> Decoder<'b> {
+    fn decode_value(&mut self) -> Result<ArrayType<'static>, MsgpackError> {
+        let start = self.current;
+        let marker = self.read(1)?[0];
+
+        // Scalars are decoded apart, which keeps the recursion through arrays and maps light on the stack
+        match marker {
+            0x80..=0x8f => self.decode_map((marker & 0x0f) as usize),
+            0x90..=0x9f => self.decode_array((marker & 0x0f) as usize),
+            0xdc => {
+                let length = u16::from_be_bytes(self.read_array()?) as usize;
+                self.decode_array(length)
+            }
+            0xdd => {
+                let length = u32::from_be_bytes(self.read_array()?) as usize;
+                self.decode_array(length)
+            }
+            0xde => {
+                let length = u16::from_be_bytes(self.read_array()?) as usize;
+                self.decode_map(length)
+            }
+            0xdf => {
+                let length = u32::from_be_bytes(self.read_array()?) as usize;
+                self.decode_map(length)
+            }
+            _ => self.decode_scalar(marker, start)
+        }
+    }
+
+    fn decode_scalar(&mut self, marker: u8, start: usize) -> Result<ArrayType<'static>, MsgpackError> {
+        let value = match marker {
+            0x00..=0x7f => ArrayType::Int(marker as i32),
+            0xa0..=0xbf => self.decode_string((marker & 0x1f) as usize)?,
+            0xc0 => ArrayType::Null(Null),
+            0xc2 => ArrayType::Bool(false),
+            0xc3 => ArrayType::Bool(true),
+            0xca => ArrayType::Float(f32::from_be_bytes(self.read_array()?)),
+            0xcb => {
+                let value = f64::from_be_bytes(self.read_array()?);
+
+                if value.is_finite() && (value as f32).is_infinite() {
+                    return Err(MsgpackError::new("Number out of range for float", start));
+                }
+                ArrayType::Float(value as f32)
+            }
+            0xcc => ArrayType::Int(self.read_array::<1>()?[0] as i32),
+            0xcd => ArrayType::Int(u16::from_be_bytes(self.read_array()?) as i32),
+            0xce => integer(u32::from_be_bytes(self.read_array()?), start)?,
+            0xcf => integer(u64::from_be_bytes(self.read_array()?), start)?,
+            0xd0 => ArrayType::Int(i8::from_be_bytes(self.read_array()?) as i32),
+            0xd1 => ArrayType::Int(i16::from_be_bytes(self.read_array()?) as i32),
+            0xd2 => ArrayType::Int(i32::from_be_bytes(self.read_array()?)),
+            0xd3 => integer(i64::from_be_bytes(self.read_array()?), start)?,
+            0xd9 => {
+                let length = self.read_array::<1>()?[0] as usize;
+                self.decode_string(length)?
+            }
+            0xda => {
+                let length = u16::from_be_bytes(self.read_array()?) as usize;
+                self.decode_string(length)?
+            }
+            0xdb => {
+                let length = u32::from_be_bytes(self.read_array()?) as usize;
+                self.decode_string(length)?
+            }
+            0xe0..=0xff => ArrayType::Int(marker as i8 as i32),  // Negative fixint
+            0xc4..=0xc6 => return Err(MsgpackError::new("Binary data has no JSON value", start)),
+            0xc7..=0xc9 |
+            0xd4..=0xd8 => return Err(MsgpackError::new("Extension types have no JSON value", start)),
+            0xc1 => return Err(MsgpackError::new("Invalid marker 0xc1", start)),
+            _ => unreachable!("Arrays and maps are decoded by decode_value")
+        };
+
+        Ok(value)
+    }
+
+    fn decode_string(&mut self, length: usize) -> Result<ArrayType<'static>, MsgpackError> {
+        let start = self.current;
+        let bytes = self.read(length)?;
+
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(string) => Ok(ArrayType::String(string)),
+            Err(_) => Err(MsgpackError::new("Invalid UTF-8 in string", start))
+        }
+    }
+
+    // The lengths aren't trusted for allocating, the data runs out first if they're wrong
+    fn decode_array(&mut self, length: usize) -> Result<ArrayType<'static>, MsgpackError> {
+        let mut array = JsonArray::new();
+        self.enter_nested()?;
+
+        for _ in 0..length {
+            array.add(self.decode_value()?);
+        }
+
+        self.depth -= 1;
+        Ok(ArrayType::Array(array))
+    }
+
+    fn decode_map(&mut self, length: usize) -> Result<ArrayType<'static>, MsgpackError> {
+        let mut object = JsonObject::new();
+        self.enter_nested()?;
+
+        for _ in 0..length {
+            let start = self.current;
+            let key = match self.decode_value()? {
+                ArrayType::String(key) => key,
+                _ => return Err(MsgpackError::new("Map keys must be strings", start))
+            };
+
+            object.insert(key, self.decode_value()?);
+        }
+
+        self.depth -= 1;
+        Ok(ArrayType::Object(object))
+    }
+
+    // The decoder recurses into every array and map, so their nesting is limited
+    fn enter_nested(&mut self) -> Result<(), MsgpackError> {
+        if self.depth >= DEFAULT_MAX_DEPTH {
+            return Err(MsgpackError::new("Nesting deeper than 128 levels", self.current - 1));
+        }
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    fn read(&mut self, length: usize) -> Result<&'b [u8], MsgpackError> {
+        if self.bytes.len() - self.current < length {
+            return Err(MsgpackError::new("Unexpected end of data", self.bytes.len()));
+        }
+
+        let bytes = &self.bytes[self.current..self.current + length];
+        self.current += length;
+
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], MsgpackError> {
+        Ok(self.read(N)?.try_into().expect("Read N bytes"))
+    }
+}
+
+fn integer<T: TryInto<i32>>(value: T, start: usize) -> Result<ArrayType<'static>, MsgpackError> {
+    match value.try_into() {
+        Ok(value) => Ok(ArrayType::Int(value)),
+        Err(_) => Err(MsgpackError::new("Integer out of range for int", start))
+    }
+}
+
+#[derive(Debug)]
+pub struct MsgpackError {
+    message: &'static str,
+    offset: usize  // Of the value with the error, or of the end of the data
+}
+
+impl MsgpackError {
+    fn new(message: &'static str, offset: usize) -> Self {
+        Self {
+            message,
+            offset
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for MsgpackError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "MsgpackError: {}\nOffset: {}", self.message, self.offset)
+    }
+}
+
+impl Error for MsgpackError {}
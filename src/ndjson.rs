@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use crate::tokenizer::{ParseError, tokenize_with, offset_error_line};
+use crate::parser::parse_value_with;
+use crate::options::ParseOptions;
+use crate::data_structure::ArrayType;
+use crate::dumper::dump_value;
+
+// NDJSON and JSON Lines (https://jsonlines.org): one JSON value per line. Each line
+// is parsed on its own, so errors give the line in the whole input, and a bad line
+// doesn't stop the lines after it from being read.
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NdjsonOptions {
+    pub parse: ParseOptions,  // For each line, scalar_root is always set
+    pub skip_blank_lines: bool,  // Lines with only whitespace, otherwise they are errors
+    pub skip_invalid_lines: bool  // Lines that aren't valid JSON, I/O errors are still returned
+}
+
+pub struct NdjsonReader<R> {
+    reader: R,
+    options: NdjsonOptions,
+    line: i32,  // Lines read so far
+    done: bool
+}
+
+pub fn read_ndjson<R: BufRead>(reader: R) -> NdjsonReader<R> {
+    read_ndjson_with(reader, &NdjsonOptions::default())
+}
+
+pub fn read_ndjson_with<R: BufRead>(reader: R, options: &NdjsonOptions) -> NdjsonReader<R> {
+    let mut options = options.clone();
+    options.parse.scalar_root = true;
+
+    NdjsonReader {
+        reader,
+        options,
+        line: 0,
+        done: false
+    }
+}
+
+impl<R> NdjsonReader<R> {
+    // The number of the last line read, starting at 1
+    pub fn line(&self) -> i32 {
+        self.line
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    // None for a skipped blank line
+    fn parse_line(&self, bytes: &[u8]) -> Option<Result<ArrayType<'static>, Box<dyn Error>>> {
+        let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+
+        let contents = match std::str::from_utf8(bytes) {
+            Ok(contents) => contents,
+            Err(error) => {
+                let valid = std::str::from_utf8(&bytes[..error.valid_up_to()]).expect("Checked by from_utf8");
+                let error = ParseError::new("Invalid UTF-8".to_string(), self.line, valid.chars().count() as i32);
+                return Some(Err(Box::new(error)));
+            }
+        };
+
+        if self.options.skip_blank_lines && contents.trim().is_empty() {
+            return None;
+        }
+
+        let value = tokenize_with(contents.to_string(), &self.options.parse)
+            .and_then(|tokens| parse_value_with(tokens, &self.options.parse))
+            .map_err(|error| offset_error_line(error, self.line - 1));
+
+        Some(value)
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<ArrayType<'static>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = Vec::new();
+
+        while !self.done {
+            bytes.clear();
+
+            match self.reader.read_until(b'\n', &mut bytes) {
+                Ok(0) => {
+                    self.done = true;
+                    return None;
+                },
+                Ok(_) => self.line += 1,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(Box::new(error)));
+                }
+            }
+
+            match self.parse_line(&bytes) {
+                None => continue,
+                Some(Err(_)) if self.options.skip_invalid_lines => continue,
+                value => return value
+            }
+        }
+
+        None
+    }
+}
+
+// Writes one compact value per line
+pub struct NdjsonWriter<W> {
+    writer: W
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        NdjsonWriter {
+            writer
+        }
+    }
+
+    pub fn write(&mut self, value: &ArrayType) -> io::Result<()> {
+        let mut output = String::new();
+        dump_value(value.to_ref(), false, 0, &mut output);
+        output.push('\n');
+
+        self.writer.write_all(output.as_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
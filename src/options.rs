@@ -0,0 +1,55 @@
+// Options for load_with and load_str_with. The defaults are strict RFC 8259,
+// except that duplicate keys are allowed and the last one wins. Nesting is limited
+// to 128 levels by default, as the parser recurses into every object and array.
+
+pub (crate) const DEFAULT_MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub comments: CommentMode,
+    pub trailing_commas: bool,
+    pub duplicate_keys: DuplicateKeys,
+    pub control_characters: bool,  // Raw U+0000 to U+001F characters in strings, line breaks included
+    pub leading_zeros: bool,  // Numbers like 007
+    pub non_finite_numbers: bool,  // The NaN, Infinity and -Infinity literals
+    pub scalar_root: bool,  // A lone string, number, boolean or null as the document, see load_value_with
+    pub max_depth: Option<usize>,  // How deeply objects and arrays may be nested, None for no limit
+    pub max_string_length: Option<usize>,  // In characters, for keys too
+    pub max_number_length: Option<usize>  // In characters, as written
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            comments: CommentMode::default(),
+            trailing_commas: false,
+            duplicate_keys: DuplicateKeys::default(),
+            control_characters: false,
+            leading_zeros: false,
+            non_finite_numbers: false,
+            scalar_root: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_string_length: None,
+            max_number_length: None
+        }
+    }
+}
+
+// What happens to "//" line comments and "/* */" block comments (JSONC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommentMode {
+    #[default]
+    Error,
+    Skip,
+    Keep  // Attaches comments to the member or item that follows them, see JsonObject::comments
+}
+
+// What happens when an object has the same key more than once. Error fails with
+// a DuplicateKeyError; find_duplicate_keys lists every duplicate instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    Error,
+    FirstWins,
+    #[default]
+    LastWins
+}
@@ -1,9 +1,491 @@
-use crate::tokenizer::Token;
-use crate::data_structure::JsonObject;
+use std::collections::HashMap;
+use std::error::Error;
 
-pub (crate) fn parse<'object>(tokens: Vec<Token>) -> JsonObject<'object> {
-    
+use crate::tokenizer::{Token, Position, ParseError, EofError, DuplicateKeyError};
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, Null};
+use crate::options::{ParseOptions, DuplicateKeys};
+use crate::diagnostic::Diagnostic;
 
+// Where the parser is in the token list, along with everything it carries between values
+pub (crate) struct ParseState<'o> {
+    pub (crate) current: usize,
+    options: &'o ParseOptions,
+    depth: usize,  // How many objects and arrays the current token is in
+    duplicates: Option<Vec<DuplicateKeyError>>,  // Set to collect every duplicate key instead of failing
+    diagnostics: Option<Vec<Diagnostic>>,  // Set to collect errors and carry on, see report
+    comments: HashMap<usize, Vec<String>>  // Kept comments, by the index of the token after them
+}
+
+impl<'o> ParseState<'o> {
+    pub (crate) fn new(options: &'o ParseOptions) -> Self {
+        Self {
+            current: 0,
+            options,
+            depth: 0,
+            duplicates: None,
+            diagnostics: None,
+            comments: HashMap::new()
+        }
+    }
+}
+
+pub (crate) fn parse<'object>(tokens: Vec<(Token, Position)>) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    parse_with(tokens, &ParseOptions::default())
+}
+
+pub (crate) fn parse_with<'object>(tokens: Vec<(Token, Position)>,
+                                   options: &ParseOptions) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let mut state = ParseState::new(options);
+    let tokens = split_comments(tokens, &mut state);
+
+    parse_document(&tokens, &mut state)
+}
+
+// Parses as much of the document as possible, adding every error to the diagnostics
+pub (crate) fn parse_recovering<'object>(tokens: Vec<(Token, Position)>, options: &ParseOptions,
+                                         diagnostics: Vec<Diagnostic>) -> (JsonObject<'object>, Vec<Diagnostic>) {
+    let mut state = ParseState::new(options);
+    state.diagnostics = Some(diagnostics);
+    let tokens = split_comments(tokens, &mut state);
+
+    let object = parse_document(&tokens, &mut state).expect("Errors are collected when recovering");
+
+    (object, state.diagnostics.unwrap_or_default())
+}
+
+fn parse_document<'object>(tokens: &[(Token, Position)],
+                           state: &mut ParseState) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let header = take_comments(state);
+
+    let mut object = match &tokens[state.current] {
+        (Token::LeftBrace, _) => match parse_object(tokens, state) {
+            Ok(object) => object,
+            Err(error) => {
+                report(state, error)?;
+                JsonObject::new()
+            }
+        },
+        (Token::Eof, position) => {
+            report(state, eof_error(position))?;
+            JsonObject::new()
+        }
+        (_, position) => {
+            report(state, unexpected_token("Expected an object at the top level", position))?;
+            JsonObject::new()
+        }
+    };
+
+    expect_end(tokens, state)?;
+
+    object.set_header_comments(header);
+    object.set_footer_comments(take_comments(state));
+
+    Ok(object)
+}
+
+// Any value can be the document, but scalars only with the scalar_root option
+pub (crate) fn parse_value_with<'object>(tokens: Vec<(Token, Position)>,
+                                         options: &ParseOptions) -> Result<ArrayType<'object>, Box<dyn Error>> {
+    let mut state = ParseState::new(options);
+    let tokens = split_comments(tokens, &mut state);
+
+    parse_root(&tokens, &mut state)
+}
+
+// Parses the whole document and returns every duplicate key in it, whatever the policy
+pub (crate) fn find_duplicates(tokens: Vec<(Token, Position)>,
+                               options: &ParseOptions) -> Result<Vec<DuplicateKeyError>, Box<dyn Error>> {
+    let mut state = ParseState::new(options);
+    state.duplicates = Some(Vec::new());
+    let tokens = split_comments(tokens, &mut state);
+
+    parse_root(&tokens, &mut state)?;
+
+    Ok(state.duplicates.unwrap_or_default())
+}
+
+fn parse_root<'object>(tokens: &[(Token, Position)],
+                       state: &mut ParseState) -> Result<ArrayType<'object>, Box<dyn Error>> {
+    let header = take_comments(state);
+
+    let mut value = match &tokens[state.current] {
+        (Token::LeftBrace, _) | (Token::LeftBracket, _) => parse_value(tokens, state)?,
+        (Token::Eof, position) => return Err(eof_error(position)),
+        (_, position) if !state.options.scalar_root => {
+            return Err(unexpected_token("Expected an object or an array at the top level", position))
+        }
+        _ => parse_value(tokens, state)?
+    };
+
+    expect_end(tokens, state)?;
+
+    let footer = take_comments(state);
+
+    match &mut value {
+        ArrayType::Object(object) => {
+            object.set_header_comments(header);
+            object.set_footer_comments(footer);
+        }
+        ArrayType::Array(array) => {
+            array.set_header_comments(header);
+            array.set_footer_comments(footer);
+        }
+        // A scalar has nowhere to keep them, so they are dropped as if they were skipped
+        _ => ()
+    }
+
+    Ok(value)
+}
+
+fn expect_end(tokens: &[(Token, Position)], state: &mut ParseState) -> Result<(), Box<dyn Error>> {
+    match &tokens[state.current] {
+        (Token::Eof, _) => Ok(()),
+        (_, position) => report(state, unexpected_token("Unexpected token after the end of the document",
+                                                        position))
+    }
+}
+
+// Takes the comment tokens out, so that the rest of the parser never sees them
+fn split_comments(tokens: Vec<(Token, Position)>, state: &mut ParseState) -> Vec<(Token, Position)> {
+    let mut remaining = Vec::with_capacity(tokens.len());
+
+    for (token, position) in tokens {
+        match token {
+            Token::Comment(comment) => {
+                state.comments.entry(remaining.len()).or_default().push(comment);
+            }
+            token => remaining.push((token, position))
+        }
+    }
+
+    remaining
+}
+
+// The comments before the current token, if any were kept
+fn take_comments(state: &mut ParseState) -> Vec<String> {
+    state.comments.remove(&state.current).unwrap_or_default()
+}
+
+// The comments between a value and the comma after it. Those before a closing
+// bracket are left to be the trailing comments of the object or array.
+fn take_separator_comments(tokens: &[(Token, Position)], state: &mut ParseState) -> Vec<String> {
+    match &tokens[state.current] {
+        (Token::Comma, _) => take_comments(state),
+        _ => Vec::new()
+    }
+}
+
+// Parses any value starting at the current token, leaving the current token
+// right after the value
+pub (crate) fn parse_value<'object>(tokens: &[(Token, Position)],
+                                    state: &mut ParseState) -> Result<ArrayType<'object>, Box<dyn Error>> {
+    let (token, position) = &tokens[state.current];
+
+    match token {
+        Token::LeftBrace => Ok(ArrayType::Object(parse_object(tokens, state)?)),
+        Token::LeftBracket => Ok(ArrayType::Array(parse_array(tokens, state)?)),
+        Token::String(value) => {
+            state.current += 1;
+            Ok(ArrayType::String(value.clone()))
+        }
+        Token::Number(value) => {
+            state.current += 1;
+            parse_number(value, position)
+        }
+        Token::Keyword(value) => {
+            state.current += 1;
+            match value.as_str() {
+                "true" => Ok(ArrayType::Bool(true)),
+                "false" => Ok(ArrayType::Bool(false)),
+                _ => Ok(ArrayType::Null(Null))
+            }
+        }
+        Token::Eof => Err(eof_error(position)),
+        _ => Err(unexpected_token("Expected a value", position))
+    }
+}
+
+fn parse_object<'object>(tokens: &[(Token, Position)],
+                         state: &mut ParseState) -> Result<JsonObject<'object>, Box<dyn Error>> {
+    let mut object = JsonObject::new();
+    let mut key_positions: HashMap<String, &Position> = HashMap::new();
+
+    enter_nested(tokens, state)?;
+    state.current += 1;  // Skip the left brace
+
+    if let (Token::RightBrace, _) = &tokens[state.current] {
+        object.set_trailing_comments(take_comments(state));
+        state.current += 1;
+        state.depth -= 1;
+        return Ok(object);
+    }
+
+    loop {
+        if let Err(error) = parse_member(tokens, state, &mut object, &mut key_positions) {
+            recover(tokens, state, error)?;
+        }
+
+        if !next_member(tokens, state, &mut object)? {
+            break;
+        }
+    }
+
+    state.depth -= 1;
+    Ok(object)
+}
+
+fn parse_member<'t>(tokens: &'t [(Token, Position)], state: &mut ParseState, object: &mut JsonObject,
+                    key_positions: &mut HashMap<String, &'t Position>) -> Result<(), Box<dyn Error>> {
+    // Comments anywhere from the key to the comma after the value belong to the member
+    let mut comments = take_comments(state);
+
+    let (key, key_position) = match &tokens[state.current] {
+        (Token::String(key), position) | (Token::Identifier(key), position) => (key.clone(), position),
+        (Token::Eof, position) => return Err(eof_error(position)),
+        (_, position) => return Err(unexpected_token("Expected a key", position))
+    };
+    state.current += 1;
+    comments.extend(take_comments(state));
+
+    match &tokens[state.current] {
+        (Token::Colon, _) => state.current += 1,
+        (Token::Eof, position) => return Err(eof_error(position)),
+        (_, position) => return Err(unexpected_token("Expected a colon", position))
+    }
+    comments.extend(take_comments(state));
+
+    let value = parse_value(tokens, state)?;
+    comments.extend(take_separator_comments(tokens, state));
+    object.set_comments(key.clone(), comments);
+
+    match key_positions.get(key.as_str()) {
+        None => {
+            key_positions.insert(key.clone(), key_position);
+            object.insert(key, value);
+        }
+        Some(first_position) => {
+            let duplicate = DuplicateKeyError::new(key.clone(), first_position, key_position);
+
+            match &mut state.duplicates {
+                Some(duplicates) => duplicates.push(duplicate),
+                None if state.options.duplicate_keys == DuplicateKeys::Error => {
+                    return Err(Box::new(duplicate));
+                }
+                None => ()
+            }
+
+            if state.options.duplicate_keys != DuplicateKeys::FirstWins {
+                object.insert(key, value);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Moves past the comma or the right brace after a member, telling whether another member follows.
+// When recovering, a missing comma before a key is assumed and an object that isn't
+// closed ends at the end of the file or at the right bracket of an outer array.
+fn next_member(tokens: &[(Token, Position)], state: &mut ParseState,
+               object: &mut JsonObject) -> Result<bool, Box<dyn Error>> {
+    loop {
+        match &tokens[state.current] {
+            (Token::Comma, _) => {
+                state.current += 1;
+
+                if let (Token::RightBrace, _) = &tokens[state.current] {
+                    if state.options.trailing_commas {
+                        object.set_trailing_comments(take_comments(state));
+                        state.current += 1;
+                        return Ok(false);
+                    }
+                }
+                return Ok(true);
+            }
+            (Token::RightBrace, _) => {
+                object.set_trailing_comments(take_comments(state));
+                state.current += 1;
+                return Ok(false);
+            }
+            (Token::Eof, position) => {
+                report(state, eof_error(position))?;
+                return Ok(false);
+            }
+            (Token::Invalid, _) => skip_to_separator(tokens, state),
+            (token, position) => {
+                report(state, unexpected_token("Expected a comma or a right brace", position))?;
+
+                match token {
+                    Token::String(_) => return Ok(true),
+                    Token::RightBracket => return Ok(false),
+                    _ => skip_to_separator(tokens, state)
+                }
+            }
+        }
+    }
+}
+
+fn parse_array<'object>(tokens: &[(Token, Position)],
+                        state: &mut ParseState) -> Result<JsonArray<'object>, Box<dyn Error>> {
+    let mut array = JsonArray::new();
+
+    enter_nested(tokens, state)?;
+    state.current += 1;  // Skip the left bracket
+
+    if let (Token::RightBracket, _) = &tokens[state.current] {
+        array.set_trailing_comments(take_comments(state));
+        state.current += 1;
+        state.depth -= 1;
+        return Ok(array);
+    }
+
+    loop {
+        let mut comments = take_comments(state);
+
+        match parse_value(tokens, state) {
+            Ok(value) => {
+                comments.extend(take_separator_comments(tokens, state));
+                array.set_comments(array.len(), comments);
+                array.add(value);
+            }
+            Err(error) => recover(tokens, state, error)?
+        }
+
+        if !next_item(tokens, state, &mut array)? {
+            break;
+        }
+    }
+
+    state.depth -= 1;
+    Ok(array)
+}
+
+// The same as next_member, for the comma or the right bracket after an item
+fn next_item(tokens: &[(Token, Position)], state: &mut ParseState,
+             array: &mut JsonArray) -> Result<bool, Box<dyn Error>> {
+    loop {
+        match &tokens[state.current] {
+            (Token::Comma, _) => {
+                state.current += 1;
+
+                if let (Token::RightBracket, _) = &tokens[state.current] {
+                    if state.options.trailing_commas {
+                        array.set_trailing_comments(take_comments(state));
+                        state.current += 1;
+                        return Ok(false);
+                    }
+                }
+                return Ok(true);
+            }
+            (Token::RightBracket, _) => {
+                array.set_trailing_comments(take_comments(state));
+                state.current += 1;
+                return Ok(false);
+            }
+            (Token::Eof, position) => {
+                report(state, eof_error(position))?;
+                return Ok(false);
+            }
+            (Token::Invalid, _) => skip_to_separator(tokens, state),
+            (token, position) => {
+                report(state, unexpected_token("Expected a comma or a right bracket", position))?;
+
+                match token {
+                    Token::String(_) | Token::Number(_) | Token::Keyword(_) |
+                    Token::LeftBrace | Token::LeftBracket => return Ok(true),
+                    Token::RightBrace => return Ok(false),
+                    _ => skip_to_separator(tokens, state)
+                }
+            }
+        }
+    }
+}
+
+fn enter_nested(tokens: &[(Token, Position)], state: &mut ParseState) -> Result<(), Box<dyn Error>> {
+    match state.options.max_depth {
+        Some(max_depth) if state.depth >= max_depth => {
+            Err(unexpected_token(&format!("Nesting deeper than {} levels", max_depth),
+                                 &tokens[state.current].1))
+        }
+        _ => {
+            state.depth += 1;
+            Ok(())
+        }
+    }
+}
+
+// Error recovery. Without diagnostics to collect into, errors are returned as they are.
+
+fn report(state: &mut ParseState, error: Box<dyn Error>) -> Result<(), Box<dyn Error>> {
+    let diagnostics = match &mut state.diagnostics {
+        Some(diagnostics) => diagnostics,
+        None => return Err(error)
+    };
+
+    // Skipping tokens can lead to the same error being found twice
+    let diagnostic = Diagnostic::new(error);
+    let is_repeated = diagnostics.last().is_some_and(|last| {
+        (last.line(), last.column()) == (diagnostic.line(), diagnostic.column())
+    });
+
+    if !is_repeated {
+        diagnostics.push(diagnostic);
+    }
+
+    Ok(())
+}
+
+// Reports the error, unless it's about a token that the tokenizer already reported,
+// then skips the rest of the member or item
+fn recover(tokens: &[(Token, Position)], state: &mut ParseState, error: Box<dyn Error>) -> Result<(), Box<dyn Error>> {
+    if let (Token::Invalid, _) = &tokens[state.current] {
+        if state.diagnostics.is_some() {
+            skip_to_separator(tokens, state);
+            return Ok(());
+        }
+    }
+
+    report(state, error)?;
+    skip_to_separator(tokens, state);
+
+    Ok(())
+}
+
+// Skips to the next comma or closing bracket that isn't inside a nested object or array
+fn skip_to_separator(tokens: &[(Token, Position)], state: &mut ParseState) {
+    let mut nesting = 0;
+
+    loop {
+        match &tokens[state.current].0 {
+            Token::Eof => return,
+            Token::Comma | Token::RightBrace | Token::RightBracket if nesting == 0 => return,
+            Token::LeftBrace | Token::LeftBracket => nesting += 1,
+            Token::RightBrace | Token::RightBracket => nesting -= 1,
+            _ => ()
+        }
+        state.current += 1;
+    }
+}
+
+// Numbers without a fractional part or an exponent are ints, unless they are too big
+// for an i32. Those and every other number are floats.
+fn parse_number<'object>(number: &str, position: &Position) -> Result<ArrayType<'object>, Box<dyn Error>> {
+    if !number.contains(['.', 'e', 'E']) {
+        if let Ok(value) = number.parse::<i32>() {
+            return Ok(ArrayType::Int(value));
+        }
+    }
+
+    match number.parse::<f32>() {
+        Ok(value) => Ok(ArrayType::Float(value)),
+        Err(_) => Err(unexpected_token("Invalid number format", position))
+    }
+}
+
+fn unexpected_token(message: &str, position: &Position) -> Box<dyn Error> {
+    Box::new(ParseError::new(message.to_string(), position.line, position.column))
+}
 
-    JsonObject::new()
+fn eof_error(position: &Position) -> Box<dyn Error> {
+    Box::new(EofError::new("Unexpected end of file", position.line, position.column))
 }
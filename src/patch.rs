@@ -0,0 +1,337 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef};
+use crate::conversion::ConversionError;
+use crate::traits::{ToJson, FromJson};
+use crate::pointer::{PointerError, parse_pointer, escape};
+
+// JSON Patch (RFC 6902) operations. Paths are JSON Pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+    Add { path: String, value: ArrayType<'static> },
+    Remove { path: String },
+    Replace { path: String, value: ArrayType<'static> },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: ArrayType<'static> }
+}
+
+// Applies every operation in order. If any of them fails, the document is left untouched.
+pub fn apply_patch(document: &mut JsonObject, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    let mut working = ArrayType::Object(document.clone());
+
+    for (i, operation) in patch.iter().enumerate() {
+        apply_operation(&mut working, operation)
+            .map_err(|kind| PatchError { operation: i, kind })?;
+    }
+
+    match working {
+        ArrayType::Object(object) => {
+            *document = object;
+            Ok(())
+        }
+        _ => Err(PatchError { operation: patch.len() - 1, kind: PatchErrorKind::RootNotObject })
+    }
+}
+
+// Builds a patch that turns one document into the other. Objects and arrays are
+// compared recursively, so that only the values that differ are touched.
+pub fn create_patch(from: &JsonObject, to: &JsonObject) -> Vec<PatchOperation> {
+    let mut patch = Vec::new();
+    diff_objects("", from, to, &mut patch);
+
+    patch
+}
+
+fn apply_operation(document: &mut ArrayType, operation: &PatchOperation) -> Result<(), PatchErrorKind> {
+    match operation {
+        PatchOperation::Add { path, value } => {
+            document.pointer_insert(path, value.clone())?;
+        }
+        PatchOperation::Remove { path } => {
+            document.pointer_remove(path)?;
+        }
+        PatchOperation::Replace { path, value } => {
+            if path.is_empty() {
+                *document = value.clone();
+            } else {
+                document.pointer_remove(path)?;
+                document.pointer_insert(path, value.clone())?;
+            }
+        }
+        PatchOperation::Move { from, path } => {
+            // A value can't be moved into one of its own children
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(PatchErrorKind::MoveIntoChild { from: from.clone(), path: path.clone() });
+            }
+            if from != path {
+                let value = document.pointer_remove(from)?;
+                document.pointer_insert(path, value)?;
+            }
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = match document.pointer(from) {
+                Some(value) => ArrayType::from(value),
+                None => return Err(PointerError::NotFound(from.clone()).into())
+            };
+            document.pointer_insert(path, value)?;
+        }
+        PatchOperation::Test { path, value } => {
+            let matches = match document.pointer(path) {
+                Some(found) => ArrayType::from(found).eq_numeric(value),
+                None => false
+            };
+            if !matches {
+                return Err(PatchErrorKind::TestFailed { path: path.clone() });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn diff_values(path: &str, from: ArrayTypeRef, to: ArrayTypeRef, patch: &mut Vec<PatchOperation>) {
+    match (from, to) {
+        (ArrayTypeRef::Object(from), ArrayTypeRef::Object(to)) => diff_objects(path, from, to, patch),
+        (ArrayTypeRef::Array(from), ArrayTypeRef::Array(to)) => diff_arrays(path, from, to, patch),
+        (from, to) => {
+            if from != to {
+                patch.push(PatchOperation::Replace { path: path.to_string(), value: to.to_json() });
+            }
+        }
+    }
+}
+
+fn diff_objects(path: &str, from: &JsonObject, to: &JsonObject, patch: &mut Vec<PatchOperation>) {
+    let mut from_keys: Vec<&str> = from.keys().collect();
+    let mut to_keys: Vec<&str> = to.keys().collect();
+    from_keys.sort_unstable();
+    to_keys.sort_unstable();
+
+    for key in from_keys {
+        let member_path = format!("{}/{}", path, escape(key));
+
+        match to.get(key) {
+            Some(to_value) => {
+                diff_values(&member_path, from.get(key).expect("Key exists"), to_value, patch);
+            }
+            None => patch.push(PatchOperation::Remove { path: member_path })
+        }
+    }
+
+    for key in to_keys {
+        if !from.contains_key(key) {
+            patch.push(PatchOperation::Add {
+                path: format!("{}/{}", path, escape(key)),
+                value: to.get(key).expect("Key exists").to_json()
+            });
+        }
+    }
+}
+
+// Items are aligned on their longest common subsequence, like diff does, so that an
+// item inserted or removed in the middle doesn't replace every item after it. Items
+// between two aligned ones are compared pairwise, the rest are removed or added.
+// Operations apply in order, so every path is the index the item has at that point,
+// which is its index in the new array.
+fn diff_arrays(path: &str, from: &JsonArray, to: &JsonArray, patch: &mut Vec<PatchOperation>) {
+    let from_items: Vec<ArrayTypeRef> = from.iter().collect();
+    let to_items: Vec<ArrayTypeRef> = to.iter().collect();
+
+    let mut from_start = 0;
+    let mut to_start = 0;
+
+    let end = (from_items.len(), to_items.len());
+
+    for (from_end, to_end) in longest_common_subsequence(&from_items, &to_items).into_iter()
+        .chain(std::iter::once(end)) {
+        let paired = (from_end - from_start).min(to_end - to_start);
+
+        for i in 0..paired {
+            diff_values(&format!("{}/{}", path, to_start + i), from_items[from_start + i],
+                        to_items[to_start + i], patch);
+        }
+        for _ in from_start + paired..from_end {
+            patch.push(PatchOperation::Remove { path: format!("{}/{}", path, to_start + paired) });
+        }
+        for (i, item) in to_items.iter().enumerate().take(to_end).skip(to_start + paired) {
+            patch.push(PatchOperation::Add { path: format!("{}/{}", path, i), value: item.to_json() });
+        }
+
+        from_start = from_end + 1;
+        to_start = to_end + 1;
+    }
+}
+
+// Longest common subsequences of arrays with more items in between their common
+// start and end than this are not searched, as the table takes the product in memory
+const MAX_TABLE_SIZE: usize = 1 << 22;
+
+// Index pairs of equal items, in order. Items in the common start and end are paired
+// directly, and if the rest is too large, its items are left to be compared by index.
+pub (crate) fn longest_common_subsequence(old: &[ArrayTypeRef], new: &[ArrayTypeRef]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new.iter()).take_while(|(old, new)| old == new).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+
+    let (old_middle, new_middle) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    if (old_middle.len() + 1).saturating_mul(new_middle.len() + 1) <= MAX_TABLE_SIZE {
+        pairs.extend(table_subsequence(old_middle, new_middle).into_iter()
+            .map(|(i, j)| (prefix + i, prefix + j)));
+    }
+
+    pairs.extend((0..suffix).rev().map(|i| (old.len() - 1 - i, new.len() - 1 - i)));
+
+    pairs
+}
+
+fn table_subsequence(old: &[ArrayTypeRef], new: &[ArrayTypeRef]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+impl ToJson for PatchOperation {
+    fn to_json(&self) -> ArrayType<'static> {
+        let mut object = JsonObject::new();
+
+        let (name, path) = match self {
+            PatchOperation::Add { path, .. } => ("add", path),
+            PatchOperation::Remove { path } => ("remove", path),
+            PatchOperation::Replace { path, .. } => ("replace", path),
+            PatchOperation::Move { path, .. } => ("move", path),
+            PatchOperation::Copy { path, .. } => ("copy", path),
+            PatchOperation::Test { path, .. } => ("test", path)
+        };
+        object.insert("op", ArrayType::from(name));
+        object.insert("path", ArrayType::from(path.as_str()));
+
+        match self {
+            PatchOperation::Add { value, .. } |
+            PatchOperation::Replace { value, .. } |
+            PatchOperation::Test { value, .. } => {
+                object.insert("value", value.clone());
+            }
+            PatchOperation::Move { from, .. } |
+            PatchOperation::Copy { from, .. } => {
+                object.insert("from", ArrayType::from(from.as_str()));
+            }
+            PatchOperation::Remove { .. } => ()
+        }
+
+        ArrayType::Object(object)
+    }
+}
+
+impl FromJson for PatchOperation {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        let object = match value {
+            ArrayTypeRef::Object(object) => object,
+            other => return Err(ConversionError::WrongType { expected: "object", found: other.type_name() })
+        };
+
+        let member = |key: &str| match object.get(key) {
+            Some(value) => Ok(value),
+            None => Err(ConversionError::MissingField { field: key.to_string() })
+        };
+
+        let op = String::from_json(member("op")?)?;
+        let pointer = |key: &str| {
+            let pointer = String::from_json(member(key)?)?;
+            parse_pointer(&pointer).map_err(|_| ConversionError::WrongType { expected: "pointer", found: "string" })?;
+
+            Ok(pointer)
+        };
+        let path = pointer("path")?;
+
+        let operation = match op.as_str() {
+            "add" => PatchOperation::Add { path, value: member("value")?.to_json() },
+            "remove" => PatchOperation::Remove { path },
+            "replace" => PatchOperation::Replace { path, value: member("value")?.to_json() },
+            "move" => PatchOperation::Move { from: pointer("from")?, path },
+            "copy" => PatchOperation::Copy { from: pointer("from")?, path },
+            "test" => PatchOperation::Test { path, value: member("value")?.to_json() },
+            _ => return Err(ConversionError::UnknownVariant { variant: op })
+        };
+
+        Ok(operation)
+    }
+}
+
+#[derive(Debug)]
+pub struct PatchError {
+    operation: usize,  // Index of the failed operation in the patch
+    kind: PatchErrorKind
+}
+
+#[derive(Debug)]
+pub enum PatchErrorKind {
+    Pointer(PointerError),
+    TestFailed { path: String },
+    MoveIntoChild { from: String, path: String },
+    RootNotObject
+}
+
+impl PatchError {
+    pub fn operation(&self) -> usize {
+        self.operation
+    }
+
+    pub fn kind(&self) -> &PatchErrorKind {
+        &self.kind
+    }
+}
+
+impl From<PointerError> for PatchErrorKind {
+    fn from(error: PointerError) -> Self {
+        PatchErrorKind::Pointer(error)
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "PatchError: operation {}: ", self.operation)?;
+
+        match &self.kind {
+            PatchErrorKind::Pointer(error) => write!(formatter, "{}", error),
+            PatchErrorKind::TestFailed { path } => write!(formatter, "test failed at \"{}\"", path),
+            PatchErrorKind::MoveIntoChild { from, path } => {
+                write!(formatter, "can't move \"{}\" into its child \"{}\"", from, path)
+            }
+            PatchErrorKind::RootNotObject => write!(formatter, "the document is no longer an object")
+        }
+    }
+}
+
+impl Error for PatchError {}
@@ -0,0 +1,245 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, ArrayTypeMut};
+
+// JSON Pointer (RFC 6901) addressing, like "/foo/bar/0". The empty pointer
+// refers to the whole document, "~1" stands for "/" and "~0" for "~".
+// When inserting, "-" refers to the position after the last item of an array.
+
+impl<'a> JsonObject<'a> {
+    pub fn pointer(&self, pointer: &str) -> Option<ArrayTypeRef<'_, 'a>> {
+        resolve(ArrayTypeRef::Object(self), &parse_pointer(pointer).ok()?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<ArrayTypeMut<'_, 'a>> {
+        resolve_mut(ArrayTypeMut::Object(self), &parse_pointer(pointer).ok()?)
+    }
+
+    // Adds or replaces the value, returning the old one if a member was replaced
+    pub fn pointer_insert(&mut self, pointer: &str,
+                          value: ArrayType<'a>) -> Result<Option<ArrayType<'a>>, PointerError> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() {
+            return Err(PointerError::Root);
+        }
+
+        insert(ArrayTypeMut::Object(self), &tokens, value)
+    }
+
+    pub fn pointer_remove(&mut self, pointer: &str) -> Result<ArrayType<'a>, PointerError> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() {
+            return Err(PointerError::Root);
+        }
+
+        remove(ArrayTypeMut::Object(self), &tokens)
+    }
+}
+
+impl<'a> JsonArray<'a> {
+    pub fn pointer(&self, pointer: &str) -> Option<ArrayTypeRef<'_, 'a>> {
+        resolve(ArrayTypeRef::Array(self), &parse_pointer(pointer).ok()?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<ArrayTypeMut<'_, 'a>> {
+        resolve_mut(ArrayTypeMut::Array(self), &parse_pointer(pointer).ok()?)
+    }
+
+    pub fn pointer_insert(&mut self, pointer: &str,
+                          value: ArrayType<'a>) -> Result<Option<ArrayType<'a>>, PointerError> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() {
+            return Err(PointerError::Root);
+        }
+
+        insert(ArrayTypeMut::Array(self), &tokens, value)
+    }
+
+    pub fn pointer_remove(&mut self, pointer: &str) -> Result<ArrayType<'a>, PointerError> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() {
+            return Err(PointerError::Root);
+        }
+
+        remove(ArrayTypeMut::Array(self), &tokens)
+    }
+}
+
+impl<'a> ArrayType<'a> {
+    pub fn pointer(&self, pointer: &str) -> Option<ArrayTypeRef<'_, 'a>> {
+        resolve(self.to_ref(), &parse_pointer(pointer).ok()?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<ArrayTypeMut<'_, 'a>> {
+        resolve_mut(self.to_mut(), &parse_pointer(pointer).ok()?)
+    }
+
+    // Inserting at the empty pointer replaces the whole value
+    pub fn pointer_insert(&mut self, pointer: &str,
+                          value: ArrayType<'a>) -> Result<Option<ArrayType<'a>>, PointerError> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() {
+            return Ok(Some(std::mem::replace(self, value)));
+        }
+
+        insert(self.to_mut(), &tokens, value)
+    }
+
+    pub fn pointer_remove(&mut self, pointer: &str) -> Result<ArrayType<'a>, PointerError> {
+        let tokens = parse_pointer(pointer)?;
+
+        if tokens.is_empty() {
+            return Err(PointerError::Root);
+        }
+
+        remove(self.to_mut(), &tokens)
+    }
+}
+
+pub (crate) fn parse_pointer(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if !pointer.starts_with('/') {
+        return Err(PointerError::Invalid(pointer.to_string()));
+    }
+
+    pointer[1..].split('/').map(|token| unescape(token, pointer)).collect()
+}
+
+pub (crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(token: &str, pointer: &str) -> Result<String, PointerError> {
+    let mut result = String::new();
+    let mut characters = token.chars();
+
+    while let Some(character) = characters.next() {
+        if character == '~' {
+            match characters.next() {
+                Some('0') => result.push('~'),
+                Some('1') => result.push('/'),
+                _ => return Err(PointerError::Invalid(pointer.to_string()))
+            }
+        } else {
+            result.push(character);
+        }
+    }
+
+    Ok(result)
+}
+
+// Array indices are plain decimal numbers without leading zeros
+pub (crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || token.len() > 1 && token.starts_with('0') ||
+       !token.chars().all(|character| character.is_ascii_digit()) {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+fn resolve<'r, 'a>(value: ArrayTypeRef<'r, 'a>, tokens: &[String]) -> Option<ArrayTypeRef<'r, 'a>> {
+    let mut current = value;
+
+    for token in tokens {
+        current = match current {
+            ArrayTypeRef::Object(object) => object.get(token)?,
+            ArrayTypeRef::Array(array) => array.get(parse_index(token)?).ok()?,
+            _ => return None
+        };
+    }
+
+    Some(current)
+}
+
+fn resolve_mut<'r, 'a>(value: ArrayTypeMut<'r, 'a>, tokens: &[String]) -> Option<ArrayTypeMut<'r, 'a>> {
+    let mut current = value;
+
+    for token in tokens {
+        current = match current {
+            ArrayTypeMut::Object(object) => object.get_mut(token)?,
+            ArrayTypeMut::Array(array) => array.get_mut(parse_index(token)?).ok()?,
+            _ => return None
+        };
+    }
+
+    Some(current)
+}
+
+fn insert<'a>(root: ArrayTypeMut<'_, 'a>, tokens: &[String],
+              value: ArrayType<'a>) -> Result<Option<ArrayType<'a>>, PointerError> {
+    let (last, parent_tokens) = tokens.split_last().expect("Pointer is not empty");
+
+    match resolve_mut(root, parent_tokens) {
+        Some(ArrayTypeMut::Object(object)) => Ok(object.insert(last.clone(), value)),
+        Some(ArrayTypeMut::Array(array)) => {
+            let index = if last == "-" {
+                array.len()
+            } else {
+                parse_index(last).ok_or_else(|| PointerError::InvalidIndex(last.clone()))?
+            };
+
+            match array.insert(index, value) {
+                Ok(()) => Ok(None),
+                Err(_) => Err(PointerError::InvalidIndex(last.clone()))
+            }
+        }
+        _ => Err(PointerError::NotFound(pointer_from_tokens(parent_tokens)))
+    }
+}
+
+fn remove<'a>(root: ArrayTypeMut<'_, 'a>, tokens: &[String]) -> Result<ArrayType<'a>, PointerError> {
+    let (last, parent_tokens) = tokens.split_last().expect("Pointer is not empty");
+
+    let removed = match resolve_mut(root, parent_tokens) {
+        Some(ArrayTypeMut::Object(object)) => object.delete(last),
+        Some(ArrayTypeMut::Array(array)) => {
+            parse_index(last).and_then(|index| array.remove(index).ok())
+        }
+        _ => None
+    };
+
+    removed.ok_or_else(|| PointerError::NotFound(pointer_from_tokens(tokens)))
+}
+
+pub (crate) fn pointer_from_tokens(tokens: &[String]) -> String {
+    tokens.iter().map(|token| format!("/{}", escape(token))).collect()
+}
+
+#[derive(Debug)]
+pub enum PointerError {
+    Invalid(String),
+    NotFound(String),
+    InvalidIndex(String),
+    Root
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PointerError::Invalid(pointer) => {
+                write!(formatter, "PointerError: invalid pointer \"{}\"", pointer)
+            }
+            PointerError::NotFound(pointer) => {
+                write!(formatter, "PointerError: nothing found at \"{}\"", pointer)
+            }
+            PointerError::InvalidIndex(index) => {
+                write!(formatter, "PointerError: invalid array index \"{}\"", index)
+            }
+            PointerError::Root => {
+                write!(formatter, "PointerError: the root can't be inserted or removed")
+            }
+        }
+    }
+}
+
+impl Error for PointerError {}
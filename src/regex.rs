@@ -0,0 +1,514 @@
+// A small regular expression engine for the ECMA-262 subset that JSONPath and
+// JSON Schema patterns use in practice: literals, ".", classes with ranges,
+// \d \w \s and their negations, anchors, word boundaries, groups, alternation and
+// greedy or lazy quantifiers. Lookarounds and backreferences aren't supported.
+//
+// Patterns are compiled into a program for a Pike VM, which follows every way the
+// pattern can go at once instead of backtracking. Matching is linear in the length
+// of the text, whatever the pattern, and doesn't recurse.
+
+#[derive(Debug, Clone)]
+pub (crate) struct Regex {
+    program: Vec<Instruction>
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class { items: Vec<ClassItem>, negated: bool },
+    Start,
+    End,
+    WordBoundary { negated: bool },
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize>, greedy: bool }
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit { negated: bool },
+    Word { negated: bool },
+    Space { negated: bool }
+}
+
+impl Regex {
+    pub (crate) fn new(pattern: &str) -> Result<Regex, String> {
+        let pattern_chars: Vec<char> = pattern.chars().collect();
+        let mut current = 0;
+
+        let node = parse_alternation(&pattern_chars, &mut current)?;
+
+        if current < pattern_chars.len() {
+            return Err(format!("Unmatched ')' at {}", current));
+        }
+
+        let mut program = Vec::new();
+        compile(&node, &mut program)?;
+        program.push(Instruction::Match);
+
+        Ok(Regex { program })
+    }
+
+    pub (crate) fn is_match(&self, text: &str) -> bool {
+        let text_chars: Vec<char> = text.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+
+        for position in 0..=text_chars.len() {
+            // Patterns aren't anchored, so a match may start anywhere in the text
+            current.add(&self.program, 0, &text_chars, position);
+
+            if current.list.iter().any(|&pc| matches!(self.program[pc], Instruction::Match)) {
+                return true;
+            }
+
+            let character = match text_chars.get(position) {
+                Some(&character) => character,
+                None => break
+            };
+
+            for &pc in &current.list {
+                if consumes(&self.program[pc], character) {
+                    next.add(&self.program, pc + 1, &text_chars, position + 1);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        false
+    }
+}
+
+// Parsing
+
+fn parse_alternation(pattern_chars: &[char], current: &mut usize) -> Result<Node, String> {
+    let mut branches = vec![parse_concat(pattern_chars, current)?];
+
+    while pattern_chars.get(*current) == Some(&'|') {
+        *current += 1;
+        branches.push(parse_concat(pattern_chars, current)?);
+    }
+
+    if branches.len() == 1 {
+        Ok(branches.remove(0))
+    } else {
+        Ok(Node::Alternation(branches))
+    }
+}
+
+fn parse_concat(pattern_chars: &[char], current: &mut usize) -> Result<Node, String> {
+    let mut nodes = Vec::new();
+
+    while let Some(&character) = pattern_chars.get(*current) {
+        if character == '|' || character == ')' {
+            break;
+        }
+
+        let atom = parse_atom(pattern_chars, current)?;
+        nodes.push(parse_quantifier(atom, pattern_chars, current)?);
+    }
+
+    Ok(Node::Concat(nodes))
+}
+
+fn parse_atom(pattern_chars: &[char], current: &mut usize) -> Result<Node, String> {
+    let character = pattern_chars[*current];
+    *current += 1;
+
+    match character {
+        '.' => Ok(Node::Any),
+        '^' => Ok(Node::Start),
+        '$' => Ok(Node::End),
+        '[' => parse_class(pattern_chars, current),
+        '(' => {
+            if pattern_chars.get(*current) == Some(&'?') {
+                if pattern_chars.get(*current + 1) != Some(&':') {
+                    return Err(format!("Unsupported group at {}", *current - 1));
+                }
+                *current += 2;
+            }
+
+            let node = parse_alternation(pattern_chars, current)?;
+
+            if pattern_chars.get(*current) != Some(&')') {
+                return Err(format!("Unclosed group at {}", *current));
+            }
+            *current += 1;
+
+            Ok(node)
+        }
+        '\\' => match pattern_chars.get(*current) {
+            Some('b') => {
+                *current += 1;
+                Ok(Node::WordBoundary { negated: false })
+            }
+            Some('B') => {
+                *current += 1;
+                Ok(Node::WordBoundary { negated: true })
+            }
+            _ => match parse_escape(pattern_chars, current)? {
+                ClassItem::Range(character, _) => Ok(Node::Char(character)),
+                item => Ok(Node::Class { items: vec![item], negated: false })
+            }
+        },
+        '*' | '+' | '?' => Err(format!("Nothing to repeat at {}", *current - 1)),
+        character => Ok(Node::Char(character))
+    }
+}
+
+fn parse_quantifier(atom: Node, pattern_chars: &[char], current: &mut usize) -> Result<Node, String> {
+    let (min, max) = match pattern_chars.get(*current) {
+        Some('*') => (0, None),
+        Some('+') => (1, None),
+        Some('?') => (0, Some(1)),
+        Some('{') => match parse_bounds(pattern_chars, *current + 1) {
+            Some((min, max, end)) => {
+                *current = end - 1;
+                (min, max)
+            }
+            None => return Ok(atom)  // A brace that doesn't start a quantifier is a literal
+        },
+        _ => return Ok(atom)
+    };
+    *current += 1;
+
+    if let Some(max) = max {
+        if max < min {
+            return Err(format!("Invalid quantifier bounds at {}", *current));
+        }
+    }
+
+    let greedy = pattern_chars.get(*current) != Some(&'?');
+    if !greedy {
+        *current += 1;
+    }
+
+    Ok(Node::Repeat { node: Box::new(atom), min, max, greedy })
+}
+
+// Reads "n}", "n,}" or "n,m}", returning the bounds and the index after the brace
+fn parse_bounds(pattern_chars: &[char], start: usize) -> Option<(usize, Option<usize>, usize)> {
+    let read_number = |index: &mut usize| {
+        let mut digits = String::new();
+        while let Some(character) = pattern_chars.get(*index).filter(|character| character.is_ascii_digit()) {
+            digits.push(*character);
+            *index += 1;
+        }
+        digits.parse::<usize>().ok()
+    };
+
+    let mut index = start;
+    let min = read_number(&mut index)?;
+
+    let max = if pattern_chars.get(index) == Some(&',') {
+        index += 1;
+        read_number(&mut index)
+    } else {
+        Some(min)
+    };
+
+    if pattern_chars.get(index) != Some(&'}') {
+        return None;
+    }
+
+    Some((min, max, index + 1))
+}
+
+fn parse_class(pattern_chars: &[char], current: &mut usize) -> Result<Node, String> {
+    let mut items = Vec::new();
+
+    let negated = pattern_chars.get(*current) == Some(&'^');
+    if negated {
+        *current += 1;
+    }
+
+    loop {
+        let first = match pattern_chars.get(*current) {
+            Some(']') => {
+                *current += 1;
+                break;
+            }
+            Some('\\') => {
+                *current += 1;
+                parse_escape(pattern_chars, current)?
+            }
+            Some(&character) => {
+                *current += 1;
+                ClassItem::Range(character, character)
+            }
+            None => return Err("Unclosed character class".to_string())
+        };
+
+        // A dash between two single characters makes a range
+        let is_range = pattern_chars.get(*current) == Some(&'-') &&
+                       pattern_chars.get(*current + 1).is_some_and(|character| *character != ']');
+
+        match first {
+            ClassItem::Range(start, _) if is_range => {
+                *current += 1;
+                let end = match pattern_chars[*current] {
+                    '\\' => {
+                        *current += 1;
+                        match parse_escape(pattern_chars, current)? {
+                            ClassItem::Range(end, _) => end,
+                            _ => return Err(format!("Invalid range at {}", *current))
+                        }
+                    }
+                    character => {
+                        *current += 1;
+                        character
+                    }
+                };
+
+                if end < start {
+                    return Err(format!("Invalid range at {}", *current));
+                }
+                items.push(ClassItem::Range(start, end));
+            }
+            item => items.push(item)
+        }
+    }
+
+    Ok(Node::Class { items, negated })
+}
+
+// Parses what comes after a backslash; single characters are returned as one character ranges
+fn parse_escape(pattern_chars: &[char], current: &mut usize) -> Result<ClassItem, String> {
+    let character = match pattern_chars.get(*current) {
+        Some(&character) => character,
+        None => return Err("Trailing backslash".to_string())
+    };
+    *current += 1;
+
+    let literal = match character {
+        'd' => return Ok(ClassItem::Digit { negated: false }),
+        'D' => return Ok(ClassItem::Digit { negated: true }),
+        'w' => return Ok(ClassItem::Word { negated: false }),
+        'W' => return Ok(ClassItem::Word { negated: true }),
+        's' => return Ok(ClassItem::Space { negated: false }),
+        'S' => return Ok(ClassItem::Space { negated: true }),
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'f' => '\u{c}',
+        'v' => '\u{b}',
+        '0' => '\0',
+        'x' | 'u' => {
+            let length = if character == 'x' { 2 } else { 4 };
+            let digits: String = pattern_chars.iter().skip(*current).take(length).collect();
+
+            let code = match u32::from_str_radix(&digits, 16) {
+                Ok(code) if digits.len() == length => code,
+                _ => return Err(format!("Invalid escape at {}", *current))
+            };
+            *current += length;
+
+            char::from_u32(code).ok_or_else(|| format!("Invalid escape at {}", *current))?
+        }
+        character if character.is_ascii_alphanumeric() => {
+            return Err(format!("Unsupported escape \\{}", character));
+        }
+        character => character
+    };
+
+    Ok(ClassItem::Range(literal, literal))
+}
+
+// Compiling. Repetitions are unrolled, so the program grows with their bounds,
+// which are limited to keep patterns like "(a{1000}){1000}" from using up memory.
+
+const MAX_PROGRAM_LENGTH: usize = 100_000;
+
+#[derive(Debug, Clone)]
+enum Instruction {
+    Char(char),
+    Any,
+    Class { items: Vec<ClassItem>, negated: bool },
+    Start,
+    End,
+    WordBoundary { negated: bool },
+    Split(usize, usize),  // Goes both ways, the first one is preferred
+    Jump(usize),
+    Match
+}
+
+fn compile(node: &Node, program: &mut Vec<Instruction>) -> Result<(), String> {
+    if program.len() > MAX_PROGRAM_LENGTH {
+        return Err("Pattern too large".to_string());
+    }
+
+    match node {
+        Node::Char(character) => program.push(Instruction::Char(*character)),
+        Node::Any => program.push(Instruction::Any),
+        Node::Class { items, negated } => {
+            program.push(Instruction::Class { items: items.clone(), negated: *negated });
+        }
+        Node::Start => program.push(Instruction::Start),
+        Node::End => program.push(Instruction::End),
+        Node::WordBoundary { negated } => program.push(Instruction::WordBoundary { negated: *negated }),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        }
+        Node::Alternation(branches) => {
+            let mut jumps = Vec::new();
+
+            for (i, branch) in branches.iter().enumerate() {
+                if i + 1 == branches.len() {
+                    compile(branch, program)?;
+                    break;
+                }
+
+                let split = program.len();
+                program.push(Instruction::Match);  // Patched once the branch is compiled
+                compile(branch, program)?;
+                jumps.push(program.len());
+                program.push(Instruction::Match);
+                program[split] = Instruction::Split(split + 1, program.len());
+            }
+
+            for jump in jumps {
+                program[jump] = Instruction::Jump(program.len());
+            }
+        }
+        Node::Repeat { node, min, max, greedy } => {
+            if (*min).max(max.unwrap_or(0)) > MAX_PROGRAM_LENGTH {
+                return Err("Pattern too large".to_string());
+            }
+
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+
+            let choice = |repeat: usize, skip: usize| {
+                if *greedy {
+                    Instruction::Split(repeat, skip)
+                } else {
+                    Instruction::Split(skip, repeat)
+                }
+            };
+
+            match max {
+                Some(max) => {
+                    let mut splits = Vec::new();
+
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Instruction::Match);
+                        compile(node, program)?;
+                    }
+
+                    for split in splits {
+                        program[split] = choice(split + 1, program.len());
+                    }
+                }
+                None => {
+                    let split = program.len();
+                    program.push(Instruction::Match);
+                    compile(node, program)?;
+                    program.push(Instruction::Jump(split));
+                    program[split] = choice(split + 1, program.len());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Matching. The threads at a position are the instructions that consume the next
+// character, or Match. Each instruction is added once per position, which keeps
+// the work per character bounded and stops loops that don't consume anything.
+
+struct Threads {
+    list: Vec<usize>,
+    added: Vec<bool>,
+    stack: Vec<usize>
+}
+
+impl Threads {
+    fn new(length: usize) -> Self {
+        Threads {
+            list: Vec::new(),
+            added: vec![false; length],
+            stack: Vec::new()
+        }
+    }
+
+    // Follows the jumps, splits and assertions from an instruction
+    fn add(&mut self, program: &[Instruction], pc: usize, text: &[char], position: usize) {
+        self.stack.push(pc);
+
+        while let Some(pc) = self.stack.pop() {
+            if self.added[pc] {
+                continue;
+            }
+            self.added[pc] = true;
+
+            match &program[pc] {
+                Instruction::Jump(target) => self.stack.push(*target),
+                Instruction::Split(first, second) => {
+                    self.stack.push(*second);
+                    self.stack.push(*first);
+                }
+                Instruction::Start => {
+                    if position == 0 {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Instruction::End => {
+                    if position == text.len() {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Instruction::WordBoundary { negated } => {
+                    let before = position > 0 && is_word(text[position - 1]);
+                    let after = text.get(position).is_some_and(|character| is_word(*character));
+
+                    if (before != after) != *negated {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                _ => self.list.push(pc)
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.added.iter_mut().for_each(|added| *added = false);
+    }
+}
+
+fn consumes(instruction: &Instruction, character: char) -> bool {
+    match instruction {
+        Instruction::Char(expected) => *expected == character,
+        Instruction::Any => !is_line_terminator(character),
+        Instruction::Class { items, negated } => {
+            items.iter().any(|item| class_item_matches(item, character)) != *negated
+        }
+        _ => false
+    }
+}
+
+fn class_item_matches(item: &ClassItem, character: char) -> bool {
+    match item {
+        ClassItem::Range(start, end) => *start <= character && character <= *end,
+        ClassItem::Digit { negated } => character.is_ascii_digit() != *negated,
+        ClassItem::Word { negated } => is_word(character) != *negated,
+        ClassItem::Space { negated } => character.is_whitespace() != *negated
+    }
+}
+
+fn is_word(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_'
+}
+
+fn is_line_terminator(character: char) -> bool {
+    matches!(character, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
@@ -0,0 +1,532 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structure::{JsonObject, ArrayType, ArrayTypeRef};
+use crate::traits::ToJson;
+use crate::pointer::escape;
+use crate::regex::Regex;
+
+// JSON Schema (draft 2020-12) validation. Supported keywords are type, enum, const,
+// the numeric, string, array and object constraints, pattern and patternProperties,
+// allOf, anyOf, oneOf, not and $ref to locations inside the same schema, like
+// "#/$defs/name". Unknown keywords are ignored, as the specification requires.
+
+type Comparison = fn(f64, f64) -> bool;
+
+// $ref chains deeper than this at the same place in the instance are considered a loop
+const MAX_REFERENCE_DEPTH: usize = 64;
+
+// Keywords whose values are subschemas, on their own, in arrays or as object members
+const SUBSCHEMAS: [&str; 3] = ["items", "additionalProperties", "not"];
+const SUBSCHEMA_ARRAYS: [&str; 4] = ["prefixItems", "allOf", "anyOf", "oneOf"];
+const SUBSCHEMA_OBJECTS: [&str; 3] = ["properties", "patternProperties", "$defs"];
+
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    schema: ArrayType<'static>,
+    patterns: HashMap<String, Regex>  // Compiled in advance, as they are used for every instance
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    instance_path: String,
+    schema_path: String,
+    message: String
+}
+
+impl JsonSchema {
+    pub fn new(schema: &JsonObject) -> Result<JsonSchema, SchemaError> {
+        let schema = schema.to_json();
+        let mut patterns = HashMap::new();
+        compile_patterns(&schema, schema.to_ref(), "", &mut patterns, &mut HashSet::new())?;
+
+        Ok(JsonSchema { schema, patterns })
+    }
+
+    pub fn validate(&self, instance: &JsonObject) -> Result<(), Vec<ValidationError>> {
+        self.validate_value(ArrayTypeRef::Object(instance))
+    }
+
+    pub fn validate_value(&self, instance: ArrayTypeRef) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.validate_schema(self.schema.to_ref(), instance, "", "", 0, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &JsonObject) -> bool {
+        self.validate(instance).is_ok()
+    }
+
+    fn validate_schema(&self, schema: ArrayTypeRef, instance: ArrayTypeRef, instance_path: &str,
+                       schema_path: &str, depth: usize, errors: &mut Vec<ValidationError>) {
+        let schema = match schema {
+            ArrayTypeRef::Bool(true) => return,
+            ArrayTypeRef::Bool(false) => {
+                errors.push(error(instance_path, schema_path, "No value is allowed here".to_string()));
+                return;
+            }
+            ArrayTypeRef::Object(schema) => schema,
+            _ => return
+        };
+
+        if let Some(reference) = schema.get("$ref") {
+            self.validate_reference(reference, instance, instance_path, schema_path, depth, errors);
+        }
+
+        self.validate_type(schema, instance, instance_path, schema_path, errors);
+        self.validate_values(schema, instance, instance_path, schema_path, errors);
+        self.validate_number(schema, instance, instance_path, schema_path, errors);
+        self.validate_string(schema, instance, instance_path, schema_path, errors);
+        self.validate_array(schema, instance, instance_path, schema_path, errors);
+        self.validate_object(schema, instance, instance_path, schema_path, errors);
+        self.validate_combinators(schema, instance, instance_path, schema_path, depth, errors);
+    }
+
+    fn validate_reference(&self, reference: ArrayTypeRef, instance: ArrayTypeRef, instance_path: &str,
+                          schema_path: &str, depth: usize, errors: &mut Vec<ValidationError>) {
+        let reference_path = format!("{}/$ref", schema_path);
+
+        let target = match reference {
+            ArrayTypeRef::String(reference) => match reference.strip_prefix('#') {
+                Some(pointer) => self.schema.pointer(pointer),
+                None => None
+            },
+            _ => None
+        };
+
+        match target {
+            Some(_) if depth >= MAX_REFERENCE_DEPTH => {
+                errors.push(error(instance_path, &reference_path, "Too many nested references".to_string()));
+            }
+            Some(target) => {
+                self.validate_schema(target, instance, instance_path, &reference_path, depth + 1, errors);
+            }
+            None => {
+                errors.push(error(instance_path, &reference_path, "Unresolvable reference".to_string()));
+            }
+        }
+    }
+
+    fn validate_type(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                     schema_path: &str, errors: &mut Vec<ValidationError>) {
+        let types: Vec<&String> = match schema.get("type") {
+            Some(ArrayTypeRef::String(name)) => vec![name],
+            Some(ArrayTypeRef::Array(names)) => names.iter().filter_map(|name| match name {
+                ArrayTypeRef::String(name) => Some(name),
+                _ => None
+            }).collect(),
+            _ => return
+        };
+
+        if !types.iter().any(|name| has_type(instance, name)) {
+            let expected: Vec<&str> = types.iter().map(|name| name.as_str()).collect();
+            errors.push(error(instance_path, &format!("{}/type", schema_path),
+                              format!("Expected {}, found {}", expected.join(" or "), type_name(instance))));
+        }
+    }
+
+    fn validate_values(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                       schema_path: &str, errors: &mut Vec<ValidationError>) {
+        if let Some(ArrayTypeRef::Array(values)) = schema.get("enum") {
+            if !values.iter().any(|value| value.eq_numeric(&instance)) {
+                errors.push(error(instance_path, &format!("{}/enum", schema_path),
+                                  "Value is not one of the allowed values".to_string()));
+            }
+        }
+
+        if let Some(value) = schema.get("const") {
+            if !value.eq_numeric(&instance) {
+                errors.push(error(instance_path, &format!("{}/const", schema_path),
+                                  "Value is not the constant value".to_string()));
+            }
+        }
+    }
+
+    fn validate_number(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                       schema_path: &str, errors: &mut Vec<ValidationError>) {
+        let value = match number(instance) {
+            Some(value) => value,
+            None => return
+        };
+
+        let checks: [(&str, Comparison, &str); 4] = [
+            ("minimum", |value, limit| value >= limit, "greater than or equal to"),
+            ("maximum", |value, limit| value <= limit, "less than or equal to"),
+            ("exclusiveMinimum", |value, limit| value > limit, "greater than"),
+            ("exclusiveMaximum", |value, limit| value < limit, "less than")
+        ];
+
+        for (keyword, check, description) in checks.iter() {
+            if let Some(limit) = schema.get(keyword).and_then(number) {
+                if !check(value, limit) {
+                    errors.push(error(instance_path, &format!("{}/{}", schema_path, keyword),
+                                      format!("{} is not {} {}", value, description, limit)));
+                }
+            }
+        }
+
+        if let Some(divisor) = schema.get("multipleOf").and_then(number) {
+            let quotient = value / divisor;
+
+            if divisor > 0.0 && (quotient - quotient.round()).abs() > 1e-9 {
+                errors.push(error(instance_path, &format!("{}/multipleOf", schema_path),
+                                  format!("{} is not a multiple of {}", value, divisor)));
+            }
+        }
+    }
+
+    fn validate_string(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                       schema_path: &str, errors: &mut Vec<ValidationError>) {
+        let string = match instance {
+            ArrayTypeRef::String(string) => string,
+            _ => return
+        };
+        let length = string.chars().count();
+
+        if let Some(min) = schema.get("minLength").and_then(count) {
+            if length < min {
+                errors.push(error(instance_path, &format!("{}/minLength", schema_path),
+                                  format!("String is shorter than {} characters", min)));
+            }
+        }
+
+        if let Some(max) = schema.get("maxLength").and_then(count) {
+            if length > max {
+                errors.push(error(instance_path, &format!("{}/maxLength", schema_path),
+                                  format!("String is longer than {} characters", max)));
+            }
+        }
+
+        if let Some(ArrayTypeRef::String(pattern)) = schema.get("pattern") {
+            if !self.patterns[pattern].is_match(string) {
+                errors.push(error(instance_path, &format!("{}/pattern", schema_path),
+                                  format!("String doesn't match the pattern \"{}\"", pattern)));
+            }
+        }
+    }
+
+    // Items and members start counting references anew, as a loop has to come back to the same value
+    fn validate_array(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                      schema_path: &str, errors: &mut Vec<ValidationError>) {
+        let array = match instance {
+            ArrayTypeRef::Array(array) => array,
+            _ => return
+        };
+        let items: Vec<ArrayTypeRef> = array.iter().collect();
+
+        let mut prefix_length = 0;
+        if let Some(ArrayTypeRef::Array(prefix)) = schema.get("prefixItems") {
+            for (i, (item_schema, item)) in prefix.iter().zip(items.iter()).enumerate() {
+                self.validate_schema(item_schema, *item, &format!("{}/{}", instance_path, i),
+                                     &format!("{}/prefixItems/{}", schema_path, i), 0, errors);
+            }
+            prefix_length = prefix.len();
+        }
+
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate().skip(prefix_length) {
+                self.validate_schema(item_schema, *item, &format!("{}/{}", instance_path, i),
+                                     &format!("{}/items", schema_path), 0, errors);
+            }
+        }
+
+        if let Some(min) = schema.get("minItems").and_then(count) {
+            if items.len() < min {
+                errors.push(error(instance_path, &format!("{}/minItems", schema_path),
+                                  format!("Array has fewer than {} items", min)));
+            }
+        }
+
+        if let Some(max) = schema.get("maxItems").and_then(count) {
+            if items.len() > max {
+                errors.push(error(instance_path, &format!("{}/maxItems", schema_path),
+                                  format!("Array has more than {} items", max)));
+            }
+        }
+
+        if let Some(ArrayTypeRef::Bool(true)) = schema.get("uniqueItems") {
+            let duplicate = (0..items.len()).any(|i| (i + 1..items.len()).any(|j| items[i].eq_numeric(&items[j])));
+
+            if duplicate {
+                errors.push(error(instance_path, &format!("{}/uniqueItems", schema_path),
+                                  "Array items are not unique".to_string()));
+            }
+        }
+    }
+
+    fn validate_object(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                       schema_path: &str, errors: &mut Vec<ValidationError>) {
+        let object = match instance {
+            ArrayTypeRef::Object(object) => object,
+            _ => return
+        };
+
+        let mut members: Vec<_> = object.iter().collect();
+        members.sort_by_key(|(key, _)| *key);
+
+        let properties = match schema.get("properties") {
+            Some(ArrayTypeRef::Object(properties)) => Some(properties),
+            _ => None
+        };
+        let pattern_properties = match schema.get("patternProperties") {
+            Some(ArrayTypeRef::Object(pattern_properties)) => Some(pattern_properties),
+            _ => None
+        };
+        let additional = schema.get("additionalProperties");
+
+        for (key, value) in members {
+            let member_path = format!("{}/{}", instance_path, escape(key));
+            let mut matched = false;
+
+            if let Some(property) = properties.and_then(|properties| properties.get(key)) {
+                self.validate_schema(property, value, &member_path,
+                                     &format!("{}/properties/{}", schema_path, escape(key)), 0, errors);
+                matched = true;
+            }
+
+            if let Some(pattern_properties) = pattern_properties {
+                let mut patterns: Vec<_> = pattern_properties.iter().collect();
+                patterns.sort_by_key(|(pattern, _)| *pattern);
+
+                for (pattern, property) in patterns {
+                    if self.patterns[pattern].is_match(key) {
+                        self.validate_schema(property, value, &member_path,
+                                             &format!("{}/patternProperties/{}", schema_path, escape(pattern)),
+                                             0, errors);
+                        matched = true;
+                    }
+                }
+            }
+
+            if let (false, Some(additional)) = (matched, additional) {
+                self.validate_schema(additional, value, &member_path,
+                                     &format!("{}/additionalProperties", schema_path), 0, errors);
+            }
+        }
+
+        if let Some(ArrayTypeRef::Array(required)) = schema.get("required") {
+            for name in required.iter() {
+                if let ArrayTypeRef::String(name) = name {
+                    if !object.contains_key(name) {
+                        errors.push(error(instance_path, &format!("{}/required", schema_path),
+                                          format!("Missing required property \"{}\"", name)));
+                    }
+                }
+            }
+        }
+
+        if let Some(min) = schema.get("minProperties").and_then(count) {
+            if object.len() < min {
+                errors.push(error(instance_path, &format!("{}/minProperties", schema_path),
+                                  format!("Object has fewer than {} properties", min)));
+            }
+        }
+
+        if let Some(max) = schema.get("maxProperties").and_then(count) {
+            if object.len() > max {
+                errors.push(error(instance_path, &format!("{}/maxProperties", schema_path),
+                                  format!("Object has more than {} properties", max)));
+            }
+        }
+    }
+
+    fn validate_combinators(&self, schema: &JsonObject, instance: ArrayTypeRef, instance_path: &str,
+                            schema_path: &str, depth: usize, errors: &mut Vec<ValidationError>) {
+        if let Some(ArrayTypeRef::Array(schemas)) = schema.get("allOf") {
+            for (i, subschema) in schemas.iter().enumerate() {
+                self.validate_schema(subschema, instance, instance_path,
+                                     &format!("{}/allOf/{}", schema_path, i), depth, errors);
+            }
+        }
+
+        if let Some(ArrayTypeRef::Array(schemas)) = schema.get("anyOf") {
+            let path = format!("{}/anyOf", schema_path);
+
+            if self.count_matching(schemas.iter(), instance, &path, depth) == 0 {
+                errors.push(error(instance_path, &path, "Value doesn't match any of the schemas".to_string()));
+            }
+        }
+
+        if let Some(ArrayTypeRef::Array(schemas)) = schema.get("oneOf") {
+            let path = format!("{}/oneOf", schema_path);
+            let matching = self.count_matching(schemas.iter(), instance, &path, depth);
+
+            if matching != 1 {
+                errors.push(error(instance_path, &path,
+                                  format!("Value matches {} of the schemas instead of exactly one", matching)));
+            }
+        }
+
+        if let Some(subschema) = schema.get("not") {
+            let path = format!("{}/not", schema_path);
+
+            if self.count_matching(std::iter::once(subschema), instance, &path, depth) == 1 {
+                errors.push(error(instance_path, &path, "Value matches the schema in not".to_string()));
+            }
+        }
+    }
+
+    fn count_matching<'r, 'a: 'r>(&self, schemas: impl Iterator<Item = ArrayTypeRef<'r, 'a>>,
+                                  instance: ArrayTypeRef, path: &str, depth: usize) -> usize {
+        schemas.enumerate().filter(|(i, subschema)| {
+            let mut errors = Vec::new();
+            self.validate_schema(*subschema, instance, "", &format!("{}/{}", path, i), depth, &mut errors);
+            errors.is_empty()
+        }).count()
+    }
+}
+
+impl ValidationError {
+    // JSON Pointer to the invalid value in the instance
+    pub fn instance_path(&self) -> &str {
+        &self.instance_path
+    }
+
+    // JSON Pointer to the keyword in the schema that failed
+    pub fn schema_path(&self) -> &str {
+        &self.schema_path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+fn error(instance_path: &str, schema_path: &str, message: String) -> ValidationError {
+    ValidationError {
+        instance_path: instance_path.to_string(),
+        schema_path: schema_path.to_string(),
+        message
+    }
+}
+
+// Only keywords that hold subschemas are searched, so that values like those of const or enum
+// are left alone. Referenced locations are searched too, as they can be anywhere in the schema.
+fn compile_patterns(root: &ArrayType, schema: ArrayTypeRef, schema_path: &str, patterns: &mut HashMap<String, Regex>,
+                    references: &mut HashSet<String>) -> Result<(), SchemaError> {
+    let object = match schema {
+        ArrayTypeRef::Object(object) => object,
+        _ => return Ok(())
+    };
+
+    for (key, value) in object.iter() {
+        let path = format!("{}/{}", schema_path, escape(key));
+
+        match (key, value) {
+            ("pattern", ArrayTypeRef::String(pattern)) => compile_pattern(pattern, &path, patterns)?,
+            ("$ref", ArrayTypeRef::String(reference)) => {
+                let pointer = match reference.strip_prefix('#') {
+                    Some(pointer) if references.insert(pointer.to_string()) => pointer,
+                    _ => continue
+                };
+
+                if let Some(target) = root.pointer(pointer) {
+                    compile_patterns(root, target, pointer, patterns, references)?;
+                }
+            }
+            (key, value) if SUBSCHEMAS.contains(&key) => compile_patterns(root, value, &path, patterns, references)?,
+            (key, ArrayTypeRef::Array(array)) if SUBSCHEMA_ARRAYS.contains(&key) => {
+                for (i, item) in array.iter().enumerate() {
+                    compile_patterns(root, item, &format!("{}/{}", path, i), patterns, references)?;
+                }
+            }
+            (key, ArrayTypeRef::Object(members)) if SUBSCHEMA_OBJECTS.contains(&key) => {
+                for (name, member) in members.iter() {
+                    let member_path = format!("{}/{}", path, escape(name));
+
+                    if key == "patternProperties" {
+                        compile_pattern(name, &member_path, patterns)?;
+                    }
+                    compile_patterns(root, member, &member_path, patterns, references)?;
+                }
+            }
+            _ => ()
+        }
+    }
+
+    Ok(())
+}
+
+fn compile_pattern(pattern: &str, path: &str,
+                   patterns: &mut HashMap<String, Regex>) -> Result<(), SchemaError> {
+    if !patterns.contains_key(pattern) {
+        let regex = Regex::new(pattern).map_err(|message| SchemaError { path: path.to_string(), message })?;
+        patterns.insert(pattern.to_string(), regex);
+    }
+
+    Ok(())
+}
+
+fn has_type(instance: ArrayTypeRef, name: &str) -> bool {
+    match (name, instance) {
+        ("null", ArrayTypeRef::Null(_)) |
+        ("boolean", ArrayTypeRef::Bool(_)) |
+        ("string", ArrayTypeRef::String(_)) |
+        ("array", ArrayTypeRef::Array(_)) |
+        ("object", ArrayTypeRef::Object(_)) |
+        ("number", ArrayTypeRef::Int(_)) |
+        ("number", ArrayTypeRef::Float(_)) |
+        ("integer", ArrayTypeRef::Int(_)) => true,
+        ("integer", ArrayTypeRef::Float(value)) => value.fract() == 0.0,
+        _ => false
+    }
+}
+
+// Type names as JSON Schema calls them
+fn type_name(instance: ArrayTypeRef) -> &'static str {
+    match instance {
+        ArrayTypeRef::Int(_) => "integer",
+        ArrayTypeRef::Float(_) => "number",
+        ArrayTypeRef::Bool(_) => "boolean",
+        ArrayTypeRef::String(_) => "string",
+        ArrayTypeRef::Array(_) => "array",
+        ArrayTypeRef::Object(_) => "object",
+        ArrayTypeRef::Null(_) => "null"
+    }
+}
+
+// Floats go through their shortest decimal form, so that 0.1 stays 0.1 as f64
+fn number(value: ArrayTypeRef) -> Option<f64> {
+    match value {
+        ArrayTypeRef::Int(value) => Some(value as f64),
+        ArrayTypeRef::Float(value) => value.to_string().parse().ok(),
+        _ => None
+    }
+}
+
+fn count(value: ArrayTypeRef) -> Option<usize> {
+    match number(value) {
+        Some(value) if value >= 0.0 && value.fract() == 0.0 => Some(value as usize),
+        _ => None
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "ValidationError: {}\nInstance path: \"{}\", schema path: \"{}\"",
+               self.message, self.instance_path, self.schema_path)
+    }
+}
+
+impl Error for ValidationError {}
+
+// Returned when the schema itself is invalid, like for a pattern that doesn't compile
+#[derive(Debug)]
+pub struct SchemaError {
+    path: String,
+    message: String
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "SchemaError: {} at \"{}\"", self.message, self.path)
+    }
+}
+
+impl Error for SchemaError {}
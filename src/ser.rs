@@ -0,0 +1,759 @@
+use std::fmt::Write;
+
+use serde::ser::{self, Serialize, SerializeSeq, SerializeMap, Error as _};
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+use crate::dumper::{dump_string, dump_float, new_line};
+use crate::error::SerdeError;
+
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, SerdeError> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.into_inner())
+}
+
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String, SerdeError> {
+    let mut serializer = Serializer::pretty();
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.into_inner())
+}
+
+// Values that don't fit an ArrayType, like a u64 beyond the i32 range, are errors
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<ArrayType<'static>, SerdeError> {
+    value.serialize(ValueSerializer)
+}
+
+// Serialize implementations for the data structures
+
+impl<'a> Serialize for JsonObject<'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        // Sorted like dump does
+        let mut members: Vec<_> = self.iter().collect();
+        members.sort_by_key(|(key, _)| *key);
+
+        for (key, value) in members {
+            map.serialize_entry(key, &value)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'a> Serialize for JsonArray<'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+
+        for item in self.iter() {
+            seq.serialize_element(&item)?;
+        }
+
+        seq.end()
+    }
+}
+
+impl<'a> Serialize for ArrayType<'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_ref().serialize(serializer)
+    }
+}
+
+impl<'r, 'a> Serialize for ArrayTypeRef<'r, 'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ArrayTypeRef::Int(value) => serializer.serialize_i32(*value),
+            ArrayTypeRef::Float(value) => serializer.serialize_f32(*value),
+            ArrayTypeRef::Bool(value) => serializer.serialize_bool(*value),
+            ArrayTypeRef::String(value) => serializer.serialize_str(value),
+            ArrayTypeRef::Array(value) => value.serialize(serializer),
+            ArrayTypeRef::Object(value) => value.serialize(serializer),
+            ArrayTypeRef::Null(_) => serializer.serialize_unit()
+        }
+    }
+}
+
+impl Serialize for Null {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+// Writes any Rust value as JSON text. Numbers are written as they are, so an i64,
+// a u64 or an f64 keeps its exact value even though an ArrayType couldn't hold it.
+// The layout is the same as dump and dump_pretty, but struct fields keep their order.
+#[derive(Default)]
+pub struct Serializer {
+    output: String,
+    pretty: bool,
+    level: usize
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pretty() -> Self {
+        Serializer {
+            pretty: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn into_inner(self) -> String {
+        self.output
+    }
+
+    fn write_number(&mut self, value: impl std::fmt::Display) -> Result<(), SerdeError> {
+        write!(self.output, "{}", value).expect("Writing to a string");
+        Ok(())
+    }
+
+    fn begin(&mut self, bracket: char) {
+        self.output.push(bracket);
+        self.level += 1;
+    }
+
+    // Opens the object that holds the data of a variant, up to its value
+    fn begin_variant(&mut self, variant: &str) {
+        self.begin('{');
+        new_line(self.pretty, self.level, &mut self.output);
+        dump_string(variant, &mut self.output);
+        self.write_colon();
+    }
+
+    fn end(&mut self, bracket: char, empty: bool) {
+        self.level -= 1;
+        if !empty {
+            new_line(self.pretty, self.level, &mut self.output);
+        }
+        self.output.push(bracket);
+    }
+
+    fn write_colon(&mut self) {
+        self.output.push(':');
+        if self.pretty {
+            self.output.push(' ');
+        }
+    }
+}
+
+impl<'s> ser::Serializer for &'s mut Serializer {
+    type Ok = ();
+    type Error = SerdeError;
+
+    type SerializeSeq = Compound<'s>;
+    type SerializeTuple = Compound<'s>;
+    type SerializeTupleStruct = Compound<'s>;
+    type SerializeTupleVariant = Compound<'s>;
+    type SerializeMap = Compound<'s>;
+    type SerializeStruct = Compound<'s>;
+    type SerializeStructVariant = Compound<'s>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), SerdeError> {
+        self.output.push_str(if value { "true" } else { "false" });
+        Ok(())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), SerdeError> {
+        self.write_number(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), SerdeError> {
+        dump_float(value, &mut self.output);
+        Ok(())
+    }
+
+    // Like dump_float, at double precision
+    fn serialize_f64(self, value: f64) -> Result<(), SerdeError> {
+        if !value.is_finite() {
+            self.output.push_str("null");
+            return Ok(());
+        }
+
+        let number = format!("{:?}", value);
+        self.output.push_str(&number);
+
+        if !number.contains(['.', 'e', 'E']) {
+            self.output.push_str(".0");
+        }
+
+        Ok(())
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), SerdeError> {
+        dump_string(&value.to_string(), &mut self.output);
+        Ok(())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), SerdeError> {
+        dump_string(value, &mut self.output);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), SerdeError> {
+        let mut seq = self.serialize_seq(Some(value.len()))?;
+
+        for byte in value {
+            seq.serialize_element(byte)?;
+        }
+
+        SerializeSeq::end(seq)
+    }
+
+    fn serialize_none(self) -> Result<(), SerdeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerdeError> {
+        self.output.push_str("null");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerdeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32,
+                              variant: &'static str) -> Result<(), SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str,
+                                                       value: &T) -> Result<(), SerdeError> {
+        value.serialize(self)
+    }
+
+    // Variants with data are written as an object with a single member
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str,
+                                                        _variant_index: u32, variant: &'static str,
+                                                        value: &T) -> Result<(), SerdeError> {
+        self.begin_variant(variant);
+        value.serialize(&mut *self)?;
+        self.end('}', false);
+
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        self.begin('[');
+        Ok(Compound { serializer: self, empty: true, variant: false })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str,
+                              len: usize) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32,
+                               variant: &'static str,
+                               _len: usize) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        self.begin_variant(variant);
+        self.begin('[');
+        Ok(Compound { serializer: self, empty: true, variant: true })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        self.begin('{');
+        Ok(Compound { serializer: self, empty: true, variant: false })
+    }
+
+    fn serialize_struct(self, _name: &'static str,
+                        len: usize) -> Result<Self::SerializeStruct, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32,
+                                variant: &'static str,
+                                _len: usize) -> Result<Self::SerializeStructVariant, SerdeError> {
+        self.begin_variant(variant);
+        self.begin('{');
+        Ok(Compound { serializer: self, empty: true, variant: true })
+    }
+}
+
+// An array or an object being written, possibly inside the object of a variant
+pub struct Compound<'s> {
+    serializer: &'s mut Serializer,
+    empty: bool,
+    variant: bool
+}
+
+impl<'s> Compound<'s> {
+    fn separator(&mut self) {
+        if !self.empty {
+            self.serializer.output.push(',');
+        }
+        self.empty = false;
+
+        new_line(self.serializer.pretty, self.serializer.level, &mut self.serializer.output);
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.separator();
+        value.serialize(&mut *self.serializer)
+    }
+
+    // Keys are written through a compact serializer of their own. Strings are kept,
+    // numbers and booleans are quoted, anything else can't be a key.
+    fn key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let mut key_serializer = Serializer::new();
+        key.serialize(&mut key_serializer)?;
+        let key = key_serializer.into_inner();
+
+        self.separator();
+
+        let output = &mut self.serializer.output;
+        if key.starts_with('"') {
+            output.push_str(&key);
+        } else if key == "true" || key == "false" || key.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+            dump_string(&key, output);
+        } else {
+            return Err(SerdeError::custom("Object keys must be strings"));
+        }
+        self.serializer.write_colon();
+
+        Ok(())
+    }
+
+    fn finish(self, bracket: char) -> Result<(), SerdeError> {
+        self.serializer.end(bracket, self.empty);
+
+        if self.variant {
+            self.serializer.end('}', false);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'s> ser::SerializeSeq for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish(']')
+    }
+}
+
+impl<'s> ser::SerializeTuple for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish(']')
+    }
+}
+
+impl<'s> ser::SerializeTupleStruct for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish(']')
+    }
+}
+
+impl<'s> ser::SerializeTupleVariant for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish(']')
+    }
+}
+
+impl<'s> ser::SerializeMap for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish('}')
+    }
+}
+
+impl<'s> ser::SerializeStruct for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str,
+                                              value: &T) -> Result<(), SerdeError> {
+        self.key(key)?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish('}')
+    }
+}
+
+impl<'s> ser::SerializeStructVariant for Compound<'s> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str,
+                                              value: &T) -> Result<(), SerdeError> {
+        self.key(key)?;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish('}')
+    }
+}
+
+// Serializes any Rust value into an ArrayType, for to_value
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Bool(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Int(value))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Float(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::from(value))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::String(value.to_string()))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Array(value.iter().copied().collect()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Null(Null))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Null(Null))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Null(Null))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32,
+                              variant: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str,
+                                                       value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    // Variants with data are written as an object with a single member
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str,
+                                                        _variant_index: u32, variant: &'static str,
+                                                        value: &T) -> Result<Self::Ok, Self::Error> {
+        let mut object = JsonObject::new();
+        object.insert(variant, value.serialize(ValueSerializer)?);
+
+        Ok(ArrayType::Object(object))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SeqSerializer { array: JsonArray::new() })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str,
+                              len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32,
+                               variant: &'static str,
+                               len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(MapSerializer { object: JsonObject::new(), next_key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str,
+                        len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32,
+                                variant: &'static str,
+                                len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(VariantSerializer { variant, inner: self.serialize_map(Some(len))? })
+    }
+}
+
+pub struct SeqSerializer {
+    array: JsonArray<'static>
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.array.add(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Array(self.array))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct MapSerializer {
+    object: JsonObject<'static>,
+    next_key: Option<String>
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let key = match key.serialize(ValueSerializer)? {
+            ArrayType::String(key) => key,
+            ArrayType::Int(key) => key.to_string(),
+            ArrayType::Bool(key) => key.to_string(),
+            _ => return Err(SerdeError::custom("Object keys must be strings"))
+        };
+        self.next_key = Some(key);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.next_key.take().expect("serialize_key is called first");
+        self.object.insert(key, value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Object(self.object))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str,
+                                              value: &T) -> Result<(), Self::Error> {
+        self.object.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(ArrayType::Object(self.object))
+    }
+}
+
+// Wraps the data of a tuple or struct variant into an object keyed by the variant name
+pub struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S
+}
+
+impl VariantSerializer<SeqSerializer> {
+    fn wrap(self) -> ArrayType<'static> {
+        let mut object = JsonObject::new();
+        object.insert(self.variant, ArrayType::Array(self.inner.array));
+
+        ArrayType::Object(object)
+    }
+}
+
+impl VariantSerializer<MapSerializer> {
+    fn wrap(self) -> ArrayType<'static> {
+        let mut object = JsonObject::new();
+        object.insert(self.variant, ArrayType::Object(self.inner.object));
+
+        ArrayType::Object(object)
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.inner.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.wrap())
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = ArrayType<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str,
+                                              value: &T) -> Result<(), Self::Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.wrap())
+    }
+}
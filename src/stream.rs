@@ -0,0 +1,446 @@
+use std::error::Error;
+use std::io::{self, BufRead, ErrorKind};
+
+use crate::tokenizer::{Position, ParseError, advance, tokenize_value};
+use crate::parser::parse_value_with;
+use crate::options::{ParseOptions, CommentMode};
+use crate::data_structure::ArrayType;
+
+// Streams of JSON values: concatenated values like {}{}[], with or without whitespace
+// between them, and RFC 7464 JSON text sequences, where every value follows a record
+// separator (U+001E). The input is read as the values are needed, and each value is
+// returned as soon as it has been read in full, so that values coming through a pipe
+// or a socket don't wait for the end of the input. Bytes that aren't valid UTF-8 are
+// only an error for the value or record they are in. An error ends a concatenated
+// stream, while a sequence goes on with the next record.
+
+const RECORD_SEPARATOR: char = '\u{1E}';
+
+pub struct JsonStream<R> {
+    reader: R,
+    options: ParseOptions,
+    sequence: bool,
+    contents_chars: Vec<char>,  // Read but not parsed yet
+    byte_offsets: Vec<usize>,  // In the whole input, of every character in contents_chars
+    invalid: Vec<usize>,  // Indices in contents_chars of the characters that stand for invalid UTF-8
+    pending: Vec<u8>,  // The start of a UTF-8 sequence that the next read completes
+    read_bytes: usize,  // Decoded so far
+    at_end: bool,  // Of the input
+    current_character: Option<char>,
+    current_position: Position,  // Its index is in contents_chars
+    byte_offset: usize,
+    done: bool
+}
+
+pub fn read_json_stream<R: BufRead>(reader: R) -> JsonStream<R> {
+    read_json_stream_with(reader, &ParseOptions::default())
+}
+
+pub fn read_json_stream_with<R: BufRead>(reader: R, options: &ParseOptions) -> JsonStream<R> {
+    JsonStream::new(reader, options, false)
+}
+
+pub fn read_json_seq<R: BufRead>(reader: R) -> JsonStream<R> {
+    read_json_seq_with(reader, &ParseOptions::default())
+}
+
+pub fn read_json_seq_with<R: BufRead>(reader: R, options: &ParseOptions) -> JsonStream<R> {
+    JsonStream::new(reader, options, true)
+}
+
+impl<R: BufRead> JsonStream<R> {
+    fn new(reader: R, options: &ParseOptions, sequence: bool) -> Self {
+        let mut options = options.clone();
+        options.scalar_root = true;
+
+        let mut current_character = None;
+        let mut current_position = Position { index: -1, line: 1, column: -1 };
+        advance(&[], &mut current_character, &mut current_position);
+
+        JsonStream {
+            reader,
+            options,
+            sequence,
+            contents_chars: Vec::new(),
+            byte_offsets: Vec::new(),
+            invalid: Vec::new(),
+            pending: Vec::new(),
+            read_bytes: 0,
+            at_end: false,
+            current_character,
+            current_position,
+            byte_offset: 0,
+            done: false
+        }
+    }
+
+    fn next_value(&mut self) -> Option<Result<ArrayType<'static>, Box<dyn Error>>> {
+        self.discard_parsed();
+
+        let start = self.current_position.index as usize;
+        let mut frame = Frame { index: start, depth: 0, state: FrameState::Between };
+        let comments = self.options.comments != CommentMode::Error;
+
+        let end = loop {
+            if let Some(end) = frame.find_end(&self.contents_chars, comments) {
+                break end;
+            }
+
+            match self.read_more() {
+                Ok(true) => (),
+                Ok(false) => break self.contents_chars.len(),  // The parser reports an unfinished value
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(Box::new(error)));
+                }
+            }
+        };
+
+        let value = match self.invalid_utf8(start, end) {
+            Some(error) => Err(error),
+            None => {
+                // The tokenizer only sees the value, which may be followed by data that isn't read yet
+                let contents = &self.contents_chars[..end];
+                self.current_character = contents.get(start).copied();
+
+                match tokenize_value(contents, &mut self.current_character, &mut self.current_position, &self.options) {
+                    Ok(Some(tokens)) => parse_value_with(tokens, &self.options),
+                    Ok(None) => return None,
+                    Err(error) => Err(error)
+                }
+            }
+        };
+        self.current_character = self.contents_chars.get(self.current_position.index as usize).copied();
+
+        match value {
+            Ok(_) => self.byte_offset = self.byte_offset_at(self.current_position.index as usize),
+            Err(_) => self.done = true
+        }
+
+        Some(value)
+    }
+
+    fn next_record(&mut self) -> Option<Result<ArrayType<'static>, Box<dyn Error>>> {
+        loop {
+            self.discard_parsed();
+
+            // Anything before the first record separator is skipped, as RFC 7464 asks
+            let separator = match self.find_separator() {
+                Ok(Some(separator)) => separator,
+                Ok(None) => {
+                    self.seek(self.contents_chars.len());
+                    return None;
+                }
+                Err(error) => return Some(Err(self.fail(error)))
+            };
+            self.seek(separator + 1);
+            self.discard_parsed();
+
+            let start = self.current_position.index as usize;
+            let end = match self.find_separator() {
+                Ok(Some(separator)) => separator,
+                Ok(None) => self.contents_chars.len(),
+                Err(error) => return Some(Err(self.fail(error)))
+            };
+
+            let mut value = match self.invalid_utf8(start, end) {
+                Some(error) => Err(error),
+                None => {
+                    // The tokenizer only sees the record, so that it can't read into the next one
+                    let record = &self.contents_chars[..end];
+                    self.current_character = record.get(start).copied();
+                    let tokens = tokenize_value(record, &mut self.current_character,
+                                                &mut self.current_position, &self.options);
+
+                    let mut value = match tokens {
+                        Ok(Some(tokens)) => parse_value_with(tokens, &self.options),
+                        Ok(None) => {
+                            self.seek(end);
+                            continue;  // An empty record
+                        }
+                        Err(error) => Err(error)
+                    };
+
+                    // One value per record
+                    if value.is_ok() {
+                        while let Some(' ' | '\t' | '\n' | '\r') = self.current_character {
+                            advance(record, &mut self.current_character, &mut self.current_position);
+                        }
+
+                        if self.current_character.is_some() {
+                            value = Err(Box::new(ParseError::new("Expected the end of the record".to_string(),
+                                                                 self.current_position.line,
+                                                                 self.current_position.column)));
+                        }
+                    }
+
+                    value
+                }
+            };
+
+            self.seek(end);
+            if value.is_ok() {
+                self.byte_offset = self.byte_offset_at(end);
+            }
+
+            return Some(value);
+        }
+    }
+
+    // The index of the next record separator from the current character, reading as
+    // much of the input as it takes. None at the end of the input.
+    fn find_separator(&mut self) -> io::Result<Option<usize>> {
+        let mut from = self.current_position.index as usize;
+
+        loop {
+            let found = self.contents_chars.get(from..).unwrap_or_default().iter()
+                .position(|character| *character == RECORD_SEPARATOR);
+
+            if let Some(length) = found {
+                return Ok(Some(from + length));
+            }
+
+            from = from.max(self.contents_chars.len());
+            if !self.read_more()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Reads the next chunk of the input, giving false at the end of it
+    fn read_more(&mut self) -> io::Result<bool> {
+        if self.at_end {
+            return Ok(false);
+        }
+
+        let bytes = loop {
+            match self.reader.fill_buf() {
+                Ok(bytes) => break bytes,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error)
+            }
+        };
+
+        if bytes.is_empty() {
+            self.at_end = true;
+
+            // The input ends in the middle of a character
+            if !self.pending.is_empty() {
+                self.push_invalid(self.pending.len());
+                self.pending.clear();
+            }
+        } else {
+            let length = bytes.len();
+            self.pending.extend_from_slice(bytes);
+            self.reader.consume(length);
+            self.decode_pending();
+        }
+
+        self.current_character = self.contents_chars.get(self.current_position.index as usize).copied();
+
+        Ok(!self.at_end)
+    }
+
+    // A character cut off by the end of a chunk stays pending until the next one
+    fn decode_pending(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        let mut start = 0;
+
+        while start < pending.len() {
+            let error = match std::str::from_utf8(&pending[start..]) {
+                Ok(valid) => {
+                    self.push_str(valid);
+                    start = pending.len();
+                    break;
+                }
+                Err(error) => error
+            };
+
+            let valid_length = error.valid_up_to();
+            self.push_str(std::str::from_utf8(&pending[start..start + valid_length]).expect("Checked by from_utf8"));
+            start += valid_length;
+
+            match error.error_len() {
+                Some(length) => {
+                    self.push_invalid(length);
+                    start += length;
+                }
+                None => break
+            }
+        }
+
+        self.pending = pending[start..].to_vec();
+    }
+
+    fn push_str(&mut self, valid: &str) {
+        for character in valid.chars() {
+            self.contents_chars.push(character);
+            self.byte_offsets.push(self.read_bytes);
+            self.read_bytes += character.len_utf8();
+        }
+    }
+
+    // Bytes that aren't valid UTF-8 take up one character, which fails the value it's in
+    fn push_invalid(&mut self, length: usize) {
+        self.invalid.push(self.contents_chars.len());
+        self.contents_chars.push(char::REPLACEMENT_CHARACTER);
+        self.byte_offsets.push(self.read_bytes);
+        self.read_bytes += length;
+    }
+
+    fn invalid_utf8(&self, start: usize, end: usize) -> Option<Box<dyn Error>> {
+        let index = *self.invalid.iter().find(|index| (start..end).contains(*index))?;
+
+        let mut position = self.current_position.clone();
+        let mut character = self.contents_chars.get(start).copied();
+        while (position.index as usize) < index {
+            advance(&self.contents_chars, &mut character, &mut position);
+        }
+
+        Some(Box::new(ParseError::new("Invalid UTF-8".to_string(), position.line, position.column)))
+    }
+
+    fn byte_offset_at(&self, index: usize) -> usize {
+        self.byte_offsets.get(index).copied().unwrap_or(self.read_bytes)
+    }
+
+    // Drops the characters before the current one, which have been parsed
+    fn discard_parsed(&mut self) {
+        let count = (self.current_position.index as usize).min(self.contents_chars.len());
+
+        self.contents_chars.drain(..count);
+        self.byte_offsets.drain(..count);
+        self.invalid.retain(|index| *index >= count);
+        self.invalid.iter_mut().for_each(|index| *index -= count);
+        self.current_position.index -= count as i32;
+    }
+
+    // Moves on to a character, the tokenizer may have stopped at the end of a record
+    fn seek(&mut self, index: usize) {
+        while (self.current_position.index as usize) < index {
+            advance(&self.contents_chars, &mut self.current_character, &mut self.current_position);
+        }
+        self.current_character = self.contents_chars.get(index).copied();
+    }
+
+    fn fail(&mut self, error: io::Error) -> Box<dyn Error> {
+        self.done = true;
+        Box::new(error)
+    }
+}
+
+impl<R> JsonStream<R> {
+    // The byte offset right after the last value read, or after its record in a sequence
+    pub fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+}
+
+impl<R: BufRead> Iterator for JsonStream<R> {
+    type Item = Result<ArrayType<'static>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.sequence {
+            self.next_record()
+        } else {
+            self.next_value()
+        }
+    }
+}
+
+// Finds where the next value of a concatenated stream ends, so that it's tokenized
+// once it has been read in full. Strings and comments are followed, so that brackets
+// in them don't count. A scalar ends at the first character that can't be part of
+// it, which may only come with the next read.
+struct Frame {
+    index: usize,  // Of the next character to look at
+    depth: usize,
+    state: FrameState
+}
+
+#[derive(Clone, Copy)]
+enum FrameState {
+    Between,  // Tokens, whitespace or nothing yet
+    String { escaped: bool },
+    LineComment,
+    BlockComment { star: bool },
+    Scalar
+}
+
+impl Frame {
+    // The index right after the value, or None if more of it has to be read
+    fn find_end(&mut self, contents_chars: &[char], comments: bool) -> Option<usize> {
+        while let Some(&character) = contents_chars.get(self.index) {
+            let mut after = self.index + 1;
+
+            match self.state {
+                FrameState::String { escaped: true } => self.state = FrameState::String { escaped: false },
+                FrameState::String { escaped: false } => match character {
+                    '\\' => self.state = FrameState::String { escaped: true },
+                    '"' => {
+                        self.state = FrameState::Between;
+                        if self.depth == 0 {
+                            return Some(after);
+                        }
+                    }
+                    _ => ()
+                },
+                FrameState::LineComment => {
+                    if character == '\n' {
+                        self.state = FrameState::Between;
+                    }
+                }
+                FrameState::BlockComment { star } => {
+                    self.state = match character {
+                        '/' if star => FrameState::Between,
+                        character => FrameState::BlockComment { star: character == '*' }
+                    };
+                }
+                FrameState::Scalar => {
+                    if ends_scalar(character) {
+                        return Some(self.index);
+                    }
+                }
+                FrameState::Between => match character {
+                    '"' => self.state = FrameState::String { escaped: false },
+                    '/' if comments => match contents_chars.get(self.index + 1) {
+                        Some('/') => {
+                            self.state = FrameState::LineComment;
+                            after += 1;
+                        }
+                        Some('*') => {
+                            self.state = FrameState::BlockComment { star: false };
+                            after += 1;
+                        }
+                        Some(_) if self.depth == 0 => return Some(after),  // The tokenizer reports it
+                        Some(_) => (),
+                        None => return None
+                    },
+                    '{' | '[' => self.depth += 1,
+                    '}' | ']' if self.depth > 0 => {
+                        self.depth -= 1;
+                        if self.depth == 0 {
+                            return Some(after);
+                        }
+                    }
+                    ' ' | '\t' | '\n' | '\r' => (),
+                    _ if self.depth > 0 => (),
+                    '}' | ']' | ':' | ',' => return Some(after),  // A lone token, the parser reports it
+                    _ => self.state = FrameState::Scalar
+                }
+            }
+
+            self.index = after;
+        }
+
+        None
+    }
+}
+
+fn ends_scalar(character: char) -> bool {
+    matches!(character, ' ' | '\t' | '\n' | '\r' | '{' | '}' | '[' | ']' | ',' | ':' | '"' | '/' | RECORD_SEPARATOR)
+}
@@ -1,82 +1,208 @@
 use std::error::Error;
 use std::fmt;
 
-pub (crate) fn tokenize(contents: String) -> Result<Vec<Token>, Box<dyn Error>> {
-    let mut tokens: Vec<Token> = Vec::new();
+use crate::options::{ParseOptions, CommentMode};
+use crate::diagnostic::Diagnostic;
+
+type Tokens = Vec<(Token, Position)>;
+
+pub (crate) fn tokenize(contents: String) -> Result<Vec<(Token, Position)>, Box<dyn Error>> {
+    tokenize_with(contents, &ParseOptions::default())
+}
+
+pub (crate) fn tokenize_with(contents: String,
+                             options: &ParseOptions) -> Result<Vec<(Token, Position)>, Box<dyn Error>> {
+    tokenize_recovering(contents, options, None)
+}
+
+// With diagnostics given, errors are collected there instead of returned. The
+// characters of a token that couldn't be read become a Token::Invalid.
+pub (crate) fn tokenize_recovering(contents: String, options: &ParseOptions,
+                                   mut diagnostics: Option<&mut Vec<Diagnostic>>)
+                                   -> Result<Vec<(Token, Position)>, Box<dyn Error>> {
+    let mut tokens: Vec<(Token, Position)> = Vec::new();
 
     let mut current_character: Option<char> = None;
     let mut current_position = Position { index: -1, line: 1, column: -1 };
 
-    let contents_chars = contents.chars().collect();
+    let contents_chars: Vec<char> = contents.chars().collect();
 
     advance(&contents_chars, &mut current_character, &mut current_position);
 
-    while let Some(character) = current_character {
-        match character {
-            ' ' | '\t' |
-            '\n' | '\r' => (),
-
-            '{' => tokens.push(Token::LeftBrace),
-            '}' => tokens.push(Token::RightBrace),
-            '[' => tokens.push(Token::LeftBracket),
-            ']' => tokens.push(Token::RightBracket),
-            ':' => tokens.push(Token::Colon),
-            ',' => tokens.push(Token::Comma),
-
-            '"' => {
-                let string = build_string(&contents_chars, &mut current_character,
-                                          &mut current_position);
-                match string {
-                    Ok(value) => tokens.push(Token::String(value)),
-                    Err(error) => return Err(error)
-                }
+    loop {
+        while let Some(' ' | '\t' | '\n' | '\r') = current_character {
+            advance(&contents_chars, &mut current_character, &mut current_position);
+        }
+
+        if current_character.is_none() {
+            break;
+        }
+
+        let start_position = current_position.clone();
+        let token = read_token(&contents_chars, &mut current_character, &mut current_position, options);
+
+        match (token, &mut diagnostics) {
+            (Ok(Some(token)), _) => tokens.push((token, start_position)),
+            (Ok(None), _) => (),  // A skipped comment
+            (Err(error), Some(diagnostics)) => {
+                diagnostics.push(Diagnostic::new(error));
+                skip_invalid(&contents_chars, &mut current_character, &mut current_position,
+                             &start_position);
+                tokens.push((Token::Invalid, start_position));
             }
+            (Err(error), None) => return Err(error)
+        }
+    }
 
-            '0' | '1' |
-            '2' | '3' |
-            '4' | '5' |
-            '6' | '7' |
-            '8' | '9' => {
-                let number = build_number(&contents_chars, &mut current_character,
-                                          &mut current_position);
-                match number {
-                    Ok(value) => tokens.push(Token::Number(value)),
-                    Err(error) => return Err(error)
-                }
+    tokens.push((Token::Eof, current_position));
+
+    Ok(tokens)
+}
+
+// Reads the tokens of a single value, leaving the current character right after it,
+// for streams of values. Gives None when only whitespace and comments are left.
+pub (crate) fn tokenize_value(contents_chars: &[char], current_character: &mut Option<char>,
+                              current_position: &mut Position, options: &ParseOptions)
+                              -> Result<Option<Tokens>, Box<dyn Error>> {
+    let mut tokens: Vec<(Token, Position)> = Vec::new();
+    let mut depth = 0;
 
-                // This is so that the character after the last number
-                // character is evaluated (to not call advance())
-                continue;
+    loop {
+        while let Some(' ' | '\t' | '\n' | '\r') = current_character {
+            advance(contents_chars, current_character, current_position);
+        }
+
+        if current_character.is_none() {
+            if tokens.iter().all(|(token, _)| matches!(token, Token::Comment(_))) {
+                return Ok(None);
             }
+            break;  // The parser reports the unfinished value
+        }
 
-            't' | 'f' | 'n' => {
-                let keyword = build_keyword(&contents_chars, &mut current_character,
-                                            &mut current_position);
-                match keyword {
-                    Ok(value) => tokens.push(Token::Keyword(value)),
-                    Err(error) => return Err(error)
-                }
+        let start_position = current_position.clone();
+        let token = match read_token(contents_chars, current_character, current_position, options)? {
+            Some(token) => token,
+            None => continue
+        };
+
+        match token {
+            Token::LeftBrace | Token::LeftBracket => depth += 1,
+            Token::RightBrace | Token::RightBracket if depth > 0 => depth -= 1,
+            _ => ()
+        }
+        let is_comment = matches!(token, Token::Comment(_));
+        tokens.push((token, start_position));
+
+        if depth == 0 && !is_comment {
+            break;
+        }
+    }
+
+    tokens.push((Token::Eof, current_position.clone()));
 
-                // This is so that the character after the last keyword
-                // character is evaluated (to not call advance())
-                continue;
+    Ok(Some(tokens))
+}
+
+// Reads the token starting at the current character, leaving the current
+// character right after it. Skipped comments give None.
+fn read_token(contents_chars: &[char], current_character: &mut Option<char>, current_position: &mut Position,
+              options: &ParseOptions) -> Result<Option<Token>, Box<dyn Error>> {
+    let start_position = current_position.clone();
+    let character = current_character.expect("Not at the end of the contents");
+
+    let token = match character {
+        '{' => Token::LeftBrace,
+        '}' => Token::RightBrace,
+        '[' => Token::LeftBracket,
+        ']' => Token::RightBracket,
+        ':' => Token::Colon,
+        ',' => Token::Comma,
+
+        '"' => {
+            let string = build_string(contents_chars, current_character, current_position, options)?;
+            check_length(&string, options.max_string_length, "String", &start_position)?;
+            Token::String(string)
+        }
+
+        'N' | 'I' |
+        '-' if options.non_finite_numbers && starts_non_finite(contents_chars, current_position) => {
+            let number = build_non_finite(contents_chars, current_character, current_position)?;
+            return Ok(Some(Token::Number(number)));
+        }
+
+        '-' |
+        '0' | '1' |
+        '2' | '3' |
+        '4' | '5' |
+        '6' | '7' |
+        '8' | '9' => {
+            let number = build_number(contents_chars, current_character, current_position, options)?;
+            check_length(&number, options.max_number_length, "Number", &start_position)?;
+
+            // The current character is already the one after the number
+            return Ok(Some(Token::Number(number)));
+        }
+
+        't' | 'f' | 'n' => {
+            let keyword = build_keyword(contents_chars, current_character, current_position)?;
+
+            // The current character is already the one after the keyword
+            return Ok(Some(Token::Keyword(keyword)));
+        }
+
+        '/' if options.comments != CommentMode::Error => {
+            let comment = build_comment(contents_chars, current_character, current_position)?;
+
+            // Line comments stop at the line break, block comments on their last character
+            if comment.starts_with("/*") {
+                advance(contents_chars, current_character, current_position);
             }
 
-            _ => return Err(Box::new(
-                    ParseError::new(format!("Unidentified character: {}", character),
-                                    current_position.line,
-                                    current_position.column))
-                 )
+            return match options.comments {
+                CommentMode::Keep => Ok(Some(Token::Comment(comment))),
+                _ => Ok(None)
+            };
         }
-        advance(&contents_chars, &mut current_character, &mut current_position);
+
+        _ => return Err(Box::new(
+                ParseError::new(format!("Unidentified character: {}", character),
+                                current_position.line,
+                                current_position.column))
+             )
+    };
+    advance(contents_chars, current_character, current_position);
+
+    Ok(Some(token))
+}
+
+// Skips the rest of a token that couldn't be read: up to the closing quote or the
+// end of the line for strings, up to whitespace or punctuation for anything else
+fn skip_invalid(contents_chars: &[char], current_character: &mut Option<char>,
+                current_position: &mut Position, start_position: &Position) {
+    let in_string = contents_chars[start_position.index as usize] == '"';
+
+    if current_position.index == start_position.index {
+        advance(contents_chars, current_character, current_position);
     }
 
-    tokens.push(Token::Eof);
+    while let Some(character) = *current_character {
+        if in_string {
+            if character == '"' {
+                advance(contents_chars, current_character, current_position);
+                break;
+            }
+            if character == '\n' || character == '\r' {
+                break;
+            }
+        } else if character.is_whitespace() || "{}[]:,\"".contains(character) {
+            break;
+        }
 
-    Ok(tokens)
+        advance(contents_chars, current_character, current_position);
+    }
 }
 
-fn advance(contents_chars: &Vec<char>, current_character: &mut Option<char>,
+pub (crate) fn advance(contents_chars: &[char], current_character: &mut Option<char>,
            current_position: &mut Position) {
     current_position.advance(current_character);
 
@@ -87,8 +213,8 @@ fn advance(contents_chars: &Vec<char>, current_character: &mut Option<char>,
     }
 }
 
-fn build_string(contents_chars: &Vec<char>, current_character: &mut Option<char>,
-                current_position: &mut Position) -> Result<String, Box<dyn Error>> {
+fn build_string(contents_chars: &[char], current_character: &mut Option<char>,
+                current_position: &mut Position, options: &ParseOptions) -> Result<String, Box<dyn Error>> {
     let mut string = String::new();
 
     let mut last_character = '\0';
@@ -105,6 +231,14 @@ fn build_string(contents_chars: &Vec<char>, current_character: &mut Option<char>
                     'n' => string.push('\n'),
                     'r' => string.push('\r'),
                     't' => string.push('\t'),
+                    '/' => string.push('/'),
+                    'b' => string.push('\u{8}'),
+                    'f' => string.push('\u{c}'),
+                    'u' => {
+                        let character = build_unicode_escape(contents_chars, current_character,
+                                                             current_position)?;
+                        string.push(character);
+                    }
                     _ => return Err(Box::new(
                         InvalidStringError::new("Unknown escape character in string",
                                                 current_position.line,
@@ -116,11 +250,16 @@ fn build_string(contents_chars: &Vec<char>, current_character: &mut Option<char>
                 match *character {
                     '"' => break,
                     '\\' => check_escape_character = true,
-                    '\n' => return Err(Box::new(
+                    '\n' if !options.control_characters => return Err(Box::new(
                         InvalidStringError::new("Unexpected end of string",
                                                 current_position.line,
                                                 current_position.column)
                     )),
+                    character if (character as u32) < 0x20 && !options.control_characters => {
+                        return Err(Box::new(InvalidStringError::new("Control character in string",
+                                                                    current_position.line,
+                                                                    current_position.column)))
+                    }
                     _ => string.push(*character)
                 }
             }
@@ -134,14 +273,15 @@ fn build_string(contents_chars: &Vec<char>, current_character: &mut Option<char>
     Ok(string)
 }
 
-fn build_number(contents_chars: &Vec<char>, current_character: &mut Option<char>,
-                current_position: &mut Position) -> Result<String, Box<dyn Error>> {
+fn build_number(contents_chars: &[char], current_character: &mut Option<char>,
+                current_position: &mut Position, options: &ParseOptions) -> Result<String, Box<dyn Error>> {
     let mut number = String::new();
     let mut is_floating_point = false;
+    let mut is_exponent = false;
 
-    let mut last_character = '\0';
+    let mut last_character = current_character.unwrap();
 
-    number.push(current_character.unwrap());
+    number.push(last_character);
 
     loop {
         advance(contents_chars, current_character, current_position);
@@ -155,7 +295,7 @@ fn build_number(contents_chars: &Vec<char>, current_character: &mut Option<char>
                 '8' | '9' => number.push(*character),
 
                 '.' => {
-                    if !is_floating_point {
+                    if !is_floating_point && !is_exponent && last_character != '-' {
                         number.push(*character);
                         is_floating_point = true;
                     } else {
@@ -167,28 +307,95 @@ fn build_number(contents_chars: &Vec<char>, current_character: &mut Option<char>
                     }
                 }
 
-                _ => {
-                    if last_character == '.' {
+                'e' | 'E' => {
+                    if !is_exponent && last_character.is_ascii_digit() {
+                        number.push(*character);
+                        is_exponent = true;
+                    } else {
                         return Err(Box::new(
                             ParseError::new("Invalid number format".to_string(),
                                             current_position.line,
                                             current_position.column))
                         );
                     }
-                    break;
                 }
+
+                '+' | '-' if last_character == 'e' || last_character == 'E' => {
+                    number.push(*character)
+                }
+
+                _ => break
             }
             last_character = *character;
         } else {  // character is None
-            return Err(Box::new(EofError::new("Reached EOF", current_position.line,
-                                              current_position.column)));
+            break;
         }
     }
 
+    // A number must end in a digit, not in a sign, dot or exponent
+    if !last_character.is_ascii_digit() {
+        return Err(Box::new(
+            ParseError::new("Invalid number format".to_string(),
+                            current_position.line,
+                            current_position.column))
+        );
+    }
+
+    let digits = number.trim_start_matches('-').as_bytes();
+    if !options.leading_zeros && digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit() {
+        return Err(Box::new(
+            ParseError::new("Leading zeros in number".to_string(),
+                            current_position.line,
+                            current_position.column))
+        );
+    }
+
     Ok(number)
 }
 
-fn build_keyword(contents_chars: &Vec<char>, current_character: &mut Option<char>,
+fn starts_non_finite(contents_chars: &[char], current_position: &Position) -> bool {
+    let index = current_position.index as usize;
+
+    match contents_chars[index] {
+        '-' => contents_chars.get(index + 1) == Some(&'I'),
+        _ => true
+    }
+}
+
+// Reads NaN, Infinity or -Infinity
+fn build_non_finite(contents_chars: &[char], current_character: &mut Option<char>,
+                    current_position: &mut Position) -> Result<String, Box<dyn Error>> {
+    let mut number = String::new();
+
+    while let Some(character) = current_character.filter(|character| {
+        character.is_ascii_alphabetic() || *character == '-' && number.is_empty()
+    }) {
+        number.push(character);
+        advance(contents_chars, current_character, current_position);
+    }
+
+    if !(number == "NaN" || number == "Infinity" || number == "-Infinity") {
+        return Err(Box::new(ParseError::new("Invalid keyword".to_string(),
+                                            current_position.line,
+                                            current_position.column)));
+    }
+
+    Ok(number)
+}
+
+fn check_length(value: &str, max_length: Option<usize>, kind: &str,
+                position: &Position) -> Result<(), Box<dyn Error>> {
+    match max_length {
+        Some(max_length) if value.chars().count() > max_length => {
+            Err(Box::new(ParseError::new(format!("{} longer than {} characters", kind, max_length),
+                                         position.line,
+                                         position.column)))
+        }
+        _ => Ok(())
+    }
+}
+
+fn build_keyword(contents_chars: &[char], current_character: &mut Option<char>,
                  current_position: &mut Position) -> Result<String, Box<dyn Error>> {
     let mut keyword = String::new();
 
@@ -202,25 +409,131 @@ fn build_keyword(contents_chars: &Vec<char>, current_character: &mut Option<char
                 'r' | 'u' | 'e' |
                 'a' | 'l' | 's' => keyword.push(*character),
 
-                _ => {
-                    if !(keyword == "true" || keyword == "false" || keyword == "null") {
-                        return Err(Box::new(ParseError::new("Invalid keyword".to_string(),
-                                                            current_position.line,
-                                                            current_position.column)));
-                    }
-                    break;
-                }
+                _ => break
             }
         } else {  // character is None
-            return Err(Box::new(EofError::new("Reached EOF", current_position.line,
-                                              current_position.column)));
+            break;
         }
     }
 
+    if !(keyword == "true" || keyword == "false" || keyword == "null") {
+        return Err(Box::new(ParseError::new("Invalid keyword".to_string(),
+                                            current_position.line,
+                                            current_position.column)));
+    }
+
     Ok(keyword)
 }
 
-#[derive(Debug)]
+// Reads a line comment up to the end of the line, or a block comment up to and
+// including "*/". The comment is returned with its delimiters.
+pub (crate) fn build_comment(contents_chars: &[char], current_character: &mut Option<char>,
+                 current_position: &mut Position) -> Result<String, Box<dyn Error>> {
+    let mut comment = String::from("/");
+
+    advance(contents_chars, current_character, current_position);
+
+    match current_character {
+        Some('/') => {
+            comment.push('/');
+
+            loop {
+                advance(contents_chars, current_character, current_position);
+
+                match current_character {
+                    Some('\n') | Some('\r') | None => break,
+                    Some(character) => comment.push(*character)
+                }
+            }
+
+            // Line comments end at the line break, not on it
+            comment.truncate(comment.trim_end().len());
+        }
+        Some('*') => {
+            comment.push('*');
+            let mut last_character = '\0';
+
+            loop {
+                advance(contents_chars, current_character, current_position);
+
+                match current_character {
+                    Some(character) => {
+                        comment.push(*character);
+                        if last_character == '*' && *character == '/' {
+                            break;
+                        }
+                        last_character = *character;
+                    }
+                    None => return Err(Box::new(EofError::new("Unterminated block comment",
+                                                              current_position.line,
+                                                              current_position.column)))
+                }
+            }
+        }
+        _ => return Err(Box::new(ParseError::new("Unidentified character: /".to_string(),
+                                                 current_position.line,
+                                                 current_position.column)))
+    }
+
+    Ok(comment)
+}
+
+// Reads the four hex digits after \u, combining surrogate pairs into one character
+pub (crate) fn build_unicode_escape(contents_chars: &[char], current_character: &mut Option<char>,
+                        current_position: &mut Position) -> Result<char, Box<dyn Error>> {
+    let high = read_hex_digits(contents_chars, current_character, current_position)?;
+
+    let code = if (0xD800..0xDC00).contains(&high) {
+        advance(contents_chars, current_character, current_position);
+        let backslash = *current_character;
+        advance(contents_chars, current_character, current_position);
+
+        if backslash != Some('\\') || *current_character != Some('u') {
+            return Err(Box::new(InvalidStringError::new("Unpaired surrogate in unicode escape",
+                                                        current_position.line,
+                                                        current_position.column)));
+        }
+
+        let low = read_hex_digits(contents_chars, current_character, current_position)?;
+
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(Box::new(InvalidStringError::new("Unpaired surrogate in unicode escape",
+                                                        current_position.line,
+                                                        current_position.column)));
+        }
+
+        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+    } else {
+        high
+    };
+
+    match std::char::from_u32(code) {
+        Some(character) => Ok(character),
+        None => Err(Box::new(InvalidStringError::new("Invalid unicode escape",
+                                                     current_position.line,
+                                                     current_position.column)))
+    }
+}
+
+pub (crate) fn read_hex_digits(contents_chars: &[char], current_character: &mut Option<char>,
+                   current_position: &mut Position) -> Result<u32, Box<dyn Error>> {
+    let mut code = 0;
+
+    for _ in 0..4 {
+        advance(contents_chars, current_character, current_position);
+
+        match current_character.and_then(|character| character.to_digit(16)) {
+            Some(digit) => code = code * 16 + digit,
+            None => return Err(Box::new(InvalidStringError::new("Invalid unicode escape",
+                                                                current_position.line,
+                                                                current_position.column)))
+        }
+    }
+
+    Ok(code)
+}
+
+#[derive(Debug, Clone)]
 pub (crate) enum Token {
     LeftBrace,
     RightBrace,
@@ -232,6 +545,9 @@ pub (crate) enum Token {
     String(String),
     Number(String),  // Integer or float
     Keyword(String),  // Boolean or null
+    Comment(String),  // Only produced when comments are kept
+    Identifier(String),  // Unquoted JSON5 key
+    Invalid,  // Characters that couldn't be read, only produced when recovering from errors
 }
 
 #[derive(Debug)]
@@ -242,11 +558,11 @@ pub struct ParseError {
 }
 
 impl ParseError {
-    fn new(message: String, line: i32, column: i32) -> Self {
+    pub (crate) fn new(message: String, line: i32, column: i32) -> Self {
         Self {
-            message: message,
-            line: line,
-            column: column
+            message,
+            line,
+            column
         }
     }
 }
@@ -268,11 +584,11 @@ pub struct InvalidStringError {
 }
 
 impl InvalidStringError {
-    fn new(message: &'static str, line: i32, column: i32) -> Self {
+    pub (crate) fn new(message: &'static str, line: i32, column: i32) -> Self {
         Self {
-            message: message,
-            line: line,
-            column: column
+            message,
+            line,
+            column
         }
     }
 }
@@ -294,11 +610,11 @@ pub struct EofError {
 }
 
 impl EofError {
-    fn new(message: &'static str, line: i32, column: i32) -> Self {
+    pub (crate) fn new(message: &'static str, line: i32, column: i32) -> Self {
         Self {
-            message: message,
-            line: line,
-            column: column
+            message,
+            line,
+            column
         }
     }
 }
@@ -312,11 +628,93 @@ impl fmt::Display for EofError {
 
 impl Error for EofError {}
 
+// An object key that was already used in the same object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError {
+    key: String,
+    first: (i32, i32),
+    second: (i32, i32)
+}
+
+impl DuplicateKeyError {
+    pub (crate) fn new(key: String, first: &Position, second: &Position) -> Self {
+        Self {
+            key,
+            first: (first.line, first.column),
+            second: (second.line, second.column)
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // Line and column of the first occurrence
+    pub fn first(&self) -> (i32, i32) {
+        self.first
+    }
+
+    // Line and column of the repeated occurrence
+    pub fn second(&self) -> (i32, i32) {
+        self.second
+    }
+}
+
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "DuplicateKeyError: \"{}\" is already used at line {}, column {}\nLine: {}, column: {}",
+               self.key, self.first.0, self.first.1, self.second.0, self.second.1)
+    }
+}
+
+impl Error for DuplicateKeyError {}
+
+// The line and column of any error the tokenizer or the parser returns
+pub (crate) fn error_position(error: &(dyn Error + 'static)) -> (i32, i32) {
+    if let Some(error) = error.downcast_ref::<ParseError>() {
+        (error.line, error.column)
+    } else if let Some(error) = error.downcast_ref::<InvalidStringError>() {
+        (error.line, error.column)
+    } else if let Some(error) = error.downcast_ref::<EofError>() {
+        (error.line, error.column)
+    } else if let Some(error) = error.downcast_ref::<DuplicateKeyError>() {
+        error.second
+    } else {
+        unreachable!("Every parser error has a position")
+    }
+}
+
+// Moves the position of an error down by a number of lines, for documents that
+// were parsed on their own but are part of a larger text, like NDJSON lines
+pub (crate) fn offset_error_line(error: Box<dyn Error>, lines: i32) -> Box<dyn Error> {
+    let error = match error.downcast::<ParseError>() {
+        Ok(mut error) => { error.line += lines; return error; },
+        Err(error) => error
+    };
+    let error = match error.downcast::<InvalidStringError>() {
+        Ok(mut error) => { error.line += lines; return error; },
+        Err(error) => error
+    };
+    let error = match error.downcast::<EofError>() {
+        Ok(mut error) => { error.line += lines; return error; },
+        Err(error) => error
+    };
+
+    match error.downcast::<DuplicateKeyError>() {
+        Ok(mut error) => {
+            error.first.0 += lines;
+            error.second.0 += lines;
+            error
+        },
+        Err(error) => error
+    }
+}
+
 #[derive(Debug, Clone)]
-struct Position {
-    index: i32,  // Character index in JSON file
-    line: i32,
-    column: i32
+pub (crate) struct Position {
+    pub (crate) index: i32,  // Character index in JSON file
+    pub (crate) line: i32,
+    pub (crate) column: i32
 }
 
 impl Position {
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, BTreeMap};
+use std::convert::TryFrom;
+
+use crate::data_structure::{JsonObject, JsonArray, ArrayType, ArrayTypeRef, Null};
+use crate::conversion::ConversionError;
+
+// Lightweight alternative to serde for mapping Rust types to JSON values.
+// Both traits can be derived with the "derive" feature.
+
+pub trait ToJson {
+    fn to_json(&self) -> ArrayType<'static>;
+}
+
+pub trait FromJson: Sized {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError>;
+
+    // Called for struct fields that are missing from the object
+    fn from_missing(field: &str) -> Result<Self, ConversionError> {
+        Err(ConversionError::MissingField { field: field.to_string() })
+    }
+
+    fn from_object(object: &JsonObject) -> Result<Self, ConversionError> {
+        Self::from_json(ArrayTypeRef::Object(object))
+    }
+
+    fn from_array(array: &JsonArray) -> Result<Self, ConversionError> {
+        Self::from_json(ArrayTypeRef::Array(array))
+    }
+}
+
+fn wrong_type(expected: &'static str, found: ArrayTypeRef) -> ConversionError {
+    ConversionError::WrongType {
+        expected,
+        found: found.type_name()
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Bool(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::Bool(value) => Ok(value),
+            other => Err(wrong_type("bool", other))
+        }
+    }
+}
+
+// Ints that don't fit into a JSON int are written as decimal strings, so that they
+// read back exactly, where a float would lose digits
+macro_rules! int_to_json {
+    ($($int:ty),*) => {
+        $(
+            impl ToJson for $int {
+                fn to_json(&self) -> ArrayType<'static> {
+                    ArrayType::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+int_to_json!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! int_from_json {
+    ($($int:ty),*) => {
+        $(
+            impl FromJson for $int {
+                fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+                    <$int>::try_from(ArrayType::from(value))
+                }
+            }
+        )*
+    };
+}
+
+int_from_json!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl ToJson for f32 {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Float(*self)
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        f32::try_from(ArrayType::from(value))
+    }
+}
+
+// Rounded to the nearest f32, and written as a string beyond the f32 range like the big ints
+impl ToJson for f64 {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::from(*self)
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        f64::try_from(ArrayType::from(value))
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::String(self.clone())
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::String(self.to_string())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::String(value) => Ok(value.clone()),
+            other => Err(wrong_type("string", other))
+        }
+    }
+}
+
+impl ToJson for Null {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Null(Null)
+    }
+}
+
+impl FromJson for Null {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::Null(value) => Ok(value),
+            other => Err(wrong_type("null", other))
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> ArrayType<'static> {
+        match self {
+            Some(value) => value.to_json(),
+            None => ArrayType::Null(Null)
+        }
+    }
+}
+
+// Optional fields may be null or missing altogether
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::Null(_) => Ok(None),
+            other => T::from_json(other).map(Some)
+        }
+    }
+
+    fn from_missing(_field: &str) -> Result<Self, ConversionError> {
+        Ok(None)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> ArrayType<'static> {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        T::from_json(value).map(Box::new)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> ArrayType<'static> {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> ArrayType<'static> {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::Array(array) => array.iter().map(T::from_json).collect(),
+            other => Err(wrong_type("array", other))
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for HashMap<String, T> {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Object(self.iter().map(|(key, value)| (key.clone(), value.to_json())).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::Object(object) => object.iter()
+                .map(|(key, value)| T::from_json(value).map(|value| (key.to_string(), value)))
+                .collect(),
+            other => Err(wrong_type("object", other))
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for BTreeMap<String, T> {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Object(self.iter().map(|(key, value)| (key.clone(), value.to_json())).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value {
+            ArrayTypeRef::Object(object) => object.iter()
+                .map(|(key, value)| T::from_json(value).map(|value| (key.to_string(), value)))
+                .collect(),
+            other => Err(wrong_type("object", other))
+        }
+    }
+}
+
+// The data structures themselves convert by cloning, with owned keys
+
+impl<'a> ToJson for JsonObject<'a> {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Object(self.iter()
+            .map(|(key, value)| (key.to_string(), ArrayTypeRef::to_json(&value)))
+            .collect())
+    }
+}
+
+impl FromJson for JsonObject<'static> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value.to_json() {
+            ArrayType::Object(object) => Ok(object),
+            other => Err(wrong_type("object", other.to_ref()))
+        }
+    }
+}
+
+impl<'a> ToJson for JsonArray<'a> {
+    fn to_json(&self) -> ArrayType<'static> {
+        ArrayType::Array(self.iter().map(|item| item.to_json()).collect())
+    }
+}
+
+impl FromJson for JsonArray<'static> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        match value.to_json() {
+            ArrayType::Array(array) => Ok(array),
+            other => Err(wrong_type("array", other.to_ref()))
+        }
+    }
+}
+
+impl<'a> ToJson for ArrayType<'a> {
+    fn to_json(&self) -> ArrayType<'static> {
+        self.to_ref().to_json()
+    }
+}
+
+impl FromJson for ArrayType<'static> {
+    fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+        Ok(value.to_json())
+    }
+}
+
+impl<'r, 'a> ToJson for ArrayTypeRef<'r, 'a> {
+    fn to_json(&self) -> ArrayType<'static> {
+        match self {
+            ArrayTypeRef::Int(value) => ArrayType::Int(*value),
+            ArrayTypeRef::Float(value) => ArrayType::Float(*value),
+            ArrayTypeRef::Bool(value) => ArrayType::Bool(*value),
+            ArrayTypeRef::String(value) => ArrayType::String((*value).clone()),
+            ArrayTypeRef::Array(value) => value.to_json(),
+            ArrayTypeRef::Object(value) => value.to_json(),
+            ArrayTypeRef::Null(value) => ArrayType::Null(*value)
+        }
+    }
+}
+
+macro_rules! tuple_traits {
+    ($length:expr => $($name:ident $index:tt),+) => {
+        impl<$($name: ToJson),+> ToJson for ($($name,)+) {
+            fn to_json(&self) -> ArrayType<'static> {
+                let mut array = JsonArray::new();
+                $(array.add(self.$index.to_json());)+
+                ArrayType::Array(array)
+            }
+        }
+
+        impl<$($name: FromJson),+> FromJson for ($($name,)+) {
+            fn from_json(value: ArrayTypeRef) -> Result<Self, ConversionError> {
+                let array = match value {
+                    ArrayTypeRef::Array(array) => array,
+                    other => return Err(wrong_type("array", other))
+                };
+
+                if array.len() != $length {
+                    return Err(ConversionError::WrongLength { expected: $length, found: array.len() });
+                }
+
+                let mut items = array.iter();
+                Ok(($($name::from_json(items.next().expect("Length was checked"))?,)+))
+            }
+        }
+    };
+}
+
+tuple_traits!(1 => A 0);
+tuple_traits!(2 => A 0, B 1);
+tuple_traits!(3 => A 0, B 1, C 2);
+tuple_traits!(4 => A 0, B 1, C 2, D 3);
+tuple_traits!(5 => A 0, B 1, C 2, D 3, E 4);
+tuple_traits!(6 => A 0, B 1, C 2, D 3, E 4, F 5);